@@ -0,0 +1,153 @@
+use compiler_tools::files::Filenames;
+use compiler_tools::lines::Offset;
+use compiler_tools::position::OffsetPosition;
+use compiler_tools::sources::Sources;
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::hint::black_box;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::process;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Default number of lines in the synthetic file.
+const DEFAULT_LINES: usize = 2_000_000;
+
+/// Number of times each benchmark is run.
+const RUNS: usize = 5;
+
+/// Number of positions looked up with `get_ctx` in each run.
+const LOOKUPS: usize = 100_000;
+
+/// Write a synthetic source file with `nlines` lines of varying
+/// length, and return its length in bytes.
+fn write_synthetic(path: &Path, nlines: usize) -> usize {
+    let file = File::create(path).expect("Expected success");
+    let mut out = BufWriter::new(file);
+    let mut len = 0;
+
+    for i in 0 .. nlines {
+        let line = format!("let value_{} = compute({}, \"{}\");\n",
+                           i, i % 977, "x".repeat(i % 61));
+
+        out.write_all(line.as_bytes()).expect("Expected success");
+        len += line.len();
+    }
+
+    out.flush().expect("Expected success");
+
+    len
+}
+
+/// Load `path` line by line with `push_line`.
+fn load_push_line<'a>(filenames: &mut Filenames<'a>, srcs: &mut Sources<'a>,
+                      path: &Path) {
+    let filename = filenames.filename(path).expect("Expected success");
+    let content = fs::read_to_string(path).expect("Expected success");
+    let nlines = content.lines().count();
+    let src = srcs.add_src_with_capacity(filename, nlines)
+        .expect("Expected some");
+    let mut start = 0;
+
+    for line in content.split_terminator('\n') {
+        let text = line.strip_suffix('\r').unwrap_or(line);
+
+        src.push_line(start, String::from(text));
+        start += line.len() + 1;
+    }
+}
+
+/// Look up `LOOKUPS` spans spread evenly across a file of `len` bytes.
+fn lookups(filenames: &mut Filenames<'_>, srcs: &Sources<'_>,
+           path: &Path, len: usize) {
+    let filename = filenames.filename(path).expect("Expected success");
+    let step = len / LOOKUPS;
+
+    for i in 0 .. LOOKUPS {
+        let pos = OffsetPosition::Span { start: Offset::from(i * step),
+                                         len: Offset::from(200) };
+
+        black_box(srcs.get_ctx(filename, &pos));
+    }
+}
+
+/// Run `f` `RUNS` times, and report the best time.
+fn bench<F>(name: &str, mut f: F)
+where F: FnMut() -> Duration {
+    let best = (0 .. RUNS).map(|_| f()).min().expect("Expected some");
+
+    println!("{:<24} {:>10.2} ms", name, best.as_secs_f64() * 1000.0);
+}
+
+fn main() {
+    let nlines = env::var("COMPILER_TOOLS_BENCH_LINES").ok()
+        .and_then(|val| val.parse().ok())
+        .unwrap_or(DEFAULT_LINES);
+    let path = env::temp_dir().join(format!("compiler-tools-bench-{}.x",
+                                            process::id()));
+    let len = write_synthetic(&path, nlines);
+
+    println!("synthetic file: {} lines, {} bytes", nlines, len);
+
+    bench("load (push_line)", || {
+        let mut filenames = Filenames::new();
+        let mut srcs = Sources::new();
+        let now = Instant::now();
+
+        load_push_line(&mut filenames, &mut srcs, &path);
+
+        now.elapsed()
+    });
+    bench("load", || {
+        let mut filenames = Filenames::new();
+        let mut srcs = Sources::new();
+        let filename = filenames.filename(&path).expect("Expected success");
+        let now = Instant::now();
+
+        srcs.load(filename).expect("Expected success");
+
+        now.elapsed()
+    });
+    bench("load_mapped", || {
+        let mut filenames = Filenames::new();
+        let mut srcs = Sources::new();
+        let filename = filenames.filename(&path).expect("Expected success");
+        let now = Instant::now();
+
+        // SAFETY: the benchmark file is not modified while mapped.
+        unsafe { srcs.load_mapped(filename) }.expect("Expected success");
+
+        now.elapsed()
+    });
+
+    let mut filenames = Filenames::new();
+    let mut srcs = Sources::new();
+
+    load_push_line(&mut filenames, &mut srcs, &path);
+    bench("get_ctx (push_line)", || {
+        let now = Instant::now();
+
+        lookups(&mut filenames, &srcs, &path, len);
+
+        now.elapsed()
+    });
+
+    let mut filenames = Filenames::new();
+    let mut srcs = Sources::new();
+    let filename = filenames.filename(&path).expect("Expected success");
+
+    // SAFETY: the benchmark file is not modified while mapped.
+    unsafe { srcs.load_mapped(filename) }.expect("Expected success");
+    bench("get_ctx (load_mapped)", || {
+        let now = Instant::now();
+
+        lookups(&mut filenames, &srcs, &path, len);
+
+        now.elapsed()
+    });
+
+    fs::remove_file(&path).expect("Expected success");
+}
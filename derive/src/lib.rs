@@ -0,0 +1,571 @@
+//! Derive macros for `compiler-tools`.
+//!
+//! See [`macro@Diagnostic`] and [`macro@Message`].
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::format_ident;
+use quote::quote;
+use syn::Attribute;
+use syn::Data;
+use syn::DeriveInput;
+use syn::Error;
+use syn::Fields;
+use syn::GenericArgument;
+use syn::GenericParam;
+use syn::Ident;
+use syn::LitStr;
+use syn::PathArguments;
+use syn::Token;
+use syn::Type;
+use syn::parse::ParseStream;
+use syn::parse_macro_input;
+use syn::parse_quote;
+
+/// Derive conversions from an error type into `Diagnostic`.
+///
+/// Types deriving `Diagnostic` can be converted into a
+/// `compiler_tools::diagnostic::Diagnostic` with `From`, which
+/// implements `Message` and `MessagePositions`.  To implement those
+/// on the type itself, derive [`macro@Message`] instead.
+///
+/// Each variant of an enum (or the struct itself) is described with a
+/// `#[message(...)]` attribute, with these keys:
+///
+/// - `severity`: one of `info`, `remark`, `lint`, `warning`, `error`,
+///   or `internal` (required).
+/// - `brief`: the brief description (required).
+/// - `code`: the code identifying the kind of message.
+/// - `key`: the key identifying the message in a catalog.  The other
+///   named fields are added as arguments, and must implement
+///   `Display`.
+/// - `highlighting`: either `foreground` or `background`.
+///
+/// A `#[message(...)]` attribute on an enum gives defaults for its
+/// variants.  `#[note("...")]` and `#[help("...")]` attributes add
+/// lines to the detailed description.
+///
+/// Fields marked with `#[label("...")]` are positions with a label,
+/// and those marked with `#[position]` are positions without one.
+/// Positions have the severity of the message, unless given with
+/// `#[label("...", severity = "info")]`.  All positions must have the
+/// same type.
+///
+/// The brief description, notes, and labels are format strings, which
+/// can refer to fields by name (or by index, for tuple fields), as in
+/// `brief = "undefined variable `{name}`"`.
+///
+/// A field marked with `#[cache]` is ignored; see [`macro@Message`].
+#[proc_macro_derive(Diagnostic,
+                    attributes(message, label, position, note, help, cache))]
+pub fn derive_diagnostic(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand(&input, Mode::Owned) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into()
+    }
+}
+
+/// Derive `Message` and `MessagePositions` for an error type.
+///
+/// This takes the same attributes as [`macro@Diagnostic`].  As
+/// `Message` borrows the descriptions and positions from the message,
+/// each variant of an enum (or the struct itself) also needs a field
+/// of type `compiler_tools::diagnostic::DiagnosticCache<P>` marked
+/// with `#[cache]`, where `P` is the type of the positions.  The
+/// message is formatted into a `Diagnostic` in this field the first
+/// time it is used, which requires `P` to implement `Clone`.
+#[proc_macro_derive(Message,
+                    attributes(message, label, position, note, help, cache))]
+pub fn derive_message(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand(&input, Mode::Cached) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into()
+    }
+}
+
+/// How a message is turned into a `Diagnostic`.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Mode {
+    /// The message is moved into a new `Diagnostic`.
+    Owned,
+    /// The message is borrowed, and its `Diagnostic` is kept in its
+    /// `#[cache]` field.
+    Cached
+}
+
+/// Settings from a `#[message(...)]` attribute.
+#[derive(Clone, Default)]
+struct MessageAttr {
+    /// Severity of the message.
+    severity: Option<TokenStream2>,
+    /// Brief description.
+    brief: Option<LitStr>,
+    /// Code identifying the kind of message.
+    code: Option<LitStr>,
+    /// Key identifying the message in a catalog.
+    key: Option<LitStr>,
+    /// Highlighting style.
+    highlighting: Option<TokenStream2>
+}
+
+/// A position field.
+struct PositionField {
+    /// Expression for the field's binding.
+    binding: Ident,
+    /// Type of the field.
+    ty: Type,
+    /// The label, if any.
+    label: Option<LitStr>,
+    /// Severity, if it differs from the message's.
+    severity: Option<TokenStream2>
+}
+
+/// Generate the implementations for `input` in `mode`.
+fn expand(input: &DeriveInput, mode: Mode) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let defaults = message_attr(&input.attrs, MessageAttr::default())?;
+    let mut arms = Vec::new();
+    let mut position_ty: Option<Type> = None;
+
+    match &input.data {
+        Data::Enum(data) => {
+            for variant in &data.variants {
+                let ident = &variant.ident;
+                let path = quote! { #name::#ident };
+                let attr = message_attr(&variant.attrs, defaults.clone())?;
+
+                arms.push(expand_fields(&path, &variant.fields, &attr,
+                                        &variant.attrs, variant.ident.span(),
+                                        mode, &mut position_ty)?);
+            }
+        },
+        Data::Struct(data) => {
+            let path = quote! { #name };
+
+            arms.push(expand_fields(&path, &data.fields, &defaults,
+                                    &input.attrs, name.span(), mode,
+                                    &mut position_ty)?);
+        },
+        Data::Union(_) => {
+            return Err(Error::new(name.span(),
+                                  "messages cannot be derived for unions"))
+        }
+    }
+
+    if mode == Mode::Cached {
+        return Ok(expand_cached(input, &arms, position_ty));
+    }
+
+    let mut generics = input.generics.clone();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let position_ty = match position_ty {
+        Some(ty) => quote! { #ty },
+        None => {
+            let param = Ident::new("__P", Span::mixed_site());
+
+            generics.params.push(GenericParam::Type(param.clone().into()));
+
+            quote! { #param }
+        }
+    };
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::std::convert::From<#name #ty_generics> for
+            ::compiler_tools::diagnostic::Diagnostic<#position_ty>
+            #where_clause {
+            #[allow(unused_variables)]
+            fn from(msg: #name #ty_generics) -> Self {
+                match msg {
+                    #(#arms)*
+                }
+            }
+        }
+    })
+}
+
+/// Generate the `Message` and `MessagePositions` implementations for
+/// `input`, with the match `arms` getting its cached `Diagnostic`.
+fn expand_cached(input: &DeriveInput, arms: &[TokenStream2],
+                 position_ty: Option<Type>) -> TokenStream2 {
+    let name = &input.ident;
+    // Every variant has a cache, so there is always a position type.
+    let position_ty = position_ty.expect("Expected position type");
+    let mut generics = input.generics.clone();
+
+    generics.make_where_clause().predicates
+        .push(parse_quote! { #position_ty: ::std::clone::Clone });
+
+    let (impl_generics, ty_generics, where_clause) =
+        generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            #[doc(hidden)]
+            #[allow(unused_variables)]
+            fn __compiler_tools_diagnostic(&self) ->
+                &::compiler_tools::diagnostic::Diagnostic<#position_ty> {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+
+        impl #impl_generics ::compiler_tools::messages::Message for
+            #name #ty_generics #where_clause {
+            #[inline]
+            fn severity(&self) -> ::compiler_tools::messages::Severity {
+                self.__compiler_tools_diagnostic().severity()
+            }
+
+            #[inline]
+            fn brief(&self) -> &str {
+                self.__compiler_tools_diagnostic().brief()
+            }
+
+            #[inline]
+            fn detail(&self) -> &str {
+                self.__compiler_tools_diagnostic().detail()
+            }
+
+            #[inline]
+            fn highlighting(&self) ->
+                ::compiler_tools::messages::Highlighting {
+                self.__compiler_tools_diagnostic().highlighting()
+            }
+
+            #[inline]
+            fn code(&self) -> ::std::option::Option<&str> {
+                self.__compiler_tools_diagnostic().code()
+            }
+
+            #[inline]
+            fn key(&self) -> ::std::option::Option<&str> {
+                self.__compiler_tools_diagnostic().key()
+            }
+
+            #[inline]
+            fn arg(&self, name: &str) ->
+                ::std::option::Option<::std::string::String> {
+                self.__compiler_tools_diagnostic().arg(name)
+            }
+        }
+
+        impl #impl_generics
+            ::compiler_tools::messages::MessagePositions<#position_ty> for
+            #name #ty_generics #where_clause {
+            #[inline]
+            fn positions(&self) -> &[(::std::option::Option<&str>,
+                                      #position_ty,
+                                      ::compiler_tools::messages::Severity)] {
+                ::compiler_tools::messages::MessagePositions::positions(
+                    self.__compiler_tools_diagnostic()
+                )
+            }
+        }
+    }
+}
+
+/// Generate the match arm for a variant or struct with `fields`.
+fn expand_fields(path: &TokenStream2, fields: &Fields, attr: &MessageAttr,
+                 attrs: &[Attribute], span: Span, mode: Mode,
+                 position_ty: &mut Option<Type>) ->
+    Result<TokenStream2, Error> {
+    let severity = match &attr.severity {
+        Some(severity) => severity,
+        None => return Err(Error::new(span, "missing message severity"))
+    };
+    let brief = match &attr.brief {
+        Some(brief) => format_string(brief),
+        None => return Err(Error::new(span, "missing message brief"))
+    };
+    let mut bindings = Vec::new();
+    let mut positions = Vec::new();
+    let mut args = Vec::new();
+    let mut cache = None;
+
+    for (idx, field) in fields.iter().enumerate() {
+        let binding = match &field.ident {
+            Some(ident) => ident.clone(),
+            None => format_ident!("_{}", idx)
+        };
+
+        if cache_attr(&field.attrs)? {
+            if cache.is_some() {
+                return Err(Error::new_spanned(field,
+                                              "duplicate `#[cache]` field"));
+            }
+
+            unify_position(position_ty, &cache_position(&field.ty)?,
+                           &field.ty)?;
+            cache = Some(binding.clone());
+            bindings.push(binding);
+
+            continue;
+        }
+
+        let position = position_attr(&field.attrs, &binding, &field.ty)?;
+
+        match position {
+            Some(position) => {
+                unify_position(position_ty, &position.ty, &field.ty)?;
+                positions.push(position)
+            },
+            None if attr.key.is_some() && field.ident.is_some() => {
+                let name = LitStr::new(&binding.to_string(), binding.span());
+
+                args.push(quote! { .with_arg(#name, &#binding) })
+            },
+            None => ()
+        }
+
+        bindings.push(binding);
+    }
+
+    let pattern = match fields {
+        Fields::Named(_) => quote! { #path { #(#bindings),* } },
+        Fields::Unnamed(_) => quote! { #path ( #(#bindings),* ) },
+        Fields::Unit => quote! { #path }
+    };
+    // All text is formatted before any position is moved into the
+    // builder, so that it can refer to position fields.
+    let mut texts = Vec::new();
+    let mut text = |value: TokenStream2| {
+        let ident = Ident::new(&format!("__text_{}", texts.len()),
+                               Span::mixed_site());
+
+        texts.push(quote! { let #ident = #value; });
+
+        ident
+    };
+    let brief = text(brief);
+    let mut lines = Vec::new();
+
+    for attr in attrs {
+        for kind in &["note", "help"] {
+            if attr.path().is_ident(kind) {
+                let value = text(format_string(&attr.parse_args::<LitStr>()?));
+                let method = format_ident!("with_{}", kind);
+
+                lines.push(quote! { .#method(&#value) })
+            }
+        }
+    }
+
+    let labels: Vec<TokenStream2> = positions.iter().map(|position| {
+        let binding = &position.binding;
+        // Borrowed positions have to be cloned into the `Diagnostic`.
+        let binding = match mode {
+            Mode::Owned => quote! { #binding },
+            Mode::Cached => quote! { ::std::clone::Clone::clone(#binding) }
+        };
+        let label = position.label.as_ref()
+            .map(|label| text(format_string(label)));
+
+        match (label, &position.severity) {
+            (Some(label), Some(severity)) =>
+                quote! { .with_label_severity(#binding, &#label, #severity) },
+            (Some(label), None) => quote! { .with_label(#binding, &#label) },
+            (None, _) => quote! { .with_position(#binding) }
+        }
+    }).collect();
+    let code = attr.code.as_ref().map(|code| quote! { .with_code(#code) });
+    let key = attr.key.as_ref().map(|key| quote! { .with_key(#key) });
+    let highlighting = attr.highlighting.as_ref()
+        .map(|highlighting| quote! { .with_highlighting(#highlighting) });
+
+    let build = quote! {
+        #(#texts)*
+
+        ::compiler_tools::diagnostic::Diagnostic::new(#severity, &#brief)
+            #(#lines)* #code #key #highlighting #(#args)* #(#labels)*
+    };
+
+    match (mode, cache) {
+        (Mode::Owned, _) => Ok(quote! { #pattern => { #build }, }),
+        (Mode::Cached, Some(cache)) =>
+            Ok(quote! { #pattern => #cache.get_or_init(|| { #build }), }),
+        (Mode::Cached, None) =>
+            Err(Error::new(span, "missing `#[cache]` field"))
+    }
+}
+
+/// Parse the `#[message(...)]` attributes in `attrs`, on top of
+/// `defaults`.
+fn message_attr(attrs: &[Attribute], defaults: MessageAttr) ->
+    Result<MessageAttr, Error> {
+    let mut out = defaults;
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("message")) {
+        attr.parse_nested_meta(|meta| {
+            let value: LitStr = meta.value()?.parse()?;
+
+            if meta.path.is_ident("severity") {
+                out.severity = Some(severity(&value)?);
+            } else if meta.path.is_ident("brief") {
+                out.brief = Some(value);
+            } else if meta.path.is_ident("code") {
+                out.code = Some(value);
+            } else if meta.path.is_ident("key") {
+                out.key = Some(value);
+            } else if meta.path.is_ident("highlighting") {
+                out.highlighting = Some(highlighting(&value)?);
+            } else {
+                return Err(meta.error("unknown message setting"));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(out)
+}
+
+/// Check whether `attrs` marks a field as the `#[cache]`.
+fn cache_attr(attrs: &[Attribute]) -> Result<bool, Error> {
+    match attrs.iter().find(|attr| attr.path().is_ident("cache")) {
+        Some(attr) => attr.meta.require_path_only().map(|_| true),
+        None => Ok(false)
+    }
+}
+
+/// Get the position type `P` of the cache field type `ty`, which must
+/// be written as `DiagnosticCache<P>`.
+fn cache_position(ty: &Type) -> Result<Type, Error> {
+    if let Type::Path(path) = ty {
+        let args = path.path.segments.last().map(|segment| {
+            &segment.arguments
+        });
+
+        if let Some(PathArguments::AngleBracketed(args)) = args {
+            if let (1, Some(GenericArgument::Type(ty))) =
+                (args.args.len(), args.args.first()) {
+                return Ok(ty.clone());
+            }
+        }
+    }
+
+    Err(Error::new_spanned(ty, "expected `DiagnosticCache<P>`"))
+}
+
+/// Check that the position type `ty` of the field of type `field_ty`
+/// agrees with the others seen so far in `position_ty`.
+fn unify_position(position_ty: &mut Option<Type>, ty: &Type,
+                  field_ty: &Type) -> Result<(), Error> {
+    match position_ty {
+        Some(prev) if !same_type(prev, ty) => {
+            Err(Error::new_spanned(field_ty,
+                                   "all positions must have the same type"))
+        },
+        Some(_) => Ok(()),
+        None => {
+            *position_ty = Some(ty.clone());
+
+            Ok(())
+        }
+    }
+}
+
+/// Parse the `#[label(...)]` or `#[position]` attribute in `attrs`, if
+/// there is one.
+fn position_attr(attrs: &[Attribute], binding: &Ident, ty: &Type) ->
+    Result<Option<PositionField>, Error> {
+    for attr in attrs {
+        if attr.path().is_ident("position") {
+            attr.meta.require_path_only()?;
+
+            return Ok(Some(PositionField { binding: binding.clone(),
+                                           ty: ty.clone(), label: None,
+                                           severity: None }));
+        } else if attr.path().is_ident("label") {
+            let (label, severity) = attr.parse_args_with(|input:
+                                                         ParseStream| {
+                let label: LitStr = input.parse()?;
+                let mut out = None;
+
+                if input.parse::<Option<Token![,]>>()?.is_some() {
+                    let name: Ident = input.parse()?;
+
+                    if name != "severity" {
+                        return Err(Error::new(name.span(),
+                                              "expected `severity`"));
+                    }
+
+                    input.parse::<Token![=]>()?;
+                    out = Some(severity(&input.parse()?)?);
+                }
+
+                Ok((label, out))
+            })?;
+
+            return Ok(Some(PositionField { binding: binding.clone(),
+                                           ty: ty.clone(),
+                                           label: Some(label),
+                                           severity }));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Check whether `a` and `b` are written the same way.
+fn same_type(a: &Type, b: &Type) -> bool {
+    quote! { #a }.to_string() == quote! { #b }.to_string()
+}
+
+/// Get the `Severity` named by `value`.
+fn severity(value: &LitStr) -> Result<TokenStream2, Error> {
+    let variant = match value.value().as_str() {
+        "info" => quote! { Info },
+        "remark" => quote! { Remark },
+        "lint" => quote! { Lint },
+        "warning" => quote! { Warning },
+        "error" => quote! { Error },
+        "internal" => quote! { Internal },
+        _ => return Err(Error::new(value.span(), "unknown severity"))
+    };
+
+    Ok(quote! { ::compiler_tools::messages::Severity::#variant })
+}
+
+/// Get the `Highlighting` named by `value`.
+fn highlighting(value: &LitStr) -> Result<TokenStream2, Error> {
+    let variant = match value.value().as_str() {
+        "foreground" => quote! { Foreground },
+        "background" => quote! { Background },
+        _ => return Err(Error::new(value.span(), "unknown highlighting"))
+    };
+
+    Ok(quote! { ::compiler_tools::messages::Highlighting::#variant })
+}
+
+/// Get an expression formatting `text` with the fields in scope.
+///
+/// Tuple fields are bound as `_0`, `_1`, and so on, so references to
+/// them by index are renamed to match.
+fn format_string(text: &LitStr) -> TokenStream2 {
+    let value = text.value();
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        out.push(c);
+
+        if c == '{' {
+            match chars.peek() {
+                Some('{') => out.extend(chars.next()),
+                Some(c) if c.is_ascii_digit() => out.push('_'),
+                _ => ()
+            }
+        }
+    }
+
+    let text = LitStr::new(&out, text.span());
+
+    quote! { ::std::format!(#text) }
+}
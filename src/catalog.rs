@@ -0,0 +1,351 @@
+use crate::messages::Message;
+use crate::messages::Severity;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fs;
+use std::io::Error;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::rc::Rc;
+
+/// A catalog of localized message text for one locale.
+///
+/// Entries map keys to templates, which may refer to named arguments
+/// as `{name}`; literal braces are written as `{{` and `}}`.  A
+/// [`Message`] with a [`key`](Message::key) has its brief description
+/// looked up under the key, and its detailed description under the
+/// key followed by `.detail`, with arguments supplied by
+/// [`arg`](Message::arg).  [`Severity`] labels are looked up under
+/// [`Severity::key`].
+///
+/// Catalogs can have a fallback, which is searched for keys that are
+/// missing.  If no catalog has an entry, the English text built into
+/// the message or severity is used.
+///
+/// Catalog files consist of lines of the form `key = template`.
+/// Blank lines and lines starting with `#` are ignored, and `\n` and
+/// `\\` in templates stand for a newline and a backslash.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Catalog {
+    /// Name of the locale, such as `fr-CA`.
+    locale: String,
+    /// Templates, indexed by key.
+    entries: HashMap<String, String>,
+    /// Catalog to search for missing keys.
+    fallback: Option<Box<Catalog>>
+}
+
+/// Errors that can occur when loading a [`Catalog`].
+#[derive(Debug)]
+pub enum CatalogError {
+    /// The catalog file could not be read.
+    Io(Error),
+    /// A line in the catalog file is not an entry.
+    Syntax {
+        /// The line number, starting at 1.
+        line: usize
+    }
+}
+
+thread_local! {
+    /// The catalog used to localize messages on this thread.
+    static ACTIVE: RefCell<Option<Rc<Catalog>>> = const { RefCell::new(None) };
+}
+
+impl Catalog {
+    /// Create a new, empty `Catalog` for `locale`.
+    #[inline]
+    pub fn new(locale: &str) -> Self {
+        Catalog { locale: String::from(locale), entries: HashMap::new(),
+                  fallback: None }
+    }
+
+    /// Parse a `Catalog` for `locale` from the contents of a catalog
+    /// file.
+    pub fn parse(locale: &str, text: &str) -> Result<Self, CatalogError> {
+        let mut catalog = Catalog::new(locale);
+
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match line.split_once('=') {
+                Some((key, template)) if !key.trim().is_empty() => {
+                    catalog.insert(key.trim(), &unescape(template.trim()))
+                },
+                _ => return Err(CatalogError::Syntax { line: idx + 1 })
+            }
+        }
+
+        Ok(catalog)
+    }
+
+    /// Load a `Catalog` for `locale` from the file at `path`.
+    #[inline]
+    pub fn load<P>(locale: &str, path: P) -> Result<Self, CatalogError>
+    where P: AsRef<Path> {
+        Catalog::parse(locale, &fs::read_to_string(path)?)
+    }
+
+    /// Load the catalog for `locale` from the directory `dir`.
+    ///
+    /// The catalog is read from `<locale>.catalog`, and falls back to
+    /// the catalog for the language alone (for example, `fr.catalog`
+    /// for `fr-CA`).  Missing files are skipped, so if neither exists,
+    /// the result is an empty catalog, and English is used.
+    pub fn load_locale<P>(dir: P, locale: &str) ->
+        Result<Self, CatalogError>
+    where P: AsRef<Path> {
+        let dir = dir.as_ref();
+        let language = match locale.split_once(['-', '_']) {
+            Some((language, _)) => {
+                let path = dir.join(format!("{}.catalog", language));
+
+                load_optional(language, &path)?
+            },
+            None => None
+        };
+        let path = dir.join(format!("{}.catalog", locale));
+        let mut catalog = load_optional(locale, &path)?
+            .unwrap_or_else(|| Catalog::new(locale));
+
+        if let Some(language) = language {
+            catalog.set_fallback(language)
+        }
+
+        Ok(catalog)
+    }
+
+    /// Get the name of the locale.
+    #[inline]
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Get the number of entries, not including the fallback.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether there are no entries, not including the
+    /// fallback.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Add an entry with `template` under `key`, replacing any
+    /// existing entry.
+    #[inline]
+    pub fn insert(&mut self, key: &str, template: &str) {
+        self.entries.insert(String::from(key), String::from(template));
+    }
+
+    /// Get the fallback catalog, if there is one.
+    #[inline]
+    pub fn fallback(&self) -> Option<&Catalog> {
+        self.fallback.as_deref()
+    }
+
+    /// Set the catalog to search for keys missing from this one.
+    #[inline]
+    pub fn set_fallback(&mut self, fallback: Catalog) {
+        self.fallback = Some(Box::new(fallback))
+    }
+
+    /// Get the template for `key` from this catalog or its fallbacks.
+    pub fn lookup(&self, key: &str) -> Option<&str> {
+        match self.entries.get(key) {
+            Some(template) => Some(template),
+            None => self.fallback.as_ref()
+                .and_then(|fallback| fallback.lookup(key))
+        }
+    }
+
+    /// Format the template for `key`, getting named arguments from
+    /// `arg`.
+    ///
+    /// Arguments for which `arg` gives `None` are left as they appear
+    /// in the template.  Returns `None` if there is no template for
+    /// `key`.
+    pub fn format<F>(&self, key: &str, arg: F) -> Option<String>
+    where F: Fn(&str) -> Option<String> {
+        self.lookup(key).map(|template| format_template(template, arg))
+    }
+
+    /// Get the localized brief description of `msg`.
+    ///
+    /// This is [`Message::brief`] if `msg` has no key, or there is no
+    /// entry for it.
+    pub fn brief<'b, M>(&self, msg: &'b M) -> Cow<'b, str>
+    where M: Message + ?Sized {
+        msg.key()
+            .and_then(|key| self.format(key, |name| msg.arg(name)))
+            .map_or(Cow::Borrowed(msg.brief()), Cow::Owned)
+    }
+
+    /// Get the localized detailed description of `msg`.
+    ///
+    /// This is [`Message::detail`] if `msg` has no key, or there is no
+    /// entry for it.  Otherwise, the [help note](Message::help) of
+    /// `msg`, if any, follows the localized detail.
+    pub fn detail<'b, M>(&self, msg: &'b M) -> Cow<'b, str>
+    where M: Message + ?Sized {
+        msg.key()
+            .and_then(|key| {
+                self.format(&format!("{}.detail", key), |name| msg.arg(name))
+            })
+            .map_or(Cow::Borrowed(msg.detail()), |detail| {
+                match msg.help() {
+                    Some(help) if detail.is_empty() =>
+                        Cow::Owned(String::from(help)),
+                    Some(help) => Cow::Owned(format!("{}\n{}", detail, help)),
+                    None => Cow::Owned(detail)
+                }
+            })
+    }
+
+    /// Get the localized label for `severity`, if there is one.
+    #[inline]
+    pub fn severity(&self, severity: Severity) -> Option<&str> {
+        self.lookup(severity.key())
+    }
+}
+
+impl Display for CatalogError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            CatalogError::Io(err) => Display::fmt(err, f),
+            CatalogError::Syntax { line } =>
+                write!(f, "expected `key = template` at line {}", line)
+        }
+    }
+}
+
+impl std::error::Error for CatalogError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CatalogError::Io(err) => Some(err),
+            CatalogError::Syntax { .. } => None
+        }
+    }
+}
+
+impl From<Error> for CatalogError {
+    #[inline]
+    fn from(err: Error) -> Self {
+        CatalogError::Io(err)
+    }
+}
+
+/// Set the catalog used to localize messages on the current thread.
+///
+/// If `catalog` is `None`, messages are written in English.
+#[inline]
+pub fn set_active_catalog(catalog: Option<Rc<Catalog>>) {
+    ACTIVE.with(|active| *active.borrow_mut() = catalog)
+}
+
+/// Get the catalog used to localize messages on the current thread,
+/// if there is one.
+#[inline]
+pub fn active_catalog() -> Option<Rc<Catalog>> {
+    ACTIVE.with(|active| active.borrow().clone())
+}
+
+/// Get the brief description of `msg`, localized with the active
+/// catalog.
+///
+/// See [`Catalog::brief`].
+#[inline]
+pub fn localized_brief<M>(msg: &M) -> Cow<'_, str>
+where M: Message + ?Sized {
+    match active_catalog() {
+        Some(catalog) => Cow::Owned(catalog.brief(msg).into_owned()),
+        None => Cow::Borrowed(msg.brief())
+    }
+}
+
+/// Get the detailed description of `msg`, localized with the active
+/// catalog.
+///
+/// See [`Catalog::detail`].
+#[inline]
+pub fn localized_detail<M>(msg: &M) -> Cow<'_, str>
+where M: Message + ?Sized {
+    match active_catalog() {
+        Some(catalog) => Cow::Owned(catalog.detail(msg).into_owned()),
+        None => Cow::Borrowed(msg.detail())
+    }
+}
+
+/// Load the catalog at `path`, if the file exists.
+fn load_optional(locale: &str, path: &Path) ->
+    Result<Option<Catalog>, CatalogError> {
+    match fs::read_to_string(path) {
+        Ok(text) => Catalog::parse(locale, &text).map(Some),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(CatalogError::Io(err))
+    }
+}
+
+/// Replace the escapes `\n` and `\\` in `text`.
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                chars.next();
+                out.push('\n')
+            },
+            ('\\', Some('\\')) => {
+                chars.next();
+                out.push('\\')
+            },
+            _ => out.push(c)
+        }
+    }
+
+    out
+}
+
+/// Substitute the named arguments given by `arg` into `template`.
+fn format_template<F>(template: &str, arg: F) -> String
+where F: Fn(&str) -> Option<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(idx) = rest.find(['{', '}']) {
+        out.push_str(&rest[.. idx]);
+        rest = &rest[idx ..];
+
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            out.push_str(&rest[.. 1]);
+            rest = &rest[2 ..];
+        } else if let (true, Some(end)) = (rest.starts_with('{'),
+                                           rest.find('}')) {
+            match arg(&rest[1 .. end]) {
+                Some(value) => out.push_str(&value),
+                None => out.push_str(&rest[.. end + 1])
+            }
+
+            rest = &rest[end + 1 ..];
+        } else {
+            out.push_str(&rest[.. 1]);
+            rest = &rest[1 ..];
+        }
+    }
+
+    out.push_str(rest);
+
+    out
+}
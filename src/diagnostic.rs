@@ -0,0 +1,357 @@
+use crate::messages::Highlighting;
+use crate::messages::Message;
+use crate::messages::MessagePositions;
+use crate::messages::Severity;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::sync::OnceLock;
+
+/// Derive conversions from error types into [`Diagnostic`](struct@Diagnostic).
+///
+/// This is only available with the `derive` feature.
+#[cfg(feature = "derive")]
+pub use compiler_tools_derive::Diagnostic;
+
+/// A compiler message that owns all of its text.
+///
+/// This is built up with methods that consume and return the
+/// `Diagnostic`, such as `Diagnostic::error(brief).with_label(pos,
+/// label)`, so that simple messages do not need their own types.
+///
+/// The detailed description consists of the notes and help messages,
+/// one per line, in the order they were added.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Diagnostic<P> {
+    /// Severity of the message.
+    severity: Severity,
+    /// Brief description.
+    brief: String,
+    /// Detailed description, built up from notes.
+    detail: String,
+    /// Code identifying the kind of message.
+    code: Option<String>,
+    /// Key identifying the message in a catalog.
+    key: Option<String>,
+    /// Named arguments for localized text.
+    args: Vec<(String, String)>,
+    /// Highlighting style.
+    highlighting: Highlighting,
+    /// Positions, with labels pointing into `labels`.
+    positions: Vec<(Option<&'static str>, P, Severity)>,
+    /// Storage for the labels of positions.
+    ///
+    /// Labels are never removed or modified, so the pointers in
+    /// `positions` stay valid as long as the `Diagnostic` exists.
+    labels: Vec<String>
+}
+
+/// Storage for the [`Diagnostic`](struct@Diagnostic) of a message type
+/// deriving `Message`.
+///
+/// The derived implementation builds the `Diagnostic` the first time
+/// the message is used, and keeps it here.  A new or cloned cache is
+/// empty, and all caches compare equal, so other traits can still be
+/// derived for the message type.
+pub struct DiagnosticCache<P> {
+    /// The diagnostic, once it has been built.
+    cell: OnceLock<Diagnostic<P>>
+}
+
+impl<P> Diagnostic<P> {
+    /// Create a new `Diagnostic` with `severity` and brief description
+    /// `brief`.
+    #[inline]
+    pub fn new(severity: Severity, brief: &str) -> Self {
+        Diagnostic { severity, brief: String::from(brief),
+                     detail: String::new(), code: None, key: None,
+                     args: Vec::new(), highlighting: Highlighting::Foreground,
+                     positions: Vec::new(), labels: Vec::new() }
+    }
+
+    /// Create a new [`Info`](Severity::Info) `Diagnostic`.
+    #[inline]
+    pub fn info(brief: &str) -> Self {
+        Diagnostic::new(Severity::Info, brief)
+    }
+
+    /// Create a new [`Remark`](Severity::Remark) `Diagnostic`.
+    #[inline]
+    pub fn remark(brief: &str) -> Self {
+        Diagnostic::new(Severity::Remark, brief)
+    }
+
+    /// Create a new [`Lint`](Severity::Lint) `Diagnostic`.
+    #[inline]
+    pub fn lint(brief: &str) -> Self {
+        Diagnostic::new(Severity::Lint, brief)
+    }
+
+    /// Create a new [`Warning`](Severity::Warning) `Diagnostic`.
+    #[inline]
+    pub fn warning(brief: &str) -> Self {
+        Diagnostic::new(Severity::Warning, brief)
+    }
+
+    /// Create a new [`Error`](Severity::Error) `Diagnostic`.
+    #[inline]
+    pub fn error(brief: &str) -> Self {
+        Diagnostic::new(Severity::Error, brief)
+    }
+
+    /// Create a new [`Internal`](Severity::Internal) `Diagnostic`.
+    #[inline]
+    pub fn internal(brief: &str) -> Self {
+        Diagnostic::new(Severity::Internal, brief)
+    }
+
+    /// Add the position `pos`, without a label.
+    ///
+    /// The position has the same [`Severity`] as the message.
+    #[inline]
+    pub fn with_position(mut self, pos: P) -> Self {
+        self.positions.push((None, pos, self.severity));
+
+        self
+    }
+
+    /// Add the position `pos`, labeled with `label`.
+    ///
+    /// The position has the same [`Severity`] as the message.
+    #[inline]
+    pub fn with_label(self, pos: P, label: &str) -> Self {
+        let severity = self.severity;
+
+        self.with_label_severity(pos, label, severity)
+    }
+
+    /// Add the secondary position `pos`, labeled with `label`.
+    ///
+    /// The position has [`Severity::Info`].
+    #[inline]
+    pub fn with_secondary(self, pos: P, label: &str) -> Self {
+        self.with_label_severity(pos, label, Severity::Info)
+    }
+
+    /// Add the position `pos`, labeled with `label` and highlighted
+    /// with `severity`.
+    pub fn with_label_severity(mut self, pos: P, label: &str,
+                               severity: Severity) -> Self {
+        let label = self.store(label);
+
+        self.positions.push((Some(label), pos, severity));
+
+        self
+    }
+
+    /// Add a line to the detailed description, of the form
+    /// `note: {note}`.
+    #[inline]
+    pub fn with_note(self, note: &str) -> Self {
+        self.with_detail_line("note", note)
+    }
+
+    /// Add a line to the detailed description, of the form
+    /// `help: {help}`.
+    #[inline]
+    pub fn with_help(self, help: &str) -> Self {
+        self.with_detail_line("help", help)
+    }
+
+    /// Set the code identifying the kind of message.
+    #[inline]
+    pub fn with_code(mut self, code: &str) -> Self {
+        self.code = Some(String::from(code));
+
+        self
+    }
+
+    /// Set the key identifying the message in a catalog.
+    ///
+    /// See [`Message::key`].
+    #[inline]
+    pub fn with_key(mut self, key: &str) -> Self {
+        self.key = Some(String::from(key));
+
+        self
+    }
+
+    /// Set the named argument `name` to `value`, for formatting
+    /// localized text.
+    ///
+    /// See [`Message::arg`].
+    pub fn with_arg<T>(mut self, name: &str, value: T) -> Self
+    where T: ToString {
+        let value = value.to_string();
+
+        match self.args.iter_mut().find(|(key, _)| key == name) {
+            Some((_, old)) => *old = value,
+            None => self.args.push((String::from(name), value))
+        }
+
+        self
+    }
+
+    /// Set the highlighting style.
+    #[inline]
+    pub fn with_highlighting(mut self, highlighting: Highlighting) -> Self {
+        self.highlighting = highlighting;
+
+        self
+    }
+
+    /// Transform each position with `f`, keeping the labels and
+    /// severities.
+    pub fn map_positions<Q, F>(self, mut f: F) -> Diagnostic<Q>
+    where F: FnMut(P) -> Q {
+        Diagnostic { severity: self.severity, brief: self.brief,
+                     detail: self.detail, code: self.code, key: self.key,
+                     args: self.args, highlighting: self.highlighting,
+                     positions: self.positions.into_iter()
+                         .map(|(label, pos, severity)| {
+                             (label, f(pos), severity)
+                         })
+                         .collect(),
+                     labels: self.labels }
+    }
+
+    /// Add the line `{prefix}: {text}` to the detailed description.
+    fn with_detail_line(mut self, prefix: &str, text: &str) -> Self {
+        if !self.detail.is_empty() {
+            self.detail.push('\n');
+        }
+
+        self.detail.push_str(prefix);
+        self.detail.push_str(": ");
+        self.detail.push_str(text);
+
+        self
+    }
+
+    /// Internal function to store a label.
+    ///
+    /// The result must not outlive `self`.
+    fn store(&mut self, label: &str) -> &'static str {
+        let label = String::from(label);
+        let ptr = label.as_str() as *const str;
+
+        self.labels.push(label);
+
+        // The string is never dropped or modified while `self`
+        // exists, and moving it into `labels` does not move its
+        // contents.
+        unsafe { &*ptr }
+    }
+}
+
+impl<P> DiagnosticCache<P> {
+    /// Create a new, empty `DiagnosticCache`.
+    #[inline]
+    pub fn new() -> Self {
+        DiagnosticCache { cell: OnceLock::new() }
+    }
+
+    /// Get the cached diagnostic, building it with `f` if it has not
+    /// been built yet.
+    #[inline]
+    pub fn get_or_init<F>(&self, f: F) -> &Diagnostic<P>
+    where F: FnOnce() -> Diagnostic<P> {
+        self.cell.get_or_init(f)
+    }
+}
+
+impl<P> Default for DiagnosticCache<P> {
+    #[inline]
+    fn default() -> Self {
+        DiagnosticCache::new()
+    }
+}
+
+impl<P> Clone for Diagnostic<P>
+where P: Clone {
+    fn clone(&self) -> Self {
+        let mut out = Diagnostic {
+            severity: self.severity, brief: self.brief.clone(),
+            detail: self.detail.clone(), code: self.code.clone(),
+            key: self.key.clone(), args: self.args.clone(),
+            highlighting: self.highlighting,
+            positions: Vec::with_capacity(self.positions.len()),
+            labels: Vec::with_capacity(self.labels.len())
+        };
+
+        // Labels must be copied, so that they point into the clone.
+        for (label, pos, severity) in &self.positions {
+            let label = label.map(|label| out.store(label));
+
+            out.positions.push((label, pos.clone(), *severity));
+        }
+
+        out
+    }
+}
+
+impl<P> Message for Diagnostic<P> {
+    #[inline]
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    #[inline]
+    fn brief(&self) -> &str {
+        &self.brief
+    }
+
+    #[inline]
+    fn detail(&self) -> &str {
+        &self.detail
+    }
+
+    #[inline]
+    fn highlighting(&self) -> Highlighting {
+        self.highlighting
+    }
+
+    #[inline]
+    fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    #[inline]
+    fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    fn arg(&self, name: &str) -> Option<String> {
+        self.args.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+    }
+}
+
+impl<P> MessagePositions<P> for Diagnostic<P> {
+    #[inline]
+    fn positions(&self) -> &[(Option<&str>, P, Severity)] {
+        &self.positions
+    }
+}
+
+impl<P> Clone for DiagnosticCache<P> {
+    #[inline]
+    fn clone(&self) -> Self {
+        DiagnosticCache::new()
+    }
+}
+
+impl<P> Debug for DiagnosticCache<P> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        f.write_str("DiagnosticCache")
+    }
+}
+
+impl<P> Eq for DiagnosticCache<P> {}
+
+impl<P> PartialEq for DiagnosticCache<P> {
+    #[inline]
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,424 @@
+use crate::files::Filename;
+use crate::lines::Offset;
+use crate::messages::Message;
+use crate::messages::MessagePositions;
+use crate::messages::Severity;
+use crate::position::FilePosition;
+use std::borrow::Cow;
+use std::char;
+use std::convert::TryFrom;
+use std::fmt::Display;
+use std::fmt::Error;
+use std::fmt::Formatter;
+use std::str;
+
+/// Character encodings of source files.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Encoding {
+    /// UTF-8, with or without a byte order mark.
+    Utf8,
+    /// Little-endian UTF-16.
+    Utf16Le,
+    /// Big-endian UTF-16.
+    Utf16Be,
+    /// ISO-8859-1 (Latin-1).
+    ///
+    /// Every byte is a valid character, so decoding never fails.
+    Latin1
+}
+
+/// Map from offsets in decoded text back to offsets in the original
+/// bytes.
+///
+/// This allows positions in a transcoded source to be reported
+/// relative to the file as it exists on disk.  The map is stored as
+/// runs of characters with the same decoded and original widths, so
+/// that mostly-ASCII text needs very few runs.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OffsetMap {
+    /// The runs, sorted by decoded offset.  There is always at least
+    /// one.
+    runs: Vec<Run>
+}
+
+/// A run of characters with the same widths in an [`OffsetMap`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Run {
+    /// Decoded offset of the start of the run.
+    decoded: usize,
+    /// Original offset of the start of the run.
+    original: usize,
+    /// Width of each character in the decoded text.
+    decoded_width: usize,
+    /// Width of each character in the original bytes.
+    original_width: usize
+}
+
+/// Text decoded from the raw bytes of a file.
+#[derive(Clone, Debug)]
+pub struct Decoded<'b> {
+    /// The decoded text, without any byte order mark.
+    ///
+    /// This borrows from the original bytes if no transcoding was
+    /// necessary.
+    pub text: Cow<'b, str>,
+    /// The encoding the bytes were decoded from.
+    pub encoding: Encoding,
+    /// Map from offsets in `text` to offsets in the original bytes.
+    pub offset_map: OffsetMap
+}
+
+/// Low-level error from [`decode`], without a filename.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct DecodeError {
+    /// The encoding being decoded.
+    pub encoding: Encoding,
+    /// Offset of the first invalid byte in the original bytes.
+    pub offset: usize,
+    /// Number of invalid bytes.
+    pub len: usize
+}
+
+/// Position of a byte in a file that could not be decoded.
+///
+/// As the file has no decoded text, there is no source context for
+/// the byte, and the line and column are computed directly from the
+/// bytes.  A `BytePosition` converts to a [`FilePosition`] for the
+/// whole file, so that an [`EncodingError`] can be reported by any
+/// [`MessageWriter`].  As the file is not in any
+/// [`Sources`](crate::sources::Sources), writers that show context
+/// fall back to the `Display` form, which gives the line and column.
+///
+/// [`MessageWriter`]: crate::messages::MessageWriter
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct BytePosition<'a> {
+    /// Position of the whole file.
+    file: FilePosition<'a>,
+    /// Offset of the byte.
+    offset: usize,
+    /// Line of the byte, starting at 0.
+    line: usize,
+    /// Column of the byte, in bytes, starting at 0.
+    column: usize
+}
+
+/// Error reported when a file cannot be decoded.
+///
+/// This is a [`Message`], positioned at the first invalid byte.
+#[derive(Clone, Debug)]
+pub struct EncodingError<'a> {
+    /// The encoding being decoded.
+    encoding: Encoding,
+    /// Brief description.
+    brief: String,
+    /// Detailed description.
+    detail: String,
+    /// Position of the first invalid byte.
+    positions: [(Option<&'static str>, BytePosition<'a>, Severity); 1]
+}
+
+impl Encoding {
+    /// Detect a byte order mark at the start of `bytes`.
+    ///
+    /// Returns the [`Encoding`] it indicates and its length in
+    /// bytes.
+    pub fn detect_bom(bytes: &[u8]) -> Option<(Encoding, usize)> {
+        match bytes {
+            [0xef, 0xbb, 0xbf, ..] => Some((Encoding::Utf8, 3)),
+            [0xff, 0xfe, ..] => Some((Encoding::Utf16Le, 2)),
+            [0xfe, 0xff, ..] => Some((Encoding::Utf16Be, 2)),
+            _ => None
+        }
+    }
+
+    /// Get the `Encoding` for a label, such as `"latin1"` or
+    /// `"UTF-16LE"`.
+    ///
+    /// Labels are matched ignoring case, `-`, and `_`.
+    pub fn from_label(label: &str) -> Option<Encoding> {
+        let label: String = label.chars()
+            .filter(|c| *c != '-' && *c != '_')
+            .flat_map(char::to_lowercase)
+            .collect();
+
+        match &label[..] {
+            "utf8" => Some(Encoding::Utf8),
+            "utf16le" => Some(Encoding::Utf16Le),
+            "utf16be" => Some(Encoding::Utf16Be),
+            "latin1" | "iso88591" | "l1" => Some(Encoding::Latin1),
+            _ => None
+        }
+    }
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            Encoding::Utf8 => write!(f, "UTF-8"),
+            Encoding::Utf16Le => write!(f, "UTF-16LE"),
+            Encoding::Utf16Be => write!(f, "UTF-16BE"),
+            Encoding::Latin1 => write!(f, "ISO-8859-1")
+        }
+    }
+}
+
+impl OffsetMap {
+    /// Create an `OffsetMap` where decoded offsets are `shift` bytes
+    /// before the original offsets.
+    #[inline]
+    pub fn shifted(shift: usize) -> Self {
+        OffsetMap { runs: vec![Run { decoded: 0, original: shift,
+                                     decoded_width: 1, original_width: 1 }] }
+    }
+
+    /// Create an `OffsetMap` where decoded and original offsets are
+    /// the same.
+    #[inline]
+    pub fn identity() -> Self {
+        OffsetMap::shifted(0)
+    }
+
+    /// Check whether decoded and original offsets are the same.
+    #[inline]
+    pub fn is_identity(&self) -> bool {
+        self.runs.len() == 1 && self.runs[0].original == 0 &&
+        self.runs[0].decoded_width == self.runs[0].original_width
+    }
+
+    /// Get the original offset corresponding to the decoded offset
+    /// `offset`.
+    ///
+    /// Offsets inside a multi-byte character map to the start of the
+    /// character.
+    pub fn original(&self, offset: Offset) -> Offset {
+        let offset = usize::from(offset);
+        let idx = self.runs.partition_point(|run| run.decoded <= offset);
+        let run = &self.runs[idx.max(1) - 1];
+        let chars = (offset - run.decoded) / run.decoded_width;
+
+        Offset::from(run.original + chars * run.original_width)
+    }
+
+    /// Add a character to the end of this map.
+    fn push(&mut self, decoded: usize, original: usize,
+            decoded_width: usize, original_width: usize) {
+        match self.runs.last() {
+            Some(last) if last.decoded_width == decoded_width &&
+                          last.original_width == original_width => (),
+            _ => self.runs.push(Run { decoded, original,
+                                      decoded_width,
+                                      original_width })
+        }
+    }
+}
+
+/// Decode `bytes` into text.
+///
+/// A byte order mark, if present, determines the encoding and is
+/// stripped.  Otherwise, `bytes` are decoded as `declared`.  UTF-8
+/// text (with or without a byte order mark) is borrowed from
+/// `bytes`; other encodings are transcoded.
+pub fn decode(bytes: &[u8], declared: Encoding) ->
+    Result<Decoded<'_>, DecodeError> {
+    let (encoding, start) = Encoding::detect_bom(bytes)
+        .unwrap_or((declared, 0));
+    let body = &bytes[start ..];
+
+    match encoding {
+        Encoding::Utf8 => match str::from_utf8(body) {
+            Ok(text) => Ok(Decoded { text: Cow::Borrowed(text),
+                                     encoding,
+                                     offset_map: OffsetMap::shifted(start) }),
+            Err(err) => Err(DecodeError {
+                encoding, offset: start + err.valid_up_to(),
+                len: err.error_len().unwrap_or(body.len() - err.valid_up_to())
+            })
+        },
+        Encoding::Latin1 => {
+            let mut text = String::with_capacity(body.len());
+            let mut offset_map = OffsetMap { runs: Vec::new() };
+
+            for (i, byte) in body.iter().enumerate() {
+                let c = char::from(*byte);
+
+                offset_map.push(text.len(), start + i, c.len_utf8(), 1);
+                text.push(c);
+            }
+
+            if offset_map.runs.is_empty() {
+                offset_map = OffsetMap::shifted(start);
+            }
+
+            Ok(Decoded { text: Cow::Owned(text), encoding,
+                         offset_map })
+        },
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            let units = body.chunks_exact(2).map(|pair| {
+                if encoding == Encoding::Utf16Le {
+                    u16::from_le_bytes([pair[0], pair[1]])
+                } else {
+                    u16::from_be_bytes([pair[0], pair[1]])
+                }
+            });
+            let mut text = String::with_capacity(body.len() / 2);
+            let mut offset_map = OffsetMap { runs: Vec::new() };
+            let mut offset = start;
+
+            for c in char::decode_utf16(units) {
+                match c {
+                    Ok(c) => {
+                        let width = c.len_utf16() * 2;
+
+                        offset_map.push(text.len(), offset,
+                                        c.len_utf8(), width);
+                        text.push(c);
+                        offset += width;
+                    },
+                    Err(_) => return Err(DecodeError {
+                        encoding, offset, len: 2
+                    })
+                }
+            }
+
+            if body.len() % 2 != 0 {
+                return Err(DecodeError { encoding,
+                                         offset: bytes.len() - 1, len: 1 });
+            }
+
+            if offset_map.runs.is_empty() {
+                offset_map = OffsetMap::shifted(start);
+            }
+
+            Ok(Decoded { text: Cow::Owned(text), encoding,
+                         offset_map })
+        }
+    }
+}
+
+impl<'a> BytePosition<'a> {
+    /// Create a `BytePosition` for `offset` in `bytes`, the contents
+    /// of `filename`.
+    pub fn new(filename: Filename<'a>, bytes: &[u8], offset: usize) -> Self {
+        let before = &bytes[.. offset.min(bytes.len())];
+        let line = before.iter().filter(|byte| **byte == b'\n').count();
+        let column = match before.iter().rposition(|byte| *byte == b'\n') {
+            Some(newline) => offset - newline - 1,
+            None => offset
+        };
+
+        BytePosition { file: FilePosition::File { filename },
+                       offset, line, column }
+    }
+
+    /// Get the name of the file.
+    #[inline]
+    pub fn filename(&self) -> Filename<'a> {
+        match self.file {
+            FilePosition::File { filename } => filename,
+            FilePosition::Portion { file_offsets, .. } =>
+                file_offsets.filename()
+        }
+    }
+
+    /// Get the offset of the byte.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Get the line of the byte, starting at 0.
+    #[inline]
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Get the column of the byte, in bytes, starting at 0.
+    #[inline]
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl Display for BytePosition<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "at {}:{}.{} (byte {})", self.filename(), self.line + 1,
+               self.column + 1, self.offset)
+    }
+}
+
+impl<'a> TryFrom<&'a BytePosition<'a>> for &'a FilePosition<'a> {
+    type Error = ();
+
+    #[inline]
+    fn try_from(val: &'a BytePosition<'a>) -> Result<Self, Self::Error> {
+        Ok(&val.file)
+    }
+}
+
+impl<'a> EncodingError<'a> {
+    /// Create an `EncodingError` from `err`, which occurred decoding
+    /// `bytes`, the contents of `filename`.
+    pub fn new(filename: Filename<'a>, bytes: &[u8], err: DecodeError) ->
+        Self {
+        let invalid = &bytes[err.offset .. (err.offset + err.len)
+                                              .min(bytes.len())];
+        let hex: Vec<String> = invalid.iter()
+            .map(|byte| format!("0x{:02x}", byte))
+            .collect();
+        let brief = format!("invalid {} in {}", err.encoding, filename);
+        let detail = format!("byte{} {} at offset {} {} not valid {}",
+                             if hex.len() == 1 { "" } else { "s" },
+                             hex.join(" "), err.offset,
+                             if hex.len() == 1 { "is" } else { "are" },
+                             err.encoding);
+        let pos = BytePosition::new(filename, bytes, err.offset);
+
+        EncodingError { encoding: err.encoding, brief, detail,
+                        positions: [(Some("invalid byte"), pos,
+                                     Severity::Error)] }
+    }
+
+    /// Get the encoding that was being decoded.
+    #[inline]
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Get the position of the first invalid byte.
+    #[inline]
+    pub fn position(&self) -> &BytePosition<'a> {
+        &self.positions[0].1
+    }
+}
+
+impl Display for EncodingError<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "{} {}: {}", self.brief, self.position(), self.detail)
+    }
+}
+
+impl std::error::Error for EncodingError<'_> {}
+
+impl Message for EncodingError<'_> {
+    #[inline]
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    #[inline]
+    fn brief(&self) -> &str {
+        &self.brief
+    }
+
+    #[inline]
+    fn detail(&self) -> &str {
+        &self.detail
+    }
+}
+
+impl<'a> MessagePositions<BytePosition<'a>> for EncodingError<'a> {
+    #[inline]
+    fn positions(&self) -> &[(Option<&str>, BytePosition<'a>, Severity)] {
+        &self.positions
+    }
+}
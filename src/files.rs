@@ -4,6 +4,7 @@ use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::collections::hash_map::Entry;
+use std::env;
 use std::ffi::OsStr;
 use std::ffi::OsString;
 use std::fmt::Debug;
@@ -13,14 +14,21 @@ use std::fs::Metadata;
 use std::hash::Hash;
 use std::hash::Hasher;
 use std::io::Error;
+use std::io::ErrorKind;
 use std::iter::IntoIterator;
 use std::marker::PhantomData;
 use std::path::Ancestors;
+use std::path::Component;
 use std::path::Components;
 use std::path::Iter;
 use std::path::Path;
 use std::path::PathBuf;
 use std::path::StripPrefixError;
+use std::sync::Arc;
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+#[cfg(feature = "serde")]
+use serde::Serialize;
 
 /// A distinguished type for file paths.
 ///
@@ -30,13 +38,86 @@ use std::path::StripPrefixError;
 /// common technique employed in compiler implementation, as string
 /// hashing and comparison is so common.
 #[derive(Clone, Copy)]
-pub struct Filename<'a>(&'a Path);
+pub struct Filename<'a>(&'a FileData);
+
+/// Kinds of [`Filename`]s.
+///
+/// All kinds other than [`Disk`](FileKind::Disk) are virtual, and do
+/// not correspond to a file that can be opened.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum FileKind {
+    /// A file on disk, identified by its canonical path.
+    Disk,
+    /// Standard input.
+    Stdin,
+    /// Input from an interactive session, such as a REPL cell.
+    Repl,
+    /// Code generated by the compiler.
+    Generated,
+    /// Code held in memory, such as a test fixture.
+    Memory,
+    /// A file inside an archive.
+    Archive
+}
+
+/// Modes for displaying [`Filename`]s.
+///
+/// This only affects how filenames are displayed; they are always
+/// identified by their canonical path.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum FilenameDisplay {
+    /// Display the canonical path.
+    Canonical,
+    /// Display the path as it was supplied when the filename was
+    /// first created.
+    Supplied,
+    /// Display the path relative to the base directory.
+    ///
+    /// Paths outside the base directory are displayed as the
+    /// canonical path.  If no base directory is set, paths are
+    /// displayed as they were supplied.
+    Relative
+}
+
+/// Interned data for a [`Filename`].
+struct FileData {
+    /// The canonical path for a file on disk, or the name of a
+    /// virtual file.
+    path: PathBuf,
+    /// The path as it was first supplied.
+    supplied: PathBuf,
+    /// The kind of file.
+    kind: FileKind,
+    /// The path to display, as of when the file was interned.
+    display: PathBuf,
+    /// The canonical path with prefix rewrites applied, as of when
+    /// the file was interned.
+    remapped: PathBuf,
+    /// Canonical working directory, when the [`Filenames`] was created.
+    cwd: Option<Arc<Path>>
+}
 
 /// Interned filenames table, for producing [`Filename`]s.
 pub struct Filenames<'a> {
     lifetime: PhantomData<&'a Path>,
     /// Interned [Path]s
-    interned: HashMap<PathBuf, ()>
+    interned: HashMap<PathBuf, Box<FileData>>,
+    /// Interned virtual filenames
+    virtuals: HashMap<(FileKind, String), Box<FileData>>,
+    /// How to display filenames.
+    mode: FilenameDisplay,
+    /// Canonical base directory for relative display.
+    base: Option<PathBuf>,
+    /// Prefix rewrites, in the order they were added.
+    remaps: Vec<(PathBuf, PathBuf)>,
+    /// Canonical working directory, when this was created.
+    cwd: Option<Arc<Path>>,
+    /// Canonical paths, by the lexical resolution of spellings
+    /// that were supplied for them, as of the last time each was
+    /// supplied.  Only spellings that do not resolve lexically to
+    /// their canonical path are kept.
+    spellings: HashMap<PathBuf, PathBuf>
 }
 
 /// Line offsets for a given file.
@@ -59,196 +140,345 @@ impl<'a> Filename<'a> {
     /// form a contiguous or dense range.
     #[inline]
     pub fn id(&self) -> usize {
-        ((self.0 as *const _) as *const u8) as usize
+        (self.0 as *const FileData) as usize
+    }
+
+    /// Get the [`FileKind`] of this `Filename`.
+    #[inline]
+    pub fn kind(&self) -> FileKind {
+        self.0.kind
+    }
+
+    /// Check whether this is a virtual `Filename`.
+    ///
+    /// Virtual filenames do not correspond to a file on disk.  For
+    /// these, the [`Path`] methods operate on the name supplied when
+    /// it was created, and methods that access the filesystem
+    /// report that the file does not exist.
+    #[inline]
+    pub fn is_virtual(&self) -> bool {
+        self.0.kind != FileKind::Disk
+    }
+
+    /// Get the path as it was supplied when this `Filename` was
+    /// first created.
+    ///
+    /// For virtual filenames, this is the name.
+    #[inline]
+    pub fn supplied(&self) -> &Path {
+        &self.0.supplied
+    }
+
+    /// Get the path used to display this `Filename`.
+    ///
+    /// This depends on the [`FilenameDisplay`] mode of the
+    /// [`Filenames`] that created it, and has its path prefix
+    /// rewrites (see [`Filenames::add_remap`]) applied, as they were
+    /// when this `Filename` was first created.  For virtual
+    /// filenames, this is the name.
+    #[inline]
+    pub fn display_path(&self) -> &Path {
+        &self.0.display
+    }
+
+    /// Get the name of this `Filename` as plain text.
+    ///
+    /// This is the [`display_path`](Filename::display_path) of files
+    /// on disk, without the quotes added by `Display`, and the
+    /// `Display` form (such as `<stdin>`) of virtual filenames.  It
+    /// is intended for output parsed by other tools.
+    pub fn display_name(&self) -> String {
+        if self.is_virtual() {
+            self.to_string()
+        } else {
+            self.display_path().display().to_string()
+        }
+    }
+
+    /// Get the canonical path of this `Filename`, with the path
+    /// prefix rewrites of the [`Filenames`] that created it applied,
+    /// as they were when this `Filename` was first created.
+    ///
+    /// This is the path that should be written to serialized data
+    /// and generated artefacts.  For virtual filenames, this is the
+    /// name.
+    #[inline]
+    pub fn remapped_path(&self) -> &Path {
+        &self.0.remapped
+    }
+
+    /// Resolve `path` for comparison with this `Filename`, without
+    /// accessing the filesystem.
+    ///
+    /// The path as it was first supplied resolves to this file.
+    /// Other paths are resolved lexically, relative to the working
+    /// directory when the [`Filenames`] was created.
+    #[inline]
+    fn resolve<'b>(&'b self, path: &'b Path) -> Option<Cow<'b, Path>> {
+        if path == self.0.supplied {
+            Some(Cow::from(self.0.path.as_path()))
+        } else {
+            lexical(self.0.cwd.as_deref(), path)
+        }
+    }
+
+    /// Check whether `path` refers to this `Filename`, without
+    /// accessing the filesystem.
+    #[inline]
+    fn eq_path(&self, path: &Path) -> bool {
+        !self.is_virtual() && match self.resolve(path) {
+            Some(resolved) => resolved == self.0.path.as_path(),
+            None => false
+        }
+    }
+
+    /// Compare the canonical path of this `Filename` to `path`,
+    /// without accessing the filesystem.
+    #[inline]
+    fn cmp_path(&self, path: &Path) -> Option<Ordering> {
+        if !self.is_virtual() {
+            self.resolve(path)
+                .map(|resolved| self.0.path.as_path().cmp(&resolved))
+        } else {
+            None
+        }
     }
 
     /// See [`Path::as_os_str`].
     #[inline]
     pub fn as_os_str(&self) -> &OsStr {
-        self.0.as_os_str()
+        self.0.path.as_os_str()
     }
 
     /// See [`Path::to_str`].
     #[inline]
     pub fn to_str(&self) -> Option<&str> {
-        self.0.to_str()
+        self.0.path.to_str()
     }
 
     /// See [`Path::to_string_lossy`].
     #[inline]
     pub fn to_string_lossy(&self) -> Cow<'_, str> {
-        self.0.to_string_lossy()
+        self.0.path.to_string_lossy()
     }
 
     /// See [`Path::to_path_buf`].
     #[inline]
     pub fn to_path_buf(&self) -> PathBuf {
-        self.0.to_path_buf()
+        self.0.path.to_path_buf()
     }
 
     /// See [`Path::is_absolute`].
     #[inline]
     pub fn is_absolute(&self) -> bool {
-        self.0.is_absolute()
+        self.0.path.is_absolute()
     }
 
     /// See [`Path::is_relative`].
     #[inline]
     pub fn is_relative(&self) -> bool {
-        self.0.is_relative()
+        self.0.path.is_relative()
     }
 
     /// See [`Path::has_root`].
     #[inline]
     pub fn has_root(&self) -> bool {
-        self.0.has_root()
+        self.0.path.has_root()
     }
 
     /// See [`Path::parent`].
     #[inline]
     pub fn parent(&self) -> Option<&Path> {
-        self.0.parent()
+        self.0.path.parent()
     }
 
     /// See [`Path::ancestors`].
     #[inline]
-    pub fn ancestors(&self) -> Ancestors {
-        self.0.ancestors()
+    pub fn ancestors(&self) -> Ancestors<'_> {
+        self.0.path.ancestors()
     }
 
     /// See [`Path::file_name`].
     #[inline]
     pub fn file_name(&self) -> Option<&OsStr> {
-        self.0.file_name()
+        self.0.path.file_name()
     }
 
     /// See [`Path::strip_prefix`].
     #[inline]
     pub fn strip_prefix<P>(&self, base: P) -> Result<&Path, StripPrefixError>
     where P: AsRef<Path> {
-        self.0.strip_prefix(base)
+        self.0.path.strip_prefix(base)
     }
 
     /// See [`Path::starts_with`].
     #[inline]
     pub fn starts_with<P>(&self, base: P) -> bool
     where P: AsRef<Path> {
-        self.0.starts_with(base)
+        self.0.path.starts_with(base)
     }
 
     /// See [`Path::ends_with`].
     #[inline]
     pub fn ends_with<P>(&self, child: P) -> bool
     where P: AsRef<Path> {
-        self.0.ends_with(child)
+        self.0.path.ends_with(child)
     }
 
     /// See [`Path::file_stem`].
     #[inline]
     pub fn file_stem(&self) -> Option<&OsStr> {
-        self.0.file_stem()
+        self.0.path.file_stem()
     }
 
     /// See [`Path::extension`].
     #[inline]
     pub fn extension(&self) -> Option<&OsStr> {
-        self.0.extension()
+        self.0.path.extension()
     }
 
     /// See [`Path::join`].
     #[inline]
     pub fn join<P>(&self, path: P) -> PathBuf
     where P: AsRef<Path> {
-        self.0.join(path)
+        self.0.path.join(path)
     }
 
     /// See [`Path::with_file_name`].
     #[inline]
     pub fn with_file_name<S>(&self, file: S) -> PathBuf
     where S: AsRef<OsStr> {
-        self.0.with_file_name(file)
+        self.0.path.with_file_name(file)
     }
 
     /// See [`Path::with_extension`].
     #[inline]
     pub fn with_extension<S>(&self, file: S) -> PathBuf
     where S: AsRef<OsStr> {
-        self.0.with_extension(file)
+        self.0.path.with_extension(file)
     }
 
     /// See [`Path::components`].
     #[inline]
     pub fn components(&self) -> Components<'_> {
-        self.0.components()
+        self.0.path.components()
     }
 
     /// See [`Path::iter`].
     #[inline]
     pub fn iter(&self) -> Iter<'_> {
-        self.0.iter()
+        self.0.path.iter()
     }
 
     /// See [`Path::display`].
     #[inline]
     pub fn display(&self) -> std::path::Display<'_> {
-        self.0.display()
+        self.0.path.display()
     }
 
     /// See [`Path::metadata`].
     #[inline]
     pub fn metadata(&self) -> Result<Metadata, Error> {
-        self.0.metadata()
+        if !self.is_virtual() {
+            self.0.path.metadata()
+        } else {
+            Err(not_found())
+        }
     }
 
     /// See [`Path::symlink_metadata`].
     #[inline]
     pub fn symlink_metadata(&self) -> Result<Metadata, Error> {
-        self.0.symlink_metadata()
+        if !self.is_virtual() {
+            self.0.path.symlink_metadata()
+        } else {
+            Err(not_found())
+        }
     }
 
     /// See [`Path::read_link`].
     #[inline]
     pub fn read_link(&self) -> Result<PathBuf, Error> {
-        self.0.read_link()
+        if !self.is_virtual() {
+            self.0.path.read_link()
+        } else {
+            Err(not_found())
+        }
     }
 
     /// See [`Path::exists`].
     #[inline]
     pub fn exists(&self) -> bool {
-        self.0.exists()
+        if !self.is_virtual() {
+            self.0.path.exists()
+        } else {
+            false
+        }
     }
 
     /// See [`Path::is_file`].
     #[inline]
     pub fn is_file(&self) -> bool {
-        self.0.is_file()
+        if !self.is_virtual() {
+            self.0.path.is_file()
+        } else {
+            false
+        }
     }
 
     /// See [`Path::is_dir`].
     #[inline]
     pub fn is_dir(&self) -> bool {
-        self.0.is_dir()
+        if !self.is_virtual() {
+            self.0.path.is_dir()
+        } else {
+            false
+        }
     }
 }
 
 impl AsRef<OsStr> for Filename<'_> {
     #[inline]
     fn as_ref(&self) -> &OsStr {
-        self.0.as_ref()
+        self.0.path.as_ref()
     }
 }
 
 impl Debug for Filename<'_> {
     #[inline]
     fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
-        self.0.fmt(formatter)
+        if !self.is_virtual() {
+            self.0.path.fmt(formatter)
+        } else {
+            Display::fmt(self, formatter)
+        }
     }
 }
 
 impl Display for Filename<'_> {
     #[inline]
     fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
-        self.0.fmt(formatter)
+        if !self.is_virtual() {
+            Debug::fmt(self.display_path(), formatter)
+        } else if self.0.path.as_os_str().is_empty() {
+            write!(formatter, "<{}>", self.0.kind)
+        } else {
+            write!(formatter, "<{}:{}>", self.0.kind, self.0.path.display())
+        }
+    }
+}
+
+impl Display for FileKind {
+    #[inline]
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileKind::Disk => write!(formatter, "disk"),
+            FileKind::Stdin => write!(formatter, "stdin"),
+            FileKind::Repl => write!(formatter, "repl"),
+            FileKind::Generated => write!(formatter, "generated"),
+            FileKind::Memory => write!(formatter, "memory"),
+            FileKind::Archive => write!(formatter, "archive")
+        }
     }
 }
 
@@ -257,7 +487,7 @@ impl Eq for Filename<'_> {}
 impl<'a> From<Filename<'a>> for Cow<'a, Path> {
     #[inline]
     fn from(f: Filename<'a>) -> Cow<'a, Path> {
-        Cow::from(f.0)
+        Cow::from(f.0.path.as_path())
     }
 }
 
@@ -275,7 +505,7 @@ impl<'a> IntoIterator for Filename<'a> {
 
     #[inline]
     fn into_iter(self) -> Iter<'a> {
-        self.0.into_iter()
+        self.0.path.iter()
     }
 }
 
@@ -289,140 +519,98 @@ impl Ord for Filename<'_> {
 impl<'a, 'b> PartialEq<&'a OsStr> for Filename<'b> {
     #[inline]
     fn eq(&self, other: &&'a OsStr) -> bool {
-        match PathBuf::from(other).canonicalize() {
-            Ok(pathbuf) => self.0.eq(&pathbuf),
-            Err(_) => false
-        }
+        self.eq_path(Path::new(*other))
     }
 }
 
-impl<'a, 'b> PartialEq<Filename<'b>> for &'a OsStr {
+impl<'b> PartialEq<Filename<'b>> for &OsStr {
     #[inline]
     fn eq(&self, other: &Filename<'b>) -> bool {
-        match PathBuf::from(self).canonicalize() {
-            Ok(pathbuf) => pathbuf.eq(other.0),
-            Err(_) => false
-        }
+        other.eq_path(Path::new(*self))
     }
 }
 
 impl<'a, 'b> PartialEq<Filename<'a>> for Cow<'b, Path> {
     #[inline]
     fn eq(&self, other: &Filename<'a>) -> bool {
-        match self.canonicalize() {
-            Ok(pathbuf) => pathbuf.eq(other.0),
-            Err(_) => false
-        }
+        other.eq_path(self)
     }
 }
 
 impl<'a, 'b> PartialEq<Filename<'a>> for Cow<'b, OsStr> {
     #[inline]
     fn eq(&self, other: &Filename<'a>) -> bool {
-        match PathBuf::from(self).canonicalize() {
-            Ok(pathbuf) => pathbuf.eq(other.0),
-            Err(_) => false
-        }
+        other.eq_path(Path::new(&**self))
     }
 }
 
 impl PartialEq<Filename<'_>> for OsStr {
     #[inline]
     fn eq(&self, other: &Filename<'_>) -> bool {
-        match PathBuf::from(self).canonicalize() {
-            Ok(pathbuf) => pathbuf.eq(other.0),
-            Err(_) => false
-        }
+        other.eq_path(Path::new(self))
     }
 }
 
 impl PartialEq<Filename<'_>> for OsString {
     #[inline]
     fn eq(&self, other: &Filename<'_>) -> bool {
-        match PathBuf::from(self).canonicalize() {
-            Ok(pathbuf) => pathbuf.eq(other.0),
-            Err(_) => false
-        }
+        other.eq_path(Path::new(self))
     }
 }
 
 impl PartialEq<Filename<'_>> for Path {
     #[inline]
     fn eq(&self, other: &Filename<'_>) -> bool {
-        match self.canonicalize() {
-            Ok(pathbuf) => pathbuf.eq(other.0),
-            Err(_) => false
-        }
+        other.eq_path(self)
     }
 }
 
 impl PartialEq<Filename<'_>> for PathBuf {
     #[inline]
     fn eq(&self, other: &Filename<'_>) -> bool {
-        match self.canonicalize() {
-            Ok(pathbuf) => pathbuf.eq(other.0),
-            Err(_) => false
-        }
+        other.eq_path(self.as_path())
     }
 }
 
 impl<'a, 'b> PartialEq<Cow<'a, Path>> for Filename<'b> {
     #[inline]
     fn eq(&self, other: &Cow<'a, Path>) -> bool {
-        match other.canonicalize() {
-            Ok(pathbuf) => self.0.eq(&pathbuf),
-            Err(_) => false
-        }
+        self.eq_path(other)
     }
 }
 
 impl<'a, 'b> PartialEq<Cow<'a, OsStr>> for Filename<'b> {
     #[inline]
     fn eq(&self, other: &Cow<'a, OsStr>) -> bool {
-        match PathBuf::from(other).canonicalize() {
-            Ok(pathbuf) => self.0.eq(&pathbuf),
-            Err(_) => false
-        }
+        self.eq_path(Path::new(&**other))
     }
 }
 
 impl PartialEq<OsStr> for Filename<'_> {
     #[inline]
     fn eq(&self, other: &OsStr) -> bool {
-        match PathBuf::from(other).canonicalize() {
-            Ok(pathbuf) => self.0.eq(&pathbuf),
-            Err(_) => false
-        }
+        self.eq_path(Path::new(other))
     }
 }
 
 impl PartialEq<OsString> for Filename<'_> {
     #[inline]
     fn eq(&self, other: &OsString) -> bool {
-        match PathBuf::from(other).canonicalize() {
-            Ok(pathbuf) => self.0.eq(&pathbuf),
-            Err(_) => false
-        }
+        self.eq_path(Path::new(other))
     }
 }
 
 impl PartialEq<Path> for Filename<'_> {
     #[inline]
     fn eq(&self, other: &Path) -> bool {
-        match other.canonicalize() {
-            Ok(pathbuf) => self.0.eq(&pathbuf),
-            Err(_) => false
-        }
+        self.eq_path(other)
     }
 }
 
 impl PartialEq<PathBuf> for Filename<'_> {
     #[inline]
     fn eq(&self, other: &PathBuf) -> bool {
-        match other.canonicalize() {
-            Ok(pathbuf) => self.0.eq(&pathbuf),
-            Err(_) => false
-        }
+        self.eq_path(other.as_path())
     }
 }
 
@@ -436,147 +624,105 @@ impl PartialEq for Filename<'_> {
 impl<'a, 'b> PartialOrd<&'a OsStr> for Filename<'b> {
     #[inline]
     fn partial_cmp(&self, other: &&'a OsStr) -> Option<Ordering> {
-        match PathBuf::from(other).canonicalize() {
-            Ok(pathbuf) => self.0.partial_cmp(&pathbuf),
-            Err(_) => None
-        }
+        self.cmp_path(Path::new(*other))
     }
 }
 
-impl<'a, 'b> PartialOrd<Filename<'b>> for &'a OsStr {
+impl<'b> PartialOrd<Filename<'b>> for &OsStr {
     #[inline]
     fn partial_cmp(&self, other: &Filename<'b>) -> Option<Ordering> {
-        match PathBuf::from(self).canonicalize() {
-            Ok(pathbuf) => pathbuf.partial_cmp(other.0),
-            Err(_) => None
-        }
+        other.cmp_path(Path::new(*self)).map(Ordering::reverse)
     }
 }
 
 impl<'a, 'b> PartialOrd<Filename<'a>> for Cow<'b, Path> {
     #[inline]
     fn partial_cmp(&self, other: &Filename<'a>) -> Option<Ordering> {
-        match self.canonicalize() {
-            Ok(pathbuf) => pathbuf.partial_cmp(other.0),
-            Err(_) => None
-        }
+        other.cmp_path(self).map(Ordering::reverse)
     }
 }
 
 impl<'a, 'b> PartialOrd<Filename<'a>> for Cow<'b, OsStr> {
     #[inline]
     fn partial_cmp(&self, other: &Filename<'a>) -> Option<Ordering> {
-        match PathBuf::from(self).canonicalize() {
-            Ok(pathbuf) => pathbuf.partial_cmp(other.0),
-            Err(_) => None
-        }
+        other.cmp_path(Path::new(&**self)).map(Ordering::reverse)
     }
 }
 
 impl PartialOrd<Filename<'_>> for OsStr {
     #[inline]
     fn partial_cmp(&self, other: &Filename<'_>) -> Option<Ordering> {
-        match PathBuf::from(self).canonicalize() {
-            Ok(pathbuf) => pathbuf.partial_cmp(other.0),
-            Err(_) => None
-        }
+        other.cmp_path(Path::new(self)).map(Ordering::reverse)
     }
 }
 
 impl PartialOrd<Filename<'_>> for OsString {
     #[inline]
     fn partial_cmp(&self, other: &Filename<'_>) -> Option<Ordering> {
-        match PathBuf::from(self).canonicalize() {
-            Ok(pathbuf) => pathbuf.partial_cmp(other.0),
-            Err(_) => None
-        }
+        other.cmp_path(Path::new(self)).map(Ordering::reverse)
     }
 }
 
 impl PartialOrd<Filename<'_>> for Path {
     #[inline]
     fn partial_cmp(&self, other: &Filename<'_>) -> Option<Ordering> {
-        match self.canonicalize() {
-            Ok(pathbuf) => pathbuf.partial_cmp(other.0),
-            Err(_) => None
-        }
+        other.cmp_path(self).map(Ordering::reverse)
     }
 }
 
 impl PartialOrd<Filename<'_>> for PathBuf {
     #[inline]
     fn partial_cmp(&self, other: &Filename<'_>) -> Option<Ordering> {
-        match self.canonicalize() {
-            Ok(pathbuf) => pathbuf.partial_cmp(other.0),
-            Err(_) => None
-        }
+        other.cmp_path(self.as_path()).map(Ordering::reverse)
     }
 }
 
 impl<'a, 'b> PartialOrd<Cow<'a, Path>> for Filename<'b> {
     #[inline]
     fn partial_cmp(&self, other: &Cow<'a, Path>) -> Option<Ordering> {
-        match other.canonicalize() {
-            Ok(pathbuf) => self.0.partial_cmp(&pathbuf),
-            Err(_) => None
-        }
+        self.cmp_path(other)
     }
 }
 
 impl<'a, 'b> PartialOrd<Cow<'a, OsStr>> for Filename<'b> {
     #[inline]
     fn partial_cmp(&self, other: &Cow<'a, OsStr>) -> Option<Ordering> {
-        match PathBuf::from(other).canonicalize() {
-            Ok(pathbuf) => self.0.partial_cmp(&pathbuf),
-            Err(_) => None
-        }
+        self.cmp_path(Path::new(&**other))
     }
 }
 
 impl PartialOrd<OsStr> for Filename<'_> {
     #[inline]
     fn partial_cmp(&self, other: &OsStr) -> Option<Ordering> {
-        match PathBuf::from(other).canonicalize() {
-            Ok(pathbuf) => self.0.partial_cmp(&pathbuf),
-            Err(_) => None
-        }
+        self.cmp_path(Path::new(other))
     }
 }
 
 impl PartialOrd<OsString> for Filename<'_> {
     #[inline]
     fn partial_cmp(&self, other: &OsString) -> Option<Ordering> {
-        match PathBuf::from(other).canonicalize() {
-            Ok(pathbuf) => self.0.partial_cmp(&pathbuf),
-            Err(_) => None
-        }
+        self.cmp_path(Path::new(other))
     }
 }
 
 impl PartialOrd<Path> for Filename<'_> {
     #[inline]
     fn partial_cmp(&self, other: &Path) -> Option<Ordering> {
-        match other.canonicalize() {
-            Ok(pathbuf) => self.0.partial_cmp(&pathbuf),
-            Err(_) => None
-        }
+        self.cmp_path(other)
     }
 }
 
 impl PartialOrd<PathBuf> for Filename<'_> {
     #[inline]
     fn partial_cmp(&self, other: &PathBuf) -> Option<Ordering> {
-        match other.canonicalize() {
-            Ok(pathbuf) => self.0.partial_cmp(&pathbuf),
-            Err(_) => None
-        }
+        self.cmp_path(other.as_path())
     }
 }
 
 impl PartialOrd for Filename<'_> {
     #[inline]
     fn partial_cmp(&self, other: &Filename<'_>) -> Option<Ordering> {
-        self.id().partial_cmp(&other.id())
+        Some(self.cmp(other))
     }
 }
 
@@ -584,53 +730,324 @@ impl<'a> Filenames<'a> {
     /// Create a new `Filenames`.
     #[inline]
     pub fn new() -> Filenames<'a> {
-        Filenames { lifetime: PhantomData, interned: HashMap::new() }
+        Filenames::with_capacity(0)
     }
 
     /// Create a new `Filenames` with a size hint.
     #[inline]
     pub fn with_capacity(size: usize) -> Filenames<'a> {
+        let cwd = env::current_dir().and_then(|cwd| cwd.canonicalize()).ok();
+
         Filenames { interned: HashMap::with_capacity(size),
-                    lifetime: PhantomData }
+                    virtuals: HashMap::new(), lifetime: PhantomData,
+                    mode: FilenameDisplay::Canonical, base: None,
+                    remaps: Vec::new(), cwd: cwd.map(Arc::from),
+                    spellings: HashMap::new() }
     }
 
     /// Shring down this `Filenames` to fit the current contents.
     #[inline]
     pub fn shrink_to_fit(&mut self) {
-        self.interned.shrink_to_fit()
+        self.interned.shrink_to_fit();
+        self.virtuals.shrink_to_fit();
+        self.spellings.shrink_to_fit()
+    }
+
+    /// Get the current [`FilenameDisplay`] mode.
+    #[inline]
+    pub fn display_mode(&self) -> FilenameDisplay {
+        self.mode
+    }
+
+    /// Set the [`FilenameDisplay`] mode.
+    ///
+    /// This affects [`Filename`]s created by this `Filenames` from
+    /// now on.  The display form of a `Filename` is fixed when it is
+    /// first created, so this should be set before creating any.
+    #[inline]
+    pub fn set_display_mode(&mut self, mode: FilenameDisplay) {
+        self.mode = mode
+    }
+
+    /// Get the base directory for [`FilenameDisplay::Relative`].
+    #[inline]
+    pub fn base(&self) -> Option<&Path> {
+        self.base.as_deref()
+    }
+
+    /// Set the base directory for [`FilenameDisplay::Relative`].
+    ///
+    /// This is typically the working directory or the project root.
+    /// It fails if `base` cannot be canonicalized.  Like
+    /// [`set_display_mode`](Filenames::set_display_mode), this
+    /// affects [`Filename`]s created from now on.
+    #[inline]
+    pub fn set_base(&mut self, base: &Path) -> Result<(), Error> {
+        self.base = Some(base.canonicalize()?);
+
+        Ok(())
+    }
+
+    /// Add a path prefix rewrite, replacing `from` with `to`.
+    ///
+    /// Rewrites apply when a [`Filename`] is displayed (see
+    /// [`Filename::display_path`]) or serialized (see
+    /// [`Filename::remapped_path`]), but never when it is opened or
+    /// compared.  This can be used to replace machine-specific paths
+    /// in diagnostics and generated artefacts, to make builds
+    /// reproducible.
+    ///
+    /// Prefixes are matched by whole path components, and `from` is
+    /// not canonicalized.  If more than one rewrite matches, the one
+    /// added last is used.  Like
+    /// [`set_display_mode`](Filenames::set_display_mode), this
+    /// affects [`Filename`]s created from now on.
+    #[inline]
+    pub fn add_remap(&mut self, from: &Path, to: &Path) {
+        self.remaps.push((from.to_path_buf(), to.to_path_buf()))
+    }
+
+    /// Remove all path prefix rewrites.
+    #[inline]
+    pub fn clear_remaps(&mut self) {
+        self.remaps.clear()
+    }
+
+    /// Get all path prefix rewrites, in the order they were added.
+    #[inline]
+    pub fn remaps(&self) -> &[(PathBuf, PathBuf)] {
+        &self.remaps
+    }
+
+    /// Undo the path prefix rewrites on `path`.
+    ///
+    /// This converts a path produced by [`Filename::remapped_path`]
+    /// back into one that can be opened, provided the same rewrites
+    /// have been added to this `Filenames`.  Paths that do not match
+    /// any rewrite are returned unchanged.
+    pub fn unmap<'b>(&self, path: &'b Path) -> Cow<'b, Path> {
+        for (from, to) in self.remaps.iter().rev() {
+            if let Ok(rest) = path.strip_prefix(to) {
+                return Cow::from(join(from, rest));
+            }
+        }
+
+        Cow::from(path)
+    }
+
+    /// Find the `Filename` for `path`, if it has already been created.
+    ///
+    /// Unlike [`filename`](Filenames::filename), this never accesses
+    /// the filesystem, so it can be used in hot loops, and on files
+    /// that have since been deleted.  Paths are resolved lexically,
+    /// relative to the working directory when this `Filenames` was
+    /// created, which does not account for symbolic links.  Paths
+    /// that have previously been supplied to
+    /// [`filename`](Filenames::filename) and resolved differently
+    /// (for example, through a symbolic link) are found in a cache.
+    ///
+    /// Comparisons between `Filename`s and paths resolve paths
+    /// lexically in the same way, but only recognize the path first
+    /// supplied for each file, and not the others in the cache.
+    pub fn find(&self, path: &Path) -> Option<Filename<'a>> {
+        let resolved = lexical(self.cwd.as_deref(), path)?;
+        let canonical = match self.spellings.get(resolved.as_ref()) {
+            Some(canonical) => canonical.as_path(),
+            None => resolved.as_ref()
+        };
+
+        self.interned.get(canonical).map(|data| Self::make(data))
+    }
+
+    /// Internal function to make a filename from interned data.
+    #[inline]
+    fn make(data: &FileData) -> Filename<'a> {
+        unsafe {
+            let ptr = data as *const FileData;
+
+            Filename(&*ptr)
+        }
     }
 
     /// Create a `Filename`.
+    ///
+    /// The `Filename` is identified by the canonical form of `path`.
+    /// The first `path` supplied for a given file is remembered, and
+    /// can be used to display it (see [`FilenameDisplay`]).
+    ///
+    /// `path` is canonicalized on every call, so relative paths are
+    /// resolved relative to the current working directory, and paths
+    /// to files that no longer exist are errors.  If the spelling
+    /// does not resolve lexically to the canonical path, it is
+    /// remembered for [`find`](Filenames::find), which does not
+    /// access the filesystem.
     pub fn filename(&mut self, path: &Path) ->  Result<Filename<'a>, Error> {
-        let path = path.canonicalize()?;
+        let canonical = path.canonicalize()?;
+
+        if let Some(resolved) = lexical(self.cwd.as_deref(), path) {
+            if resolved == canonical.as_path() {
+                self.spellings.remove(resolved.as_ref());
+            } else {
+                self.spellings.insert(resolved.into_owned(),
+                                      canonical.clone());
+            }
+        }
 
-        match self.interned.entry(path) {
-            Entry::Occupied(ent) => {
-                unsafe {
-                    let ptr = ent.key().as_ref() as *const Path;
+        Ok(self.intern(canonical, path))
+    }
 
-                    Ok(Filename(&*ptr))
-                }
-            },
+    /// Create a `Filename` for a file on disk from its canonical
+    /// path, without accessing the filesystem.
+    ///
+    /// `path` is used as given, so it should be a path previously
+    /// produced by canonicalization, such as one read back from
+    /// serialized data.  The file need not exist any more.
+    #[inline]
+    pub fn canonical_filename(&mut self, path: &Path) -> Filename<'a> {
+        self.intern(path.to_path_buf(), path)
+    }
+
+    /// Internal function to intern a file on disk by its canonical
+    /// path, remembering `supplied` if it is new.
+    fn intern(&mut self, canonical: PathBuf, supplied: &Path) ->
+        Filename<'a> {
+        match self.interned.entry(canonical) {
+            Entry::Occupied(ent) => Self::make(ent.get()),
+            Entry::Vacant(ent) => {
+                let path = ent.key();
+                let display = match (self.mode, &self.base) {
+                    (FilenameDisplay::Canonical, _) => path.as_path(),
+                    (FilenameDisplay::Relative, Some(base)) =>
+                        match path.strip_prefix(base) {
+                            Ok(rel) if rel.as_os_str().is_empty() =>
+                                Path::new("."),
+                            Ok(rel) => rel,
+                            Err(_) => path.as_path()
+                        },
+                    _ => supplied
+                };
+                let display = remap(&self.remaps, display)
+                    .unwrap_or_else(|| display.to_path_buf());
+                let remapped = remap(&self.remaps, path)
+                    .unwrap_or_else(|| path.clone());
+                let data = FileData { path: path.clone(),
+                                      supplied: supplied.to_path_buf(),
+                                      kind: FileKind::Disk, display,
+                                      remapped, cwd: self.cwd.clone() };
+
+                Self::make(ent.insert(Box::new(data)))
+            }
+        }
+    }
+
+    /// Create a virtual `Filename` of kind `kind`, identified by `name`.
+    ///
+    /// Virtual filenames can be used anywhere a `Filename` for a
+    /// file on disk can, but do not need to exist.  They display as
+    /// `<kind:name>`, or as `<kind>` if `name` is empty (for
+    /// example, `<generated:foo>` or `<stdin>`).  Virtual filenames
+    /// are never equal to filenames for files on disk, or to virtual
+    /// filenames of other kinds.
+    ///
+    /// The argument `kind` must not be [`FileKind::Disk`].
+    pub fn virtual_filename(&mut self, kind: FileKind, name: &str) ->
+        Filename<'a> {
+        assert!(kind != FileKind::Disk);
+
+        match self.virtuals.entry((kind, String::from(name))) {
+            Entry::Occupied(ent) => Self::make(ent.get()),
             Entry::Vacant(ent) => {
-                unsafe {
-                    let ptr = ent.key().as_ref() as *const Path;
+                let data = FileData { path: PathBuf::from(name),
+                                      supplied: PathBuf::from(name),
+                                      kind, display: PathBuf::from(name),
+                                      remapped: PathBuf::from(name),
+                                      cwd: None };
+
+                Self::make(ent.insert(Box::new(data)))
+            }
+        }
+    }
 
-                    ent.insert(());
+    /// Get the virtual `Filename` for standard input.
+    #[inline]
+    pub fn stdin(&mut self) -> Filename<'a> {
+        self.virtual_filename(FileKind::Stdin, "")
+    }
+}
 
-                    Ok(Filename(&*ptr))
-                }
+impl Default for Filenames<'_> {
+    #[inline]
+    fn default() -> Self {
+        Filenames::new()
+    }
+}
+
+/// Resolve `path` lexically to an absolute path, relative to `cwd`,
+/// without accessing the filesystem.
+fn lexical<'b>(cwd: Option<&Path>, path: &'b Path) -> Option<Cow<'b, Path>> {
+    if path.is_absolute() {
+        Some(normalize(path))
+    } else {
+        cwd.map(|cwd| Cow::from(normalize(&cwd.join(path)).into_owned()))
+    }
+}
+
+/// Lexically normalize an absolute `path`, removing `.` and `..`
+/// components.
+fn normalize(path: &Path) -> Cow<'_, Path> {
+    let normal = path.components().all(|component| {
+        !matches!(component, Component::CurDir | Component::ParentDir)
+    });
+
+    if normal {
+        Cow::from(path)
+    } else {
+        let mut out = PathBuf::new();
+
+        for component in path.components() {
+            match component {
+                Component::CurDir => (),
+                Component::ParentDir => {
+                    out.pop();
+                },
+                component => out.push(component)
             }
         }
+
+        Cow::from(out)
+    }
+}
+
+/// Apply the last matching rewrite in `remaps` to `path`, if any.
+fn remap(remaps: &[(PathBuf, PathBuf)], path: &Path) -> Option<PathBuf> {
+    remaps.iter().rev().find_map(|(from, to)| {
+        path.strip_prefix(from).ok().map(|rest| join(to, rest))
+    })
+}
+
+/// Join `rest` onto `prefix`, without adding a trailing separator
+/// when `rest` is empty.
+#[inline]
+fn join(prefix: &Path, rest: &Path) -> PathBuf {
+    if rest.as_os_str().is_empty() {
+        prefix.to_path_buf()
+    } else {
+        prefix.join(rest)
     }
 }
 
+/// Get the error for filesystem access on a virtual file.
+#[inline]
+fn not_found() -> Error {
+    Error::new(ErrorKind::NotFound, "virtual file")
+}
+
 impl<'a> FileOffsets<'a> {
     /// Create a `FileOffsets` from its components.
     #[inline]
     pub fn new(filename: Filename<'a>, line_offsets: LineOffsets) -> Self {
         FileOffsets { line_offsets: Nondistinct::from(line_offsets),
-                      filename: filename }
+                      filename }
     }
 
     /// Get the [`Filename`] for this `FileOffsets`.
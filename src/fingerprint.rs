@@ -0,0 +1,138 @@
+use crate::files::Filename;
+use std::fs;
+use std::fs::Metadata;
+use std::io::Error;
+use std::time::SystemTime;
+#[cfg(feature = "sha256")]
+use sha2::Digest;
+#[cfg(feature = "sha256")]
+use sha2::Sha256;
+use xxhash_rust::xxh3::xxh3_128;
+
+/// Hash of the contents of a file.
+///
+/// This always contains a fast, non-cryptographic 128-bit hash
+/// (XXH3), which is suitable for change detection.  If the `sha256`
+/// feature is enabled, it also contains a SHA-256 digest, which is
+/// suitable for content-addressed caches.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ContentHash {
+    /// The 128-bit XXH3 hash.
+    hash: u128,
+    /// The SHA-256 digest, if enabled.
+    sha256: Option<[u8; 32]>
+}
+
+/// Fingerprint of a file when it was loaded.
+///
+/// This records the [`ContentHash`] of the file, along with its size
+/// and modification time, as they were before it was read.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Fingerprint {
+    /// Hash of the contents.
+    hash: ContentHash,
+    /// Size of the file, in bytes.
+    size: u64,
+    /// Modification time of the file, if available.
+    modified: Option<SystemTime>
+}
+
+impl ContentHash {
+    /// Compute the `ContentHash` of `content`.
+    pub fn of(content: &[u8]) -> Self {
+        ContentHash { hash: xxh3_128(content), sha256: sha256(content) }
+    }
+
+    /// Get the 128-bit hash.
+    #[inline]
+    pub fn hash128(&self) -> u128 {
+        self.hash
+    }
+
+    /// Get a 64-bit hash.
+    ///
+    /// This is the lower half of [`hash128`](ContentHash::hash128).
+    #[inline]
+    pub fn hash64(&self) -> u64 {
+        self.hash as u64
+    }
+
+    /// Get the SHA-256 digest.
+    ///
+    /// This is only available if the `sha256` feature is enabled.
+    #[inline]
+    pub fn sha256(&self) -> Option<&[u8; 32]> {
+        self.sha256.as_ref()
+    }
+}
+
+impl Fingerprint {
+    /// Create a `Fingerprint` from the `metadata` of a file and the
+    /// `hash` of its contents.
+    ///
+    /// `metadata` must be read before the contents.  A change made
+    /// while the file is being read then leaves a newer modification
+    /// time on disk than the one recorded, so it is detected by
+    /// [`changed`](Fingerprint::changed).
+    #[inline]
+    pub fn new(metadata: &Metadata, hash: ContentHash) -> Self {
+        Fingerprint { hash, size: metadata.len(),
+                      modified: metadata.modified().ok() }
+    }
+
+    /// Get the [`ContentHash`] of the file.
+    #[inline]
+    pub fn hash(&self) -> &ContentHash {
+        &self.hash
+    }
+
+    /// Get the size of the file, in bytes.
+    #[inline]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Get the modification time of the file, if available.
+    #[inline]
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    /// Check whether `filename` has changed since this `Fingerprint`
+    /// was made.
+    ///
+    /// If the size differs, the file has changed.  If the size and
+    /// modification time are the same, it is assumed to be
+    /// unchanged.  Otherwise, the file is read and its hash is
+    /// compared, so that touching a file without changing its
+    /// contents does not count as a change.  A file that can no
+    /// longer be read results in an error.
+    pub fn changed(&self, filename: Filename<'_>) -> Result<bool, Error> {
+        let metadata = filename.metadata()?;
+
+        if metadata.len() != self.size {
+            Ok(true)
+        } else if self.modified.is_some() &&
+                  metadata.modified().ok() == self.modified {
+            Ok(false)
+        } else {
+            let content = fs::read(filename.as_os_str())?;
+
+            Ok(ContentHash::of(&content).hash != self.hash.hash)
+        }
+    }
+}
+
+/// Compute the SHA-256 digest of `content`.
+#[cfg(feature = "sha256")]
+#[inline]
+fn sha256(content: &[u8]) -> Option<[u8; 32]> {
+    Some(Sha256::digest(content).into())
+}
+
+/// Compute the SHA-256 digest of `content`.
+#[cfg(not(feature = "sha256"))]
+#[inline]
+fn sha256(_content: &[u8]) -> Option<[u8; 32]> {
+    None
+}
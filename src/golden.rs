@@ -0,0 +1,306 @@
+use crate::messages::MessagePositions;
+use crate::messages::MessageWriter;
+use crate::position::FilePosition;
+use std::convert::TryFrom;
+use std::env;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fs;
+use std::io::Error;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::path::PathBuf;
+use termcolor::Buffer;
+
+/// Name of the environment variable that causes expectation files to
+/// be overwritten with the actual output.
+pub const BLESS_VAR: &str = "COMPILER_TOOLS_BLESS";
+
+/// Harness for golden-file tests of message output.
+///
+/// Messages are rendered through a [`MessageWriter`] into a
+/// [`Buffer`], both without and with ANSI colors, and compared against
+/// expectation files named `<name>.stderr` and `<name>.color.stderr`
+/// in a directory.  Before comparing, paths registered with
+/// [`normalize_path`](GoldenTest::normalize_path) are replaced with
+/// placeholders, so expectations do not depend on where the tests
+/// run.
+///
+/// If the [`BLESS_VAR`] environment variable is set to anything but
+/// `0` or the empty string, the expectation files are written with
+/// the actual output instead of being compared.
+///
+/// This is only available with the `golden` feature.
+pub struct GoldenTest {
+    /// Directory containing the expectation files.
+    dir: PathBuf,
+    /// Whether to write expectation files instead of comparing.
+    bless: bool,
+    /// Replacements applied to output, in order.
+    replacements: Vec<(String, String)>
+}
+
+/// Errors that can occur when checking golden files.
+#[derive(Debug)]
+pub enum GoldenError {
+    /// The expectation file could not be read or written.
+    Io {
+        /// The expectation file.
+        path: PathBuf,
+        /// The error.
+        err: Error
+    },
+    /// There is no expectation file.
+    Missing {
+        /// The expectation file.
+        path: PathBuf
+    },
+    /// The output differs from the expectation file.
+    Mismatch {
+        /// The expectation file.
+        path: PathBuf,
+        /// The contents of the expectation file.
+        expected: String,
+        /// The actual output.
+        actual: String
+    }
+}
+
+impl GoldenTest {
+    /// Create a new `GoldenTest` with expectation files in `dir`.
+    ///
+    /// Whether to bless output is taken from the environment.
+    pub fn new<P>(dir: P) -> Self
+    where P: AsRef<Path> {
+        let bless = env::var(BLESS_VAR)
+            .map(|val| !val.is_empty() && val != "0")
+            .unwrap_or(false);
+
+        GoldenTest { dir: dir.as_ref().to_path_buf(), bless,
+                     replacements: Vec::new() }
+    }
+
+    /// Get the directory containing the expectation files.
+    #[inline]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Get whether expectation files are written instead of compared.
+    #[inline]
+    pub fn bless(&self) -> bool {
+        self.bless
+    }
+
+    /// Set whether expectation files are written instead of compared.
+    #[inline]
+    pub fn set_bless(&mut self, bless: bool) {
+        self.bless = bless
+    }
+
+    /// Replace all occurrences of `from` in output with `to`.
+    #[inline]
+    pub fn add_replacement(&mut self, from: &str, to: &str) {
+        if !from.is_empty() {
+            self.replacements.push((String::from(from), String::from(to)))
+        }
+    }
+
+    /// Replace `path` in output with `placeholder`, such as `$DIR`.
+    ///
+    /// Both the path as given and its canonical form (if it differs)
+    /// are replaced, and the replaced text has its path separators
+    /// normalized to `/`.
+    pub fn normalize_path<P>(&mut self, path: P, placeholder: &str)
+    where P: AsRef<Path> {
+        let path = path.as_ref();
+
+        if let Ok(canon) = path.canonicalize() {
+            if canon != path {
+                self.add_replacement(&canon.display().to_string(),
+                                     placeholder);
+            }
+        }
+
+        self.add_replacement(&path.display().to_string(), placeholder);
+    }
+
+    /// Apply the replacements to `text`.
+    pub fn normalize(&self, text: &str) -> String {
+        let mut out = text.replace("\r\n", "\n");
+
+        for (from, to) in &self.replacements {
+            if out.contains(from.as_str()) {
+                out = replace_path(&out, from, to);
+            }
+        }
+
+        out
+    }
+
+    /// Render `msgs` through `writer`, with ANSI colors if `color` is
+    /// set.
+    pub fn render<'a, I, M, P, W>(writer: &W, msgs: I, color: bool) ->
+        Result<String, Error>
+    where I: IntoIterator<Item = &'a M>,
+          &'a FilePosition<'a>: TryFrom<&'a P>,
+          M: 'a + MessagePositions<P>,
+          W: MessageWriter,
+          P: 'a + Display {
+        let mut out = if color { Buffer::ansi() } else { Buffer::no_color() };
+
+        for msg in msgs {
+            writer.write_msg(msg, &mut out)?;
+        }
+
+        String::from_utf8(out.into_inner())
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))
+    }
+
+    /// Check `actual` against the expectation file `file`, after
+    /// normalizing it.
+    ///
+    /// When blessing, the expectation file is written instead.
+    pub fn check(&self, file: &str, actual: &str) -> Result<(), GoldenError> {
+        let path = self.dir.join(file);
+        let actual = self.normalize(actual);
+
+        if self.bless {
+            return fs::create_dir_all(&self.dir)
+                .and_then(|_| fs::write(&path, &actual))
+                .map_err(|err| GoldenError::Io { path, err });
+        }
+
+        match fs::read_to_string(&path) {
+            Ok(expected) => {
+                let expected = expected.replace("\r\n", "\n");
+
+                if expected == actual {
+                    Ok(())
+                } else {
+                    Err(GoldenError::Mismatch { path,
+                                                expected,
+                                                actual })
+                }
+            },
+            Err(err) if err.kind() == ErrorKind::NotFound =>
+                Err(GoldenError::Missing { path }),
+            Err(err) => Err(GoldenError::Io { path, err })
+        }
+    }
+
+    /// Check the output of `writer` for `msgs` against the expectation
+    /// files `<name>.stderr` (without colors) and `<name>.color.stderr`
+    /// (with ANSI colors).
+    pub fn check_messages<'a, I, M, P, W>(&self, name: &str, writer: &W,
+                                          msgs: I) ->
+        Result<(), GoldenError>
+    where I: IntoIterator<Item = &'a M>,
+          &'a FilePosition<'a>: TryFrom<&'a P>,
+          M: 'a + MessagePositions<P>,
+          W: MessageWriter,
+          P: 'a + Display {
+        let msgs: Vec<&'a M> = msgs.into_iter().collect();
+
+        for (suffix, color) in &[("stderr", false), ("color.stderr", true)] {
+            let file = format!("{}.{}", name, suffix);
+            let actual = GoldenTest::render(writer, msgs.iter().copied(),
+                                            *color)
+                .map_err(|err| GoldenError::Io { path: self.dir.join(&file),
+                                                 err })?;
+
+            self.check(&file, &actual)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check the output of `writer` for `msgs` as with
+    /// [`check_messages`](GoldenTest::check_messages), panicking with
+    /// a description of any difference.
+    pub fn assert_messages<'a, I, M, P, W>(&self, name: &str, writer: &W,
+                                           msgs: I)
+    where I: IntoIterator<Item = &'a M>,
+          &'a FilePosition<'a>: TryFrom<&'a P>,
+          M: 'a + MessagePositions<P>,
+          W: MessageWriter,
+          P: 'a + Display {
+        if let Err(err) = self.check_messages(name, writer, msgs) {
+            panic!("{}", err)
+        }
+    }
+}
+
+impl Display for GoldenError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            GoldenError::Io { path, err } =>
+                write!(f, "cannot access {}: {}", path.display(), err),
+            GoldenError::Missing { path } =>
+                write!(f, "missing expectation file {} (set {}=1 to \
+                           create it)",
+                       path.display(), BLESS_VAR),
+            GoldenError::Mismatch { path, expected, actual } => {
+                writeln!(f, "output differs from {} (set {}=1 to update \
+                             it):",
+                         path.display(), BLESS_VAR)?;
+
+                let expected: Vec<&str> = expected.lines().collect();
+                let actual: Vec<&str> = actual.lines().collect();
+
+                for idx in 0 .. expected.len().max(actual.len()) {
+                    let old = expected.get(idx);
+                    let new = actual.get(idx);
+
+                    if old != new {
+                        if let Some(old) = old {
+                            writeln!(f, "{:4} -{}", idx + 1,
+                                     old.escape_debug())?;
+                        }
+
+                        if let Some(new) = new {
+                            writeln!(f, "{:4} +{}", idx + 1,
+                                     new.escape_debug())?;
+                        }
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for GoldenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GoldenError::Io { err, .. } => Some(err),
+            _ => None
+        }
+    }
+}
+
+/// Replace occurrences of the path `from` in `text` with `to`,
+/// normalizing path separators in the rest of each path.
+fn replace_path(text: &str, from: &str, to: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(idx) = rest.find(from) {
+        out.push_str(&rest[.. idx]);
+        out.push_str(to);
+        rest = &rest[idx + from.len() ..];
+
+        // Normalize separators up to the end of the path.
+        let end = rest.find(|c: char| c.is_whitespace() || c == ':' ||
+                                      c == '"' || c == '\'')
+            .unwrap_or(rest.len());
+
+        out.push_str(&rest[.. end].replace('\\', "/"));
+        rest = &rest[end ..];
+    }
+
+    out.push_str(rest);
+
+    out
+}
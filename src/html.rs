@@ -0,0 +1,323 @@
+use crate::catalog::localized_brief;
+use crate::catalog::localized_detail;
+use crate::messages::MessagePositions;
+use crate::messages::Severity;
+use crate::messages::group_by_file;
+use crate::position::FilePosition;
+use crate::snippet::SnippetRenderer;
+use crate::sources::SourceContext;
+use crate::sources::SourceExcerpt;
+use crate::sources::Sources;
+use std::convert::TryFrom;
+use std::convert::TryInto;
+use std::fmt::Display;
+use std::io::Error;
+use std::io::Write;
+use termcolor::Color;
+
+/// Writer for self-contained HTML reports of a batch of messages.
+///
+/// Messages are grouped by the file of their first position, in the
+/// order the files first appear, and each message has an anchor
+/// (`msg-1`, `msg-2`, and so on).  Source excerpts are extracted in
+/// the same way as by [`MessageFullWriter`], with the highlighted
+/// region marked.
+///
+/// [`MessageFullWriter`]: crate::messages::MessageFullWriter
+pub struct HtmlReportWriter<'a> {
+    sources: &'a Sources<'a>,
+    /// Title of the report.
+    title: String,
+    /// Number of lines to show before the highlighted code.
+    leading: usize,
+    /// Number of lines to show after the highlighted code.
+    trailing: usize,
+    /// Renderer for source lines.
+    renderer: SnippetRenderer
+}
+
+/// Style sheet included in every report.
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+h2 { font-family: monospace; border-bottom: 1px solid #ccc; }
+.message { margin: 1em 0; padding: 0.5em; border-left: 4px solid #ccc; }
+.anchor { color: #999; text-decoration: none; margin-right: 0.5em; }
+.badge { color: white; font-weight: bold; padding: 0.1em 0.4em; \
+border-radius: 0.3em; }
+.brief { font-weight: bold; margin-left: 0.5em; }
+.position { font-family: monospace; margin-top: 0.5em; }
+.excerpt { background: #f6f6f6; padding: 0.5em; overflow-x: auto; }
+mark { background: none; font-weight: bold; text-decoration: underline; }
+.detail { white-space: pre-wrap; }
+";
+
+/// Get the CSS color for `color`.
+fn css_color(color: Color) -> String {
+    match color {
+        Color::Black => String::from("black"),
+        Color::Blue => String::from("blue"),
+        Color::Green => String::from("green"),
+        Color::Red => String::from("red"),
+        Color::Cyan => String::from("darkcyan"),
+        Color::Magenta => String::from("magenta"),
+        Color::Yellow => String::from("darkgoldenrod"),
+        Color::White => String::from("white"),
+        Color::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        _ => String::from("gray")
+    }
+}
+
+/// Escape `text` for use in HTML text or attribute values.
+pub fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c)
+        }
+    }
+
+    out
+}
+
+impl<'a> HtmlReportWriter<'a> {
+    /// Create a new `HtmlReportWriter` that gets context from
+    /// `sources`.
+    #[inline]
+    pub fn new(sources: &'a Sources<'a>) -> Self {
+        HtmlReportWriter { sources,
+                           title: String::from("Compiler Diagnostics"),
+                           leading: 0, trailing: 0,
+                           renderer: SnippetRenderer::new() }
+    }
+
+    /// Get the title of the report.
+    #[inline]
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Set the title of the report.
+    #[inline]
+    pub fn set_title(&mut self, title: &str) {
+        self.title = String::from(title)
+    }
+
+    /// Get the number of lines shown before and after the highlighted
+    /// code.
+    #[inline]
+    pub fn context_lines(&self) -> (usize, usize) {
+        (self.leading, self.trailing)
+    }
+
+    /// Set the number of lines shown before (`leading`) and after
+    /// (`trailing`) the highlighted code.
+    #[inline]
+    pub fn set_context_lines(&mut self, leading: usize, trailing: usize) {
+        self.leading = leading;
+        self.trailing = trailing;
+    }
+
+    /// Set the distance between tab stops when rendering source
+    /// lines.
+    #[inline]
+    pub fn set_tab_width(&mut self, tab_width: usize) {
+        self.renderer.set_tab_width(tab_width)
+    }
+
+    /// Write a report of all of `msgs` out to `out`.
+    pub fn write_report<'b, I, M, P, W>(&self, msgs: I, out: &mut W) ->
+        Result<(), Error>
+    where I: IntoIterator<Item = &'b M>,
+          &'b FilePosition<'b>: TryFrom<&'b P>,
+          M: 'b + MessagePositions<P>,
+          W: Write,
+          P: 'b + Display {
+        let msgs: Vec<&'b M> = msgs.into_iter().collect();
+        let mut counts = [0; 6];
+
+        for msg in &msgs {
+            counts[msg.severity() as usize] += 1;
+        }
+
+        let groups = group_by_file(msgs);
+        let title = escape_html(&self.title);
+
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html>")?;
+        writeln!(out, "<head>")?;
+        writeln!(out, "<meta charset=\"utf-8\">")?;
+        writeln!(out, "<title>{}</title>", title)?;
+        write!(out, "<style>\n{}</style>\n", STYLE)?;
+        writeln!(out, "</head>")?;
+        writeln!(out, "<body>")?;
+        writeln!(out, "<h1>{}</h1>", title)?;
+        self.write_summary(&counts, out)?;
+
+        let mut id = 0;
+
+        for (idx, (file, group)) in groups.iter().enumerate() {
+            let name = match file {
+                Some(filename) => escape_html(&filename.display_name()),
+                None => String::from("Other")
+            };
+
+            writeln!(out, "<section class=\"file\" id=\"file-{}\">",
+                     idx + 1)?;
+            writeln!(out, "<h2>{}</h2>", name)?;
+
+            for msg in group {
+                id += 1;
+                self.write_entry(*msg, id, out)?;
+            }
+
+            writeln!(out, "</section>")?;
+        }
+
+        writeln!(out, "</body>")?;
+        writeln!(out, "</html>")
+    }
+
+    /// Write the number of messages of each [`Severity`].
+    fn write_summary<W>(&self, counts: &[usize; 6], out: &mut W) ->
+        Result<(), Error>
+    where W: Write {
+        const SEVERITIES: [Severity; 6] = [
+            Severity::Internal, Severity::Error, Severity::Warning,
+            Severity::Lint, Severity::Remark, Severity::Info
+        ];
+
+        write!(out, "<p class=\"summary\">")?;
+
+        let mut first = true;
+
+        for severity in SEVERITIES.iter() {
+            let count = counts[*severity as usize];
+
+            if count > 0 {
+                if !first {
+                    write!(out, ", ")?;
+                }
+
+                write!(out, "{} {}", count,
+                       escape_html(&severity.to_string()))?;
+                first = false;
+            }
+        }
+
+        if first {
+            write!(out, "No messages")?;
+        }
+
+        writeln!(out, "</p>")
+    }
+
+    /// Write the entry for `msg`, with anchor `id`.
+    fn write_entry<'b, M, P, W>(&self, msg: &'b M, id: usize,
+                                out: &mut W) -> Result<(), Error>
+    where &'b FilePosition<'b>: TryFrom<&'b P>,
+          M: MessagePositions<P>,
+          W: Write,
+          P: 'b + Display {
+        let severity = msg.severity();
+
+        writeln!(out, "<div class=\"message\" id=\"msg-{}\" \
+                       style=\"border-color: {}\">",
+                 id, css_color(severity.color()))?;
+        writeln!(out, "<a class=\"anchor\" href=\"#msg-{}\">#{}</a>\
+                       <span class=\"badge\" \
+                       style=\"background-color: {}\">{}</span>\
+                       <span class=\"brief\">{}</span>",
+                 id, id, css_color(severity.color()),
+                 escape_html(&severity.to_string()),
+                 escape_html(&localized_brief(msg)))?;
+
+        for (label, pos, severity) in msg.positions() {
+            write!(out, "<div class=\"position\">")?;
+
+            if let Some(label) = label {
+                write!(out, "{} ", escape_html(label))?;
+            }
+
+            writeln!(out, "{}</div>", escape_html(&pos.to_string()))?;
+
+            let filepos: Result<&'b FilePosition<'b>, _> = pos.try_into();
+
+            if let Ok(FilePosition::Portion { offset, file_offsets }) =
+                filepos {
+                let excerpt = self.sources.try_get_excerpt(
+                    file_offsets.filename(), offset,
+                    self.leading, self.trailing
+                );
+
+                if let Ok(excerpt) = excerpt {
+                    self.write_excerpt(&excerpt, *severity, out)?;
+                }
+            }
+        }
+
+        let detail = localized_detail(msg);
+
+        if !detail.is_empty() {
+            writeln!(out, "<p class=\"detail\">{}</p>", escape_html(&detail))?;
+        }
+
+        writeln!(out, "</div>")
+    }
+
+    /// Write `excerpt`, highlighted for `severity`.
+    fn write_excerpt<W>(&self, excerpt: &SourceExcerpt<'_>,
+                        severity: Severity, out: &mut W) ->
+        Result<(), Error>
+    where W: Write {
+        let renderer = &self.renderer;
+        let mark = format!("<mark style=\"color: {}\">",
+                           css_color(severity.color()));
+
+        write!(out, "<pre class=\"excerpt\">")?;
+
+        for line in excerpt.leading {
+            writeln!(out, "{}", escape_html(&renderer.render(line, 0).0))?;
+        }
+
+        match excerpt.context {
+            SourceContext::Single { prefix, selected, suffix } => {
+                let (prefix, col) = renderer.render(prefix, 0);
+                let (selected, col) = renderer.render(selected, col);
+                let (suffix, _) = renderer.render(suffix, col);
+
+                writeln!(out, "{}{}{}</mark>{}", escape_html(&prefix), mark,
+                         escape_html(&selected), escape_html(&suffix))?;
+            },
+            SourceContext::Multiple { prefix, first, middle, last,
+                                      suffix } => {
+                let (prefix, col) = renderer.render(prefix, 0);
+                let (first, _) = renderer.render(first, col);
+                let (last, col) = renderer.render(last, 0);
+                let (suffix, _) = renderer.render(suffix, col);
+
+                writeln!(out, "{}{}{}", escape_html(&prefix), mark,
+                         escape_html(&first))?;
+
+                for line in middle {
+                    writeln!(out, "{}",
+                             escape_html(&renderer.render(line, 0).0))?;
+                }
+
+                writeln!(out, "{}</mark>{}", escape_html(&last),
+                         escape_html(&suffix))?;
+            }
+        }
+
+        for line in excerpt.trailing {
+            writeln!(out, "{}", escape_html(&renderer.render(line, 0).0))?;
+        }
+
+        writeln!(out, "</pre>")
+    }
+}
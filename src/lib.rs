@@ -1,7 +1,20 @@
+pub mod catalog;
+pub mod diagnostic;
+pub mod encoding;
 pub mod files;
+pub mod fingerprint;
+#[cfg(feature = "golden")]
+pub mod golden;
+pub mod html;
 pub mod lines;
 pub mod messages;
 pub mod nondistinct;
 pub mod position;
+pub mod resolve;
+pub mod snippet;
 pub mod sources;
+pub mod strtab;
+pub mod suggest;
 pub mod symbol;
+pub mod ui;
+pub mod xml;
@@ -134,7 +134,7 @@ impl LineOffsets {
     /// number and offset pair.
     #[inline]
     pub fn lookup(&self, pos: Offset) -> (usize, usize) {
-        if self.lines.len() > 0 {
+        if !self.lines.is_empty() {
             match self.lines.binary_search(&pos.0) {
                 Ok(idx) => (idx + 1, 0),
                 Err(idx) if idx > 0 => {
@@ -155,3 +155,10 @@ impl LineOffsets {
         self.lines.push(start)
     }
 }
+
+impl Default for LineOffsets {
+    #[inline]
+    fn default() -> Self {
+        LineOffsets::new()
+    }
+}
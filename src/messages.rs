@@ -1,6 +1,14 @@
+use crate::catalog::active_catalog;
+use crate::catalog::localized_brief;
+use crate::catalog::localized_detail;
+use crate::files::Filename;
 use crate::position::FilePosition;
+use crate::snippet::SnippetRenderer;
 use crate::sources::Sources;
 use crate::sources::SourceContext;
+use crate::sources::SourceExcerpt;
+use crate::sources::SourceLines;
+use std::borrow::Cow;
 use std::convert::TryFrom;
 use std::convert::TryInto;
 use std::fmt::Display;
@@ -10,6 +18,13 @@ use termcolor::Color;
 use termcolor::ColorSpec;
 use termcolor::WriteColor;
 
+/// Derive [`Message`](trait@Message) and [`MessagePositions`] for
+/// error types.
+///
+/// This is only available with the `derive` feature.
+#[cfg(feature = "derive")]
+pub use compiler_tools_derive::Message;
+
 /// Trait for compiler messages.
 pub trait Message {
     /// Get the [`Severity`] of the message.
@@ -28,6 +43,49 @@ pub trait Message {
     fn highlighting(&self) -> Highlighting {
         Highlighting::Foreground
     }
+
+    /// Get the code identifying the kind of message, such as `E0308`,
+    /// if there is one.
+    ///
+    /// The default behavior returns `None`.
+    #[inline]
+    fn code(&self) -> Option<&str> {
+        None
+    }
+
+    /// Get the key identifying the message in a [`Catalog`], if it
+    /// can be localized.
+    ///
+    /// The default behavior returns `None`.
+    ///
+    /// [`Catalog`]: crate::catalog::Catalog
+    #[inline]
+    fn key(&self) -> Option<&str> {
+        None
+    }
+
+    /// Get the value of the named argument `name`, for formatting the
+    /// message's text from a [`Catalog`].
+    ///
+    /// The default behavior returns `None`.
+    ///
+    /// [`Catalog`]: crate::catalog::Catalog
+    #[inline]
+    fn arg(&self, _name: &str) -> Option<String> {
+        None
+    }
+
+    /// Get the help note at the end of the
+    /// [detail](Message::detail), if there is one.
+    ///
+    /// A [`Catalog`] keeps this note when it replaces the detail with
+    /// a localized one.  The default behavior returns `None`.
+    ///
+    /// [`Catalog`]: crate::catalog::Catalog
+    #[inline]
+    fn help(&self) -> Option<&str> {
+        None
+    }
 }
 
 /// Trait for compiler messages with positions.
@@ -108,8 +166,40 @@ pub enum Highlighting {
 /// context.
 ///
 /// This will print and highlight the code that gave rise to the error.
+/// Messages are laid out as follows, with each line ending in exactly
+/// one newline:
+///
+/// - A header, `severity: brief`.
+/// - For each position, a line with its label (if any) and the
+///   position.  If source context is available, the line ends with a
+///   `:` and is followed by the source lines, with the highlighted
+///   region in the position's color.  A region on one line is
+///   underlined with `^`; for a region spanning several lines, the
+///   part of its first line and the part of its last line are each
+///   underlined, so that it can be seen without colors.  Otherwise,
+///   only the position is written.
+/// - The detailed description, if it is not empty.
+///
+/// Colors are reset before the end of every line.
+///
+/// The number of lines shown around the highlighted code, and how
+/// long highlighted regions are elided, can be configured.  By
+/// default, no surrounding lines are shown, and regions with more
+/// than six lines between their first and last are elided, showing
+/// three lines on each side.  Source lines are rendered with a
+/// [`SnippetRenderer`].
 pub struct MessageFullWriter<'a> {
-    sources: &'a Sources<'a>
+    sources: &'a Sources<'a>,
+    /// Number of lines to show before the highlighted code.
+    leading: usize,
+    /// Number of lines to show after the highlighted code.
+    trailing: usize,
+    /// Largest number of middle lines to show without eliding.
+    elide_threshold: usize,
+    /// Number of middle lines to show on each side when eliding.
+    elide_keep: usize,
+    /// Renderer for source lines.
+    renderer: SnippetRenderer
 }
 
 /// A [`MessageWriter`] that writes human-readable messages without context.
@@ -120,6 +210,38 @@ pub struct MessageSimpleWriter;
 
 pub struct MessageMinimalWriter;
 
+/// A [`MessageWriter`] that writes messages in the GNU convention,
+/// one line per position.
+///
+/// Each line has the form `file:line:col: severity: text`, which is
+/// understood by editors such as Emacs and Vim.  The first line has
+/// the message's [`Severity`] and brief description (followed by its
+/// label, if any), and later lines have the position's severity and
+/// label.  Positions that are not in files are written without the
+/// location.  Line breaks in the text are replaced with spaces, so
+/// that each line is a complete diagnostic.
+pub struct MessageGnuWriter;
+
+/// A [`MessageWriter`] that writes messages in the MSVC convention,
+/// one line per position.
+///
+/// Each line has the form `file(line,col): severity CODE: text`,
+/// which is understood by Visual Studio and related tools.  The
+/// [`code`](Message::code) is omitted if the message has none.  Lines
+/// are otherwise as for [`MessageGnuWriter`].
+pub struct MessageMsvcWriter;
+
+/// A [`MessageWriter`] that writes messages as GitHub Actions
+/// workflow commands, one line per position.
+///
+/// Each line has the form `::error file=...,line=...,col=...::text`,
+/// which GitHub shows as an annotation on the file.  The command is
+/// given by [`Severity::github_name`].  Positions that are not in
+/// files are written without the file properties, and whole-file
+/// positions only have the `file` property.  Lines are otherwise as
+/// for [`MessageGnuWriter`].
+pub struct MessageGithubWriter;
+
 impl Severity {
     /// Get the [`Color`] for this `Severity` level.
     #[inline]
@@ -153,11 +275,81 @@ impl Severity {
             write!(out, "{}", self)
         }
     }
+
+    /// Get the key for the label of this `Severity` in a [`Catalog`],
+    /// such as `severity.error`.
+    ///
+    /// [`Catalog`]: crate::catalog::Catalog
+    #[inline]
+    pub fn key(&self) -> &'static str {
+        match self {
+            Severity::Info => "severity.info",
+            Severity::Remark => "severity.remark",
+            Severity::Lint => "severity.lint",
+            Severity::Warning => "severity.warning",
+            Severity::Error => "severity.error",
+            Severity::Internal => "severity.internal"
+        }
+    }
+
+    /// Get the name of this `Severity` in the GNU convention.
+    ///
+    /// This is the lower-case form of its `Display` name, such as
+    /// `error` or `lint warning`.
+    #[inline]
+    pub fn gnu_name(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Remark => "remark",
+            Severity::Lint => "lint warning",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+            Severity::Internal => "internal error"
+        }
+    }
+
+    /// Get the name of this `Severity` in the MSVC convention.
+    ///
+    /// MSVC tools only distinguish errors, warnings, and
+    /// informational messages, so other levels are mapped to the
+    /// closest of these, and internal errors are `fatal error`.
+    #[inline]
+    pub fn msvc_name(&self) -> &'static str {
+        match self {
+            Severity::Info | Severity::Remark => "info",
+            Severity::Lint | Severity::Warning => "warning",
+            Severity::Error => "error",
+            Severity::Internal => "fatal error"
+        }
+    }
+
+    /// Get the name of the GitHub Actions workflow command for this
+    /// `Severity`.
+    ///
+    /// Errors and internal errors are `error`, warnings and lint
+    /// warnings are `warning`, and everything else is `notice`.
+    #[inline]
+    pub fn github_name(&self) -> &'static str {
+        match self {
+            Severity::Info | Severity::Remark => "notice",
+            Severity::Lint | Severity::Warning => "warning",
+            Severity::Error | Severity::Internal => "error"
+        }
+    }
 }
 
 impl Display for Severity {
-    #[inline]
+    /// Write the label of this `Severity`, localized with the active
+    /// [`Catalog`] if there is one.
+    ///
+    /// [`Catalog`]: crate::catalog::Catalog
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        if let Some(catalog) = active_catalog() {
+            if let Some(label) = catalog.severity(*self) {
+                return f.write_str(label);
+            }
+        }
+
         match self {
             Severity::Info => write!(f, "Info"),
             Severity::Lint => write!(f, "Lint Warning"),
@@ -169,6 +361,138 @@ impl Display for Severity {
     }
 }
 
+impl<'a> MessageFullWriter<'a> {
+    /// Create a new `MessageFullWriter` that gets context from
+    /// `sources`.
+    #[inline]
+    pub fn new(sources: &'a Sources<'a>) -> Self {
+        MessageFullWriter { sources, leading: 0, trailing: 0,
+                            elide_threshold: 6, elide_keep: 3,
+                            renderer: SnippetRenderer::new() }
+    }
+
+    /// Get the [`SnippetRenderer`] used to render source lines.
+    #[inline]
+    pub fn renderer(&self) -> &SnippetRenderer {
+        &self.renderer
+    }
+
+    /// Set the distance between tab stops when rendering source
+    /// lines.
+    ///
+    /// See [`SnippetRenderer::set_tab_width`].
+    #[inline]
+    pub fn set_tab_width(&mut self, tab_width: usize) {
+        self.renderer.set_tab_width(tab_width)
+    }
+
+    /// Get the number of lines shown before and after the highlighted
+    /// code.
+    #[inline]
+    pub fn context_lines(&self) -> (usize, usize) {
+        (self.leading, self.trailing)
+    }
+
+    /// Set the number of lines shown before (`leading`) and after
+    /// (`trailing`) the highlighted code.
+    #[inline]
+    pub fn set_context_lines(&mut self, leading: usize, trailing: usize) {
+        self.leading = leading;
+        self.trailing = trailing;
+    }
+
+    /// Get the elision threshold and the number of lines kept on each
+    /// side when eliding.
+    #[inline]
+    pub fn elision(&self) -> (usize, usize) {
+        (self.elide_threshold, self.elide_keep)
+    }
+
+    /// Set how highlighted regions spanning many lines are elided.
+    ///
+    /// If there are more than `threshold` lines between the first and
+    /// last lines of a region, only `keep` lines on each side are
+    /// shown, separated by `...`.  Regions are never elided if that
+    /// would not hide any lines.
+    #[inline]
+    pub fn set_elision(&mut self, threshold: usize, keep: usize) {
+        self.elide_threshold = threshold;
+        self.elide_keep = keep;
+    }
+
+    /// Write each of `lines` out to `out`, rendered as whole lines,
+    /// in `color`.
+    fn write_lines<W>(&self, lines: SourceLines<'_>, color: &ColorSpec,
+                      out: &mut W) -> Result<(), Error>
+    where W: WriteColor {
+        for line in lines {
+            write_styled(out, color, &self.renderer.render(line, 0).0)?;
+            writeln!(out)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write `excerpt` out to `out`, highlighted for `severity`.
+    fn write_excerpt<W>(&self, excerpt: &SourceExcerpt<'_>,
+                        severity: Severity, out: &mut W) ->
+        Result<(), Error>
+    where W: WriteColor {
+        let renderer = &self.renderer;
+        let plain = ColorSpec::new();
+        let mut color = ColorSpec::new();
+
+        color.set_fg(Some(severity.color()));
+        self.write_lines(excerpt.leading, &plain, out)?;
+
+        match excerpt.context {
+            SourceContext::Single { prefix, selected, suffix } => {
+                let (prefix, start) = renderer.render(prefix, 0);
+                let (selected, end) = renderer.render(selected, start);
+                let (suffix, _) = renderer.render(suffix, end);
+
+                write!(out, "{}", prefix)?;
+                write_styled(out, &color, &selected)?;
+                writeln!(out, "{}", suffix)?;
+                write_styled(out, &color, &renderer.underline(start, end))?;
+                writeln!(out)?;
+            },
+            SourceContext::Multiple { prefix, first, middle, last,
+                                      suffix } => {
+                let (prefix, start) = renderer.render(prefix, 0);
+                let (first, first_end) = renderer.render(first, start);
+                let (last, end) = renderer.render(last, 0);
+                let (suffix, _) = renderer.render(suffix, end);
+                let nlines = middle.len();
+                let keep = self.elide_keep;
+
+                write!(out, "{}", prefix)?;
+                write_styled(out, &color, &first)?;
+                writeln!(out)?;
+                write_styled(out, &color,
+                             &renderer.underline(start, first_end))?;
+                writeln!(out)?;
+
+                if nlines > self.elide_threshold && nlines > 2 * keep {
+                    self.write_lines(middle.slice(0, keep), &color, out)?;
+                    writeln!(out, "...")?;
+                    self.write_lines(middle.slice(nlines - keep, nlines),
+                                     &color, out)?;
+                } else {
+                    self.write_lines(middle, &color, out)?;
+                }
+
+                write_styled(out, &color, &last)?;
+                writeln!(out, "{}", suffix)?;
+                write_styled(out, &color, &renderer.underline(0, end))?;
+                writeln!(out)?;
+            }
+        }
+
+        self.write_lines(excerpt.trailing, &plain, out)
+    }
+}
+
 impl MessageWriter for MessageFullWriter<'_> {
     fn write_msg<'a, M, P, W>(&self, msg: &'a M, out: &mut W) ->
         Result<(), Error>
@@ -177,114 +501,44 @@ impl MessageWriter for MessageFullWriter<'_> {
           W: WriteColor,
           P: 'a + Display {
         msg.severity().write_color(out)?;
-
-        if out.supports_color() {
-            out.set_color(ColorSpec::new().set_bold(true))?;
-            write!(out, ": {}", msg.severity())?;
-            out.reset()?;
-        } else {
-            write!(out, ": {}", msg.severity())?;
-        }
-
-        let mut first = true;
+        write!(out, ": ")?;
+        write_styled(out, ColorSpec::new().set_bold(true),
+                     &localized_brief(msg))?;
+        writeln!(out)?;
 
         for (label, pos, severity) in msg.positions() {
-            let mut color = ColorSpec::new();
-
-            color.set_fg(Some(severity.color()));
+            let filepos: Result<&'a FilePosition<'a>, _> = pos.try_into();
+            let excerpt = match filepos {
+                Ok(FilePosition::Portion { offset, file_offsets }) =>
+                    self.sources.try_get_excerpt(file_offsets.filename(),
+                                                 offset, self.leading,
+                                                 self.trailing).ok(),
+                _ => None
+            };
 
             match label {
-                Some(label) if first => {
-                    writeln!(out)?;
-                    write!(out, "  {}", label)?
-                },
-                Some(label) => write!(out, "  {}", label)?,
-                None => ()
+                Some(label) => write!(out, "  {} {}", label, pos)?,
+                None => write!(out, "  {}", pos)?
             }
 
-            write!(out, " {}:", pos)?;
-
-            let filepos: Result<&'a FilePosition<'a>, _> = pos.try_into();
-
-            match filepos {
-                Ok(FilePosition::Portion { offset, file_offsets}) => {
-
-                    match self.sources.get_ctx(file_offsets.filename(),
-                                               offset) {
-                        Some(SourceContext::Single { prefix, selected,
-                                                     suffix }) => {
-                            writeln!(out, " {}:", pos)?;
-                            write!(out, "{}", prefix)?;
-
-                            if out.supports_color() {
-                                out.set_color(&color)?;
-                                write!(out, "{}", selected)?;
-                                out.reset()?;
-                            } else {
-                                write!(out, "{}", selected)?;
-                            }
-
-                            writeln!(out, "{}", suffix)?;
-                        },
-                        Some(SourceContext::Multiple { prefix, first, middle,
-                                                       last, suffix }) => {
-                            writeln!(out, " {}:", pos)?;
-                            write!(out, "{}", prefix)?;
-
-                            if out.supports_color() {
-                                out.set_color(&color)?;
-                                write!(out, "{}", first)?;
-                            } else {
-                                writeln!(out, "{}", first)?;
-                            }
-
-                            let nlines = middle.len();
-
-                            if nlines > 6 {
-                                writeln!(out, "{}", middle[0])?;
-                                writeln!(out, "{}", middle[1])?;
-                                writeln!(out, "{}", middle[2])?;
-
-                                if out.supports_color() {
-                                    out.reset()?;
-                                    writeln!(out, "...")?;
-                                    out.set_color(&color)?;
-                                } else {
-                                    writeln!(out, "...")?;
-                                }
-
-                                writeln!(out, "{}", middle[nlines - 3])?;
-                                writeln!(out, "{}", middle[nlines - 2])?;
-                                writeln!(out, "{}", middle[nlines - 1])?;
-                            } else {
-                                for line in middle {
-                                    writeln!(out, "{}", line)?;
-                                }
-                            }
-
-                            write!(out, "{}", last)?;
-
-                            if out.supports_color() {
-                                out.reset()?;
-                                write!(out, "{}", suffix)?;
-                            } else {
-                                writeln!(out, "{}", suffix)?;
-                            }
-                        },
-                        None => {
-                            write!(out, " {}", pos)?;
-                        }
-                    }
+            match excerpt {
+                Some(excerpt) => {
+                    writeln!(out, ":")?;
+                    self.write_excerpt(&excerpt, *severity, out)?
                 },
-                _ => {
-                    write!(out, " {}", pos)?;
-                }
+                // Context is unavailable, so fall back to the
+                // position alone.
+                None => writeln!(out)?
             }
+        }
 
-            first = false;
+        let detail = localized_detail(msg);
+
+        if !detail.is_empty() {
+            writeln!(out, "{}", detail)?;
         }
 
-        writeln!(out, "{}", msg.detail())
+        Ok(())
     }
 }
 
@@ -299,10 +553,10 @@ impl MessageWriter for MessageSimpleWriter {
 
         if out.supports_color() {
             out.set_color(ColorSpec::new().set_bold(true))?;
-            write!(out, ": {}", msg.brief())?;
+            write!(out, ": {}", localized_brief(msg))?;
             out.reset()?;
         } else {
-            write!(out, ": {}", msg.brief())?;
+            write!(out, ": {}", localized_brief(msg))?;
         }
 
         let mut first = true;
@@ -319,7 +573,7 @@ impl MessageWriter for MessageSimpleWriter {
             first = false;
         }
 
-        writeln!(out, "{}", msg.detail())
+        writeln!(out, "{}", localized_detail(msg))
     }
 }
 
@@ -330,7 +584,7 @@ impl MessageWriter for MessageMinimalWriter {
           M: MessagePositions<P>,
           W: WriteColor,
           P: 'a + Display {
-        write!(out, "{}: {}", msg.severity(), msg.brief())?;
+        write!(out, "{}: {}", msg.severity(), localized_brief(msg))?;
 
         let mut first = true;
 
@@ -349,3 +603,200 @@ impl MessageWriter for MessageMinimalWriter {
         Ok(())
     }
 }
+
+impl MessageWriter for MessageGnuWriter {
+    fn write_msg<'a, M, P, W>(&self, msg: &'a M, out: &mut W) ->
+        Result<(), Error>
+    where &'a FilePosition<'a>: TryFrom<&'a P>,
+          M: MessagePositions<P>,
+          W: WriteColor,
+          P: 'a + Display {
+        write_one_line(msg, out, |out, filepos, severity, text| {
+            if let Some(filepos) = filepos {
+                let name = filepos.filename().display_name();
+
+                match filepos.start_line_col() {
+                    Some((line, col)) =>
+                        write!(out, "{}:{}:{}: ", name, line, col)?,
+                    None => write!(out, "{}: ", name)?
+                }
+            }
+
+            writeln!(out, "{}: {}", severity.gnu_name(), single_line(text))
+        })
+    }
+}
+
+impl MessageWriter for MessageMsvcWriter {
+    fn write_msg<'a, M, P, W>(&self, msg: &'a M, out: &mut W) ->
+        Result<(), Error>
+    where &'a FilePosition<'a>: TryFrom<&'a P>,
+          M: MessagePositions<P>,
+          W: WriteColor,
+          P: 'a + Display {
+        write_one_line(msg, out, |out, filepos, severity, text| {
+            if let Some(filepos) = filepos {
+                let name = filepos.filename().display_name();
+
+                match filepos.start_line_col() {
+                    Some((line, col)) =>
+                        write!(out, "{}({},{}): ", name, line, col)?,
+                    None => write!(out, "{}: ", name)?
+                }
+            }
+
+            let text = single_line(text);
+
+            match msg.code() {
+                Some(code) =>
+                    writeln!(out, "{} {}: {}", severity.msvc_name(), code,
+                             text),
+                None => writeln!(out, "{}: {}", severity.msvc_name(), text)
+            }
+        })
+    }
+}
+
+impl MessageWriter for MessageGithubWriter {
+    fn write_msg<'a, M, P, W>(&self, msg: &'a M, out: &mut W) ->
+        Result<(), Error>
+    where &'a FilePosition<'a>: TryFrom<&'a P>,
+          M: MessagePositions<P>,
+          W: WriteColor,
+          P: 'a + Display {
+        write_one_line(msg, out, |out, filepos, severity, text| {
+            write!(out, "::{}", severity.github_name())?;
+
+            if let Some(filepos) = filepos {
+                let name = filepos.filename().display_name();
+
+                write!(out, " file={}", escape_github_property(&name))?;
+
+                if let (Some((line, col)), Some((end_line, end_col))) =
+                    (filepos.start_line_col(), filepos.end_line_col()) {
+                    write!(out, ",line={},col={},endLine={},endColumn={}",
+                           line, col, end_line, end_col)?;
+                }
+            }
+
+            writeln!(out, "::{}", escape_github_data(text))
+        })
+    }
+}
+
+/// Escape `text` for use as the data of a GitHub Actions workflow
+/// command.
+pub fn escape_github_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape `text` for use as a property value of a GitHub Actions
+/// workflow command.
+///
+/// This escapes the same characters as [`escape_github_data`], as
+/// well as `:` and `,`.
+pub fn escape_github_property(text: &str) -> String {
+    escape_github_data(text)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+/// Group `msgs` by the file of their first position.
+///
+/// Groups are in the order their files first appear, and messages
+/// keep their order within each group.  Messages whose first position
+/// is not in a file, or that have no positions, are grouped under
+/// `None`.
+pub fn group_by_file<'a, I, M, P>(msgs: I) ->
+    Vec<(Option<Filename<'a>>, Vec<&'a M>)>
+where I: IntoIterator<Item = &'a M>,
+      &'a FilePosition<'a>: TryFrom<&'a P>,
+      M: 'a + MessagePositions<P>,
+      P: 'a {
+    let mut groups: Vec<(Option<Filename<'a>>, Vec<&'a M>)> = Vec::new();
+
+    for msg in msgs {
+        let file = msg.positions().first().and_then(|(_, pos, _)| {
+            let filepos: Result<&'a FilePosition<'a>, _> = pos.try_into();
+
+            filepos.ok().map(FilePosition::filename)
+        });
+
+        match groups.iter_mut().find(|(key, _)| *key == file) {
+            Some((_, group)) => group.push(msg),
+            None => groups.push((file, vec![msg]))
+        }
+    }
+
+    groups
+}
+
+/// Write `text` out to `out` in `color`, without a newline.
+///
+/// The color is reset afterward, so that it never carries over onto
+/// the next line.  Nothing is written for empty text.
+fn write_styled<W>(out: &mut W, color: &ColorSpec, text: &str) ->
+    Result<(), Error>
+where W: WriteColor {
+    if text.is_empty() {
+        Ok(())
+    } else if color.is_none() {
+        write!(out, "{}", text)
+    } else {
+        out.set_color(color)?;
+        write!(out, "{}", text)?;
+        out.reset()
+    }
+}
+
+/// Join the lines of `text` with spaces.
+fn single_line(text: &str) -> Cow<'_, str> {
+    if text.contains(['\n', '\r']) {
+        Cow::from(text.lines().collect::<Vec<&str>>().join(" "))
+    } else {
+        Cow::from(text)
+    }
+}
+
+/// Write `msg` out as one line per position, using `line` to write
+/// each line.
+///
+/// `line` is given the file position (if any), the severity, and the
+/// text for the line.  If the message has no positions, a single
+/// line is written with no position.
+fn write_one_line<'a, M, P, W, F>(msg: &'a M, out: &mut W, mut line: F) ->
+    Result<(), Error>
+where &'a FilePosition<'a>: TryFrom<&'a P>,
+      M: MessagePositions<P>,
+      W: WriteColor,
+      P: 'a + Display,
+      F: FnMut(&mut W, Option<&'a FilePosition<'a>>, Severity, &str) ->
+         Result<(), Error> {
+    let positions = msg.positions();
+    let brief = localized_brief(msg);
+
+    if positions.is_empty() {
+        return line(out, None, msg.severity(), &brief);
+    }
+
+    for (idx, (label, pos, severity)) in positions.iter().enumerate() {
+        let filepos: Option<&'a FilePosition<'a>> = pos.try_into().ok();
+
+        if idx == 0 {
+            match label {
+                Some(label) => {
+                    let text = format!("{}: {}", brief, label);
+
+                    line(out, filepos, msg.severity(), &text)?
+                },
+                None => line(out, filepos, msg.severity(), &brief)?
+            }
+        } else {
+            line(out, filepos, *severity, label.unwrap_or(&brief))?
+        }
+    }
+
+    Ok(())
+}
@@ -63,7 +63,7 @@ impl<T> Hash for Nondistinct<T> {
 impl<T> From<T> for Nondistinct<T> {
     #[inline]
     fn from(val: T) -> Self {
-        Nondistinct { val: val }
+        Nondistinct { val }
     }
 }
 
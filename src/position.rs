@@ -133,32 +133,80 @@ pub enum DWARFPosition<'a, DefID, TyDefID> {
     CmdLine
 }
 */
+impl<'a> FilePosition<'a> {
+    /// Get the [`Filename`] of the file.
+    #[inline]
+    pub fn filename(&self) -> Filename<'a> {
+        match self {
+            FilePosition::Portion { file_offsets, .. } =>
+                file_offsets.filename(),
+            FilePosition::File { filename } => *filename
+        }
+    }
+
+    /// Get the line and column of the start of the portion, if this
+    /// is a portion of the file.
+    ///
+    /// Lines and columns are numbered as in the `Display` form, so
+    /// that all writers report the same position.
+    pub fn start_line_col(&self) -> Option<(usize, usize)> {
+        match self {
+            FilePosition::Portion { file_offsets, offset } => {
+                let start = match offset {
+                    OffsetPosition::Span { start, .. } => *start,
+                    OffsetPosition::Point { point } => *point
+                };
+                Some(line_col(file_offsets.line_offsets(), start))
+            },
+            FilePosition::File { .. } => None
+        }
+    }
+
+    /// Get the line and column of the end of the portion, if this is
+    /// a portion of the file.
+    ///
+    /// For a span, this is the position just after its last
+    /// character; for a point, it is the point itself.  See
+    /// [`start_line_col`](FilePosition::start_line_col).
+    pub fn end_line_col(&self) -> Option<(usize, usize)> {
+        match self {
+            FilePosition::Portion { file_offsets, offset } => {
+                let end = match offset {
+                    OffsetPosition::Span { start, len } => *start + *len,
+                    OffsetPosition::Point { point } => *point
+                };
+                Some(line_col(file_offsets.line_offsets(), end))
+            },
+            FilePosition::File { .. } => None
+        }
+    }
+}
+
 impl Display for FilePosition<'_> {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
         match self {
             FilePosition::Portion { file_offsets, offset } => match offset {
                 OffsetPosition::Span { start, len } => {
                     let end = *start + *len;
-                    let (start_line, start_col) = file_offsets
-                        .line_offsets().lookup(*start);
-                    let (end_line, end_col) = file_offsets
-                        .line_offsets().lookup(end);
+                    let (start_line, start_col) =
+                        line_col(file_offsets.line_offsets(), *start);
+                    let (end_line, end_col) =
+                        line_col(file_offsets.line_offsets(), end);
 
                     if start_line == end_line {
                         write!(f, "at {}:{}.{}-{}", file_offsets.filename(),
-                               start_line + 1, start_col + 1, end_col + 1)
+                               start_line, start_col, end_col)
                     } else {
                         write!(f, "at {}:{}.{}-{}.{}", file_offsets.filename(),
-                               start_line + 1, start_col + 1,
-                               end_line + 1, end_col + 1)
+                               start_line, start_col, end_line, end_col)
                     }
                 },
                 OffsetPosition::Point { point } => {
-                    let (line, col) = file_offsets
-                        .line_offsets().lookup(*point);
+                    let (line, col) =
+                        line_col(file_offsets.line_offsets(), *point);
 
                     write!(f, "at {}:{}.{}", file_offsets.filename(),
-                           line + 1, col + 1)
+                           line, col)
                 }
             },
             FilePosition::File { filename } => {
@@ -176,23 +224,21 @@ impl Display for BasicPosition<'_> {
                 OffsetPosition::Span { start, len } => {
                     let end = *start + *len;
                     let (start_line, start_col) =
-                        line_offsets.val.lookup(*start);
-                    let (end_line, end_col) =
-                        line_offsets.val.lookup(end);
+                        line_col(line_offsets.val, *start);
+                    let (end_line, end_col) = line_col(line_offsets.val, end);
 
                     if start_line == end_line {
                         write!(f, "at input {}.{}-{}",
-                               start_line + 1, start_col + 1, end_col + 1)
+                               start_line, start_col, end_col)
                     } else {
                         write!(f, "at input {}.{}-{}.{}",
-                               start_line + 1, start_col + 1,
-                               end_line + 1, end_col + 1)
+                               start_line, start_col, end_line, end_col)
                     }
                 },
                 OffsetPosition::Point { point } => {
-                    let (line, col) = line_offsets.val.lookup(*point);
+                    let (line, col) = line_col(line_offsets.val, *point);
 
-                    write!(f, "at input {}.{}", line + 1, col + 1)
+                    write!(f, "at input {}.{}", line, col)
                 }
             },
             BasicPosition::Synthetic { desc } => desc.fmt(f),
@@ -224,3 +270,15 @@ impl<'a> TryFrom<&'a BasicPosition<'a>> for &'a FilePosition<'a> {
         }
     }
 }
+
+/// Get the line and column of `pos`, as shown by the `Display` forms
+/// of positions.
+///
+/// These are one more than the values given by
+/// [`LineOffsets::lookup`].
+#[inline]
+fn line_col(line_offsets: &LineOffsets, pos: Offset) -> (usize, usize) {
+    let (line, col) = line_offsets.lookup(pos);
+
+    (line + 1, col + 1)
+}
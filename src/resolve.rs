@@ -0,0 +1,338 @@
+use crate::files::Filename;
+use crate::files::Filenames;
+use crate::messages::Message;
+use crate::messages::MessagePositions;
+use crate::messages::Severity;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt::Write;
+use std::fs;
+use std::io::Error;
+use std::io::ErrorKind;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Resolver for logical module paths, such as `a.b.c`, to files.
+///
+/// A module path `a.b.c` is resolved by searching each root
+/// directory, in order, for `a/b/c` with each of the candidate
+/// extensions appended, in order.  Each component must be a single
+/// plain file name, so that modules cannot name files outside the
+/// roots.  Within a single root, the first extension
+/// that matches is used.  If the module is found in more than one
+/// root, it is ambiguous.
+///
+/// Results, including failures, are cached until the roots or
+/// extensions change, or [`clear_cache`](ModuleResolver::clear_cache)
+/// is called.
+pub struct ModuleResolver<'a> {
+    /// Directories to search.
+    roots: Vec<PathBuf>,
+    /// Candidate extensions, without the leading `.`.
+    extensions: Vec<OsString>,
+    /// Separator used to display module paths.
+    separator: String,
+    /// Cached results, by module path.
+    cache: HashMap<Vec<String>, Result<Filename<'a>, ResolveError<'a>>>
+}
+
+/// Errors that can occur when resolving a module path.
+///
+/// These are [`Message`]s, which can be reported directly.  They
+/// have no positions of their own.
+#[derive(Clone, Debug)]
+pub enum ResolveError<'a> {
+    /// No file was found for the module.
+    NotFound {
+        /// The module path, as it should be displayed.
+        module: String,
+        /// All paths that were tried, in order.
+        tried: Vec<PathBuf>,
+        /// Brief description.
+        brief: String,
+        /// Detailed description, listing the paths tried.
+        detail: String
+    },
+    /// Files for the module were found in more than one root.
+    Ambiguous {
+        /// The module path, as it should be displayed.
+        module: String,
+        /// All files that were found, in the order of their roots.
+        found: Vec<Filename<'a>>,
+        /// Brief description.
+        brief: String,
+        /// Detailed description, listing the files found.
+        detail: String
+    },
+    /// A component of the module path is not a plain file name, such
+    /// as `..`, an absolute path, or a name containing a separator.
+    InvalidComponent {
+        /// The module path, as it should be displayed.
+        module: String,
+        /// The offending component.
+        component: String,
+        /// Brief description.
+        brief: String,
+        /// Detailed description.
+        detail: String
+    },
+    /// A candidate file could not be accessed.
+    Io {
+        /// The module path, as it should be displayed.
+        module: String,
+        /// The candidate file.
+        path: PathBuf,
+        /// The error.
+        err: Arc<Error>,
+        /// Brief description.
+        brief: String,
+        /// Detailed description, giving the path and the error.
+        detail: String
+    }
+}
+
+impl<'a> ModuleResolver<'a> {
+    /// Create a new `ModuleResolver` with no roots or extensions.
+    #[inline]
+    pub fn new() -> Self {
+        ModuleResolver { roots: Vec::new(), extensions: Vec::new(),
+                         separator: String::from("."),
+                         cache: HashMap::new() }
+    }
+
+    /// Add `root` to the end of the search directories.
+    #[inline]
+    pub fn add_root(&mut self, root: &Path) {
+        self.roots.push(root.to_path_buf());
+        self.cache.clear()
+    }
+
+    /// Add `ext` to the end of the candidate extensions.
+    ///
+    /// Extensions are given without the leading `.`.
+    #[inline]
+    pub fn add_extension(&mut self, ext: &str) {
+        self.extensions.push(OsString::from(ext));
+        self.cache.clear()
+    }
+
+    /// Set the separator used to display module paths in messages.
+    ///
+    /// This defaults to `.`.
+    #[inline]
+    pub fn set_separator(&mut self, separator: &str) {
+        self.separator = String::from(separator);
+        self.cache.clear()
+    }
+
+    /// Get the search directories.
+    #[inline]
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Get the candidate extensions.
+    #[inline]
+    pub fn extensions(&self) -> &[OsString] {
+        &self.extensions
+    }
+
+    /// Discard all cached results.
+    ///
+    /// This should be called if files may have been created or
+    /// removed since the last resolution.
+    #[inline]
+    pub fn clear_cache(&mut self) {
+        self.cache.clear()
+    }
+
+    /// Resolve `module`, given as its components, to a file.
+    ///
+    /// The file is interned in `filenames`.
+    pub fn resolve<S>(&mut self, filenames: &mut Filenames<'a>,
+                      module: &[S]) -> Result<Filename<'a>, ResolveError<'a>>
+    where S: AsRef<str> {
+        let key: Vec<String> = module.iter()
+            .map(|part| String::from(part.as_ref()))
+            .collect();
+
+        if let Some(result) = self.cache.get(&key) {
+            return result.clone();
+        }
+
+        let result = self.search(filenames, &key);
+
+        self.cache.insert(key, result.clone());
+
+        result
+    }
+
+    /// Search the roots for `module`.
+    fn search(&self, filenames: &mut Filenames<'a>, module: &[String]) ->
+        Result<Filename<'a>, ResolveError<'a>> {
+        let name = module.join(&self.separator);
+        let mut tried = Vec::new();
+        let mut found = Vec::new();
+
+        if let Some(part) = module.iter().find(|part| !is_plain(part)) {
+            return Err(ResolveError::invalid_component(name, part));
+        }
+
+        if !module.is_empty() {
+            for root in &self.roots {
+                let base: PathBuf = module.iter()
+                    .fold(root.clone(), |path, part| path.join(part));
+                // Candidates under a missing directory are not found,
+                // rather than errors.
+                let dir = base.parent().is_some_and(Path::is_dir);
+
+                for ext in &self.extensions {
+                    let mut path = base.clone().into_os_string();
+
+                    path.push(".");
+                    path.push(ext);
+
+                    let path = PathBuf::from(path);
+
+                    tried.push(path.clone());
+
+                    if !dir {
+                        continue;
+                    }
+
+                    match fs::metadata(&path) {
+                        Ok(meta) if meta.is_file() => {
+                            let filename = filenames.filename(&path)
+                                .map_err(|err| {
+                                    ResolveError::io(name.clone(), &path, err)
+                                })?;
+
+                            if !found.contains(&filename) {
+                                found.push(filename);
+                            }
+
+                            break;
+                        },
+                        Ok(_) => (),
+                        Err(err) if err.kind() == ErrorKind::NotFound => (),
+                        Err(err) => return Err(ResolveError::io(name, &path,
+                                                                err))
+                    }
+                }
+            }
+        }
+
+        match found.len() {
+            0 => Err(ResolveError::not_found(name, tried)),
+            1 => Ok(found[0]),
+            _ => Err(ResolveError::ambiguous(name, found))
+        }
+    }
+}
+
+impl Default for ModuleResolver<'_> {
+    #[inline]
+    fn default() -> Self {
+        ModuleResolver::new()
+    }
+}
+
+impl<'a> ResolveError<'a> {
+    /// Create a [`ResolveError::NotFound`].
+    fn not_found(module: String, tried: Vec<PathBuf>) -> Self {
+        let brief = format!("module `{}` not found", module);
+        let mut detail = String::from("searched for:");
+
+        for path in &tried {
+            // Writing to a String cannot fail.
+            let _ = write!(detail, "\n  {}", path.display());
+        }
+
+        ResolveError::NotFound { module, tried, brief,
+                                 detail }
+    }
+
+    /// Create a [`ResolveError::Ambiguous`].
+    fn ambiguous(module: String, found: Vec<Filename<'a>>) -> Self {
+        let brief = format!("module `{}` is ambiguous", module);
+        let mut detail = String::from("found in:");
+
+        for filename in &found {
+            let path = filename.display_path();
+
+            let _ = write!(detail, "\n  {}", path.display());
+        }
+
+        ResolveError::Ambiguous { module, found, brief,
+                                  detail }
+    }
+
+    /// Create a [`ResolveError::InvalidComponent`].
+    fn invalid_component(module: String, component: &str) -> Self {
+        let brief = format!("invalid module path `{}`", module);
+        let detail = format!("`{}` is not a valid module name", component);
+
+        ResolveError::InvalidComponent { module,
+                                         component: String::from(component),
+                                         brief, detail }
+    }
+
+    /// Create a [`ResolveError::Io`].
+    fn io(module: String, path: &Path, err: Error) -> Self {
+        let brief = format!("cannot access module `{}`", module);
+        let detail = format!("{}: {}", path.display(), err);
+
+        ResolveError::Io { module, path: path.to_path_buf(),
+                           err: Arc::new(err), brief, detail }
+    }
+
+    /// Get the module path, as it should be displayed.
+    #[inline]
+    pub fn module(&self) -> &str {
+        match self {
+            ResolveError::NotFound { module, .. } => module,
+            ResolveError::Ambiguous { module, .. } => module,
+            ResolveError::InvalidComponent { module, .. } => module,
+            ResolveError::Io { module, .. } => module
+        }
+    }
+}
+
+impl Message for ResolveError<'_> {
+    #[inline]
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    #[inline]
+    fn brief(&self) -> &str {
+        match self {
+            ResolveError::NotFound { brief, .. } => brief,
+            ResolveError::Ambiguous { brief, .. } => brief,
+            ResolveError::InvalidComponent { brief, .. } => brief,
+            ResolveError::Io { brief, .. } => brief
+        }
+    }
+
+    #[inline]
+    fn detail(&self) -> &str {
+        match self {
+            ResolveError::NotFound { detail, .. } => detail,
+            ResolveError::Ambiguous { detail, .. } => detail,
+            ResolveError::InvalidComponent { detail, .. } => detail,
+            ResolveError::Io { detail, .. } => detail
+        }
+    }
+}
+
+impl<P> MessagePositions<P> for ResolveError<'_> {}
+
+/// Check whether `part` is a single plain file name.
+fn is_plain(part: &str) -> bool {
+    let mut components = Path::new(part).components();
+
+    matches!((components.next(), components.next()),
+             (Some(Component::Normal(_)), None))
+}
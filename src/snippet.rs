@@ -0,0 +1,124 @@
+use std::fmt::Write;
+use unicode_width::UnicodeWidthChar;
+
+/// Renderer for lines of source code in message snippets.
+///
+/// Source text is not written raw: tabs are expanded to tab stops,
+/// and control, zero-width, and bidirectional formatting characters
+/// are replaced with visible escapes such as `<U+202E>`, so they
+/// cannot hide or visually reorder code.  Rendering tracks the
+/// display column, so that underlines can be aligned with the
+/// rendered text.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SnippetRenderer {
+    /// Distance between tab stops.
+    tab_width: usize
+}
+
+/// Check whether `c` should be escaped when rendering snippets.
+///
+/// This is true for control characters (other than tab),
+/// zero-width characters, and bidirectional formatting characters.
+pub fn is_invisible(c: char) -> bool {
+    match c {
+        '\t' => false,
+        // Arabic letter mark, and left-to-right and right-to-left marks.
+        '\u{61c}' | '\u{200e}' | '\u{200f}' => true,
+        // Bidirectional embeddings, overrides, and isolates.
+        '\u{202a}' ..= '\u{202e}' | '\u{2066}' ..= '\u{2069}' => true,
+        // Zero-width spaces, joiners, and the byte order mark.
+        '\u{200b}' ..= '\u{200d}' | '\u{2060}' | '\u{feff}' => true,
+        _ => c.is_control()
+    }
+}
+
+impl SnippetRenderer {
+    /// Create a new `SnippetRenderer` with tab stops every 4 columns.
+    #[inline]
+    pub fn new() -> Self {
+        SnippetRenderer { tab_width: 4 }
+    }
+
+    /// Get the distance between tab stops.
+    #[inline]
+    pub fn tab_width(&self) -> usize {
+        self.tab_width
+    }
+
+    /// Set the distance between tab stops.
+    ///
+    /// A width of 0 is treated as 1.
+    #[inline]
+    pub fn set_tab_width(&mut self, tab_width: usize) {
+        self.tab_width = tab_width.max(1)
+    }
+
+    /// Render `text`, which starts at display column `col`, onto the
+    /// end of `out`.
+    ///
+    /// Returns the display column after the rendered text.
+    pub fn render_into(&self, text: &str, col: usize, out: &mut String) ->
+        usize {
+        let mut col = col;
+
+        for c in text.chars() {
+            if c == '\t' {
+                let width = self.tab_width - col % self.tab_width;
+
+                out.extend((0 .. width).map(|_| ' '));
+                col += width;
+            } else if is_invisible(c) {
+                let start = out.len();
+
+                // Writing to a String cannot fail.
+                let _ = write!(out, "<U+{:04X}>", c as u32);
+                col += out.len() - start;
+            } else {
+                out.push(c);
+                col += c.width().unwrap_or(0);
+            }
+        }
+
+        col
+    }
+
+    /// Render `text`, which starts at display column `col`.
+    ///
+    /// Returns the rendered text and the display column after it.
+    #[inline]
+    pub fn render(&self, text: &str, col: usize) -> (String, usize) {
+        let mut out = String::with_capacity(text.len());
+        let col = self.render_into(text, col, &mut out);
+
+        (out, col)
+    }
+
+    /// Get the display column after rendering `text` starting at
+    /// display column `col`.
+    #[inline]
+    pub fn column(&self, text: &str, col: usize) -> usize {
+        self.render(text, col).1
+    }
+
+    /// Get an underline from display column `start` to `end`.
+    ///
+    /// The underline is indented with spaces to `start`, and is always
+    /// at least one column wide, so that empty selections are still
+    /// visible.
+    pub fn underline(&self, start: usize, end: usize) -> String {
+        let len = end.saturating_sub(start).max(1);
+        let mut out = String::with_capacity(start + len);
+
+        out.extend((0 .. start).map(|_| ' '));
+        out.extend((0 .. len).map(|_| '^'));
+
+        out
+    }
+}
+
+impl Default for SnippetRenderer {
+    #[inline]
+    fn default() -> Self {
+        SnippetRenderer::new()
+    }
+}
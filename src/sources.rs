@@ -1,12 +1,64 @@
+use crate::encoding::Encoding;
+use crate::encoding::EncodingError;
+use crate::encoding::OffsetMap;
+use crate::encoding::decode;
 use crate::files::Filename;
+use crate::fingerprint::ContentHash;
+use crate::fingerprint::Fingerprint;
 use crate::lines::LineOffsets;
+use crate::lines::Offset;
 use crate::position::OffsetPosition;
+use memchr::memchr_iter;
+use memmap2::Mmap;
+use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::collections::hash_map::Entry;
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fs::File;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::io::Error;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::ops::Index;
+use std::ptr;
+use std::str;
+
+/// Storage for the text of a [`Source`].
+enum SourceText {
+    /// Text held in memory.
+    Owned(String),
+    /// Text in a read-only memory-mapped file, starting at the given
+    /// offset, which has been checked to be valid UTF-8.
+    Mapped(Mmap, usize)
+}
 
 pub struct Source {
-    content: Vec<String>,
-    line_offsets: LineOffsets
+    /// The text of the source.
+    text: SourceText,
+    /// Start and end of each line in `text`, without terminators.
+    ranges: Vec<(usize, usize)>,
+    line_offsets: LineOffsets,
+    /// The encoding the text was decoded from.
+    encoding: Encoding,
+    /// Map from offsets in the text to offsets in the original file.
+    offset_map: OffsetMap,
+    /// Hash of the contents, if the whole source was supplied at once.
+    hash: Option<ContentHash>,
+    /// Fingerprint of the file on disk, if it was loaded from disk.
+    fingerprint: Option<Fingerprint>
+}
+
+/// Errors that can occur when loading a [`Source`].
+#[derive(Debug)]
+pub enum LoadError<'a> {
+    /// The file could not be read.
+    Io(Error),
+    /// The file could not be decoded.
+    Encoding(Box<EncodingError<'a>>)
 }
 
 pub struct Sources<'a> {
@@ -14,6 +66,62 @@ pub struct Sources<'a> {
     files: HashMap<Filename<'a>, Source>
 }
 
+/// Errors that can occur when extracting a [`SourceContext`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ContextError<'a> {
+    /// There is no [`Source`] for the file.
+    UnknownFile {
+        /// The name of the file.
+        filename: Filename<'a>
+    },
+    /// An offset is outside the [`Source`].
+    OutOfRange {
+        /// The name of the file.
+        filename: Filename<'a>,
+        /// The offset.
+        offset: Offset
+    },
+    /// An offset is in the middle of a character.
+    NotCharBoundary {
+        /// The name of the file.
+        filename: Filename<'a>,
+        /// The offset.
+        offset: Offset
+    }
+}
+
+/// A sequence of lines, borrowed from a [`Source`].
+///
+/// This allows lines to be served directly from the source text,
+/// without allocating a `String` for each line.
+#[derive(Clone, Copy)]
+pub struct SourceLines<'a>(LinesRepr<'a>);
+
+/// Internal representation of [`SourceLines`].
+#[derive(Clone, Copy)]
+enum LinesRepr<'a> {
+    /// Individual strings.
+    Strings(&'a [String]),
+    /// Ranges in a single text.
+    Ranges {
+        /// The text containing the lines.
+        text: &'a str,
+        /// Start and end of each line in `text`.
+        ranges: &'a [(usize, usize)]
+    }
+}
+
+/// Iterator over [`SourceLines`].
+#[derive(Clone)]
+pub struct SourceLinesIter<'a> {
+    /// The lines being iterated.
+    lines: SourceLines<'a>,
+    /// Index of the next line from the front.
+    front: usize,
+    /// Index after the next line from the back.
+    back: usize
+}
+
 /// Source context, retrieved from a [`FilePosition`].
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum SourceContext<'a> {
@@ -33,7 +141,7 @@ pub enum SourceContext<'a> {
         /// The part of the selected region on the first line.
         first: &'a str,
         /// The middle lines of the selected region.
-        middle: &'a [String],
+        middle: SourceLines<'a>,
         /// The part of the selected region on the last line.
         last: &'a str,
         /// Part of the line after the selected region.
@@ -41,31 +149,495 @@ pub enum SourceContext<'a> {
     }
 }
 
+/// A [`SourceContext`] with surrounding lines, retrieved from a
+/// [`FilePosition`].
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SourceExcerpt<'a> {
+    /// Lines before the context.
+    pub leading: SourceLines<'a>,
+    /// The context itself.
+    pub context: SourceContext<'a>,
+    /// Lines after the context.
+    pub trailing: SourceLines<'a>
+}
+
+impl SourceText {
+    /// Get the text as a `str`.
+    #[inline]
+    fn as_str(&self) -> &str {
+        match self {
+            SourceText::Owned(text) => text,
+            // SAFETY: the mapping was checked to be valid UTF-8 when
+            // loaded, and the caller of `Sources::load_mapped`
+            // guarantees that it has not changed since.
+            SourceText::Mapped(map, start) => unsafe {
+                str::from_utf8_unchecked(&map[*start ..])
+            }
+        }
+    }
+}
+
 impl Source {
     /// Create a new `Source`.
     #[inline]
     fn new() -> Self {
-        Source { content: Vec::new(), line_offsets: LineOffsets::new() }
+        Source { text: SourceText::Owned(String::new()), ranges: Vec::new(),
+                 line_offsets: LineOffsets::new(), encoding: Encoding::Utf8,
+                 offset_map: OffsetMap::identity(),
+                 hash: None, fingerprint: None }
     }
 
     /// Create a new `Source` with a size hint.
     #[inline]
     fn with_capacity(nlines: usize) -> Self {
-        Source { line_offsets: LineOffsets::with_capacity(nlines),
-                 content: Vec::with_capacity(nlines) }
+        Source { text: SourceText::Owned(String::new()),
+                 ranges: Vec::with_capacity(nlines),
+                 line_offsets: LineOffsets::with_capacity(nlines),
+                 encoding: Encoding::Utf8, offset_map: OffsetMap::identity(),
+                 hash: None, fingerprint: None }
+    }
+
+    /// Create a new `Source` from the entire contents of a file,
+    /// whose [`ContentHash`] is `hash`.
+    ///
+    /// Lines are split at `\n`, and line terminators (including a
+    /// `\r` before the `\n`) are not included in the lines.  The
+    /// lines are found with a single vectorized scan of `text`, and
+    /// are served directly from it.
+    fn from_text(text: SourceText, hash: ContentHash) -> Self {
+        let content = text.as_str().as_bytes();
+        let nlines = memchr_iter(b'\n', content).count() + 1;
+        let mut ranges = Vec::with_capacity(nlines);
+        let mut line_offsets = LineOffsets::with_capacity(nlines);
+        let mut start = 0;
+        let mut add = |start: usize, end: usize| {
+            let stripped = if end > start && content[end - 1] == b'\r' {
+                end - 1
+            } else {
+                end
+            };
+
+            ranges.push((start, stripped));
+            line_offsets.push_line(start);
+        };
+
+        for end in memchr_iter(b'\n', content) {
+            add(start, end);
+            start = end + 1;
+        }
+
+        if start < content.len() {
+            add(start, content.len());
+        }
+
+        Source { text, ranges, line_offsets,
+                 encoding: Encoding::Utf8, offset_map: OffsetMap::identity(),
+                 hash: Some(hash), fingerprint: None }
+    }
+
+    /// Get the text of this `Source` as an owned `String`, copying it
+    /// out of the mapping if it is memory-mapped.
+    fn owned_text(&mut self) -> &mut String {
+        if let SourceText::Mapped(..) = self.text {
+            let text = String::from(self.text.as_str());
+
+            self.text = SourceText::Owned(text);
+        }
+
+        match &mut self.text {
+            SourceText::Owned(text) => text,
+            SourceText::Mapped(..) => unreachable!()
+        }
+    }
+
+    /// Get line `idx` (starting at 0) of this `Source`.
+    #[inline]
+    fn line(&self, idx: usize) -> &str {
+        let (start, end) = self.ranges[idx];
+
+        &self.text.as_str()[start .. end]
+    }
+
+    /// Find the line (starting at 1) and column of `offset`, checking
+    /// that it is within this `Source` and on a character boundary.
+    ///
+    /// Columns past the end of a line's text, other than on the last
+    /// line, are moved to the end of the text.  The offset of the end
+    /// of the text is at the end of the last line, even if the text
+    /// ends with a line break.
+    fn locate<'a>(&self, filename: Filename<'a>, offset: usize) ->
+        Result<(usize, usize), ContextError<'a>> {
+        let (line, col) = self.line_offsets.lookup(Offset::from(offset));
+
+        if line == 0 || line > self.ranges.len() {
+            return Err(ContextError::OutOfRange {
+                filename, offset: Offset::from(offset)
+            });
+        }
+
+        let content = self.line(line - 1);
+        // The last line's terminator, if the text has one, is the rest
+        // of the text after the start of the line.
+        let last_len = self.text.as_str().len() - self.ranges[line - 1].0;
+        let col = if col <= content.len() {
+            col
+        } else if line < self.ranges.len() || col <= last_len {
+            content.len()
+        } else {
+            return Err(ContextError::OutOfRange {
+                filename, offset: Offset::from(offset)
+            });
+        };
+
+        if content.is_char_boundary(col) {
+            Ok((line, col))
+        } else {
+            Err(ContextError::NotCharBoundary {
+                filename, offset: Offset::from(offset)
+            })
+        }
     }
 
     /// Shrink the structures of this `Source` to fit its current size.
     #[inline]
     pub fn shrink_to_fit(&mut self) {
         self.line_offsets.shrink_to_fit();
-        self.content.shrink_to_fit();
+        self.ranges.shrink_to_fit();
+
+        if let SourceText::Owned(text) = &mut self.text {
+            text.shrink_to_fit()
+        }
     }
 
     #[inline]
     pub fn push_line(&mut self, start: usize, line: String) {
-        self.content.push(line);
+        let text = self.owned_text();
+        let begin = text.len();
+
+        text.push_str(&line);
+
+        let end = text.len();
+
+        self.ranges.push((begin, end));
         self.line_offsets.push_line(start);
+        self.hash = None;
+        self.fingerprint = None;
+    }
+
+    /// Get the lines of this `Source`.
+    #[inline]
+    pub fn lines(&self) -> SourceLines<'_> {
+        SourceLines(LinesRepr::Ranges { text: self.text.as_str(),
+                                        ranges: &self.ranges })
+    }
+
+    /// Check whether this `Source` is served from a memory-mapped
+    /// file.
+    ///
+    /// See [`Sources::load_mapped`].
+    #[inline]
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.text, SourceText::Mapped(..))
+    }
+
+    /// Get the [`LineOffsets`] for this `Source`.
+    #[inline]
+    pub fn line_offsets(&self) -> &LineOffsets {
+        &self.line_offsets
+    }
+
+    /// Get the [`Encoding`] this `Source` was decoded from.
+    #[inline]
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Get the [`OffsetMap`] from offsets in this `Source` to offsets
+    /// in the original file.
+    ///
+    /// This is the identity unless the file had a byte order mark or
+    /// was transcoded.
+    #[inline]
+    pub fn offset_map(&self) -> &OffsetMap {
+        &self.offset_map
+    }
+
+    /// Get the [`ContentHash`] of this `Source`.
+    ///
+    /// This is only available if the entire contents were supplied
+    /// at once, with [`Sources::load`], [`Sources::load_encoded`],
+    /// [`Sources::load_mapped`], or [`Sources::add_str`].  It is the
+    /// hash of the decoded text.
+    #[inline]
+    pub fn hash(&self) -> Option<&ContentHash> {
+        self.hash.as_ref()
+    }
+
+    /// Get the [`Fingerprint`] of the file this `Source` was loaded
+    /// from.
+    ///
+    /// This is only available if it was loaded with [`Sources::load`],
+    /// [`Sources::load_encoded`], or [`Sources::load_mapped`].
+    #[inline]
+    pub fn fingerprint(&self) -> Option<&Fingerprint> {
+        self.fingerprint.as_ref()
+    }
+}
+
+impl<'a> SourceLines<'a> {
+    /// Get the number of lines.
+    #[inline]
+    pub fn len(&self) -> usize {
+        match self.0 {
+            LinesRepr::Strings(strs) => strs.len(),
+            LinesRepr::Ranges { ranges, .. } => ranges.len()
+        }
+    }
+
+    /// Check whether there are no lines.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get line `idx` (starting at 0), if it exists.
+    #[inline]
+    pub fn get(&self, idx: usize) -> Option<&'a str> {
+        match self.0 {
+            LinesRepr::Strings(strs) => strs.get(idx).map(|s| &s[..]),
+            LinesRepr::Ranges { text, ranges } =>
+                ranges.get(idx).map(|(start, end)| &text[*start .. *end])
+        }
+    }
+
+    /// Get the lines in the range `start .. end`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    #[inline]
+    pub fn slice(&self, start: usize, end: usize) -> SourceLines<'a> {
+        match self.0 {
+            LinesRepr::Strings(strs) =>
+                SourceLines(LinesRepr::Strings(&strs[start .. end])),
+            LinesRepr::Ranges { text, ranges } =>
+                SourceLines(LinesRepr::Ranges {
+                    text, ranges: &ranges[start .. end]
+                })
+        }
+    }
+
+    /// Get an iterator over the lines.
+    #[inline]
+    pub fn iter(&self) -> SourceLinesIter<'a> {
+        SourceLinesIter { lines: *self, front: 0, back: self.len() }
+    }
+}
+
+impl Debug for SourceLines<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl Eq for SourceLines<'_> {}
+
+impl<'a> From<&'a [String]> for SourceLines<'a> {
+    #[inline]
+    fn from(strs: &'a [String]) -> SourceLines<'a> {
+        SourceLines(LinesRepr::Strings(strs))
+    }
+}
+
+impl<'a> From<&'a Vec<String>> for SourceLines<'a> {
+    #[inline]
+    fn from(strs: &'a Vec<String>) -> SourceLines<'a> {
+        SourceLines(LinesRepr::Strings(strs))
+    }
+}
+
+impl Hash for SourceLines<'_> {
+    fn hash<H>(&self, state: &mut H)
+    where H: Hasher {
+        self.len().hash(state);
+
+        for line in self.iter() {
+            line.hash(state)
+        }
+    }
+}
+
+impl Index<usize> for SourceLines<'_> {
+    type Output = str;
+
+    #[inline]
+    fn index(&self, idx: usize) -> &str {
+        match self.get(idx) {
+            Some(line) => line,
+            None => panic!("line index {} out of range for {} lines",
+                           idx, self.len())
+        }
+    }
+}
+
+impl<'a> IntoIterator for SourceLines<'a> {
+    type Item = &'a str;
+    type IntoIter = SourceLinesIter<'a>;
+
+    #[inline]
+    fn into_iter(self) -> SourceLinesIter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'_ SourceLines<'a> {
+    type Item = &'a str;
+    type IntoIter = SourceLinesIter<'a>;
+
+    #[inline]
+    fn into_iter(self) -> SourceLinesIter<'a> {
+        self.iter()
+    }
+}
+
+impl Ord for SourceLines<'_> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl PartialEq for SourceLines<'_> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<S> PartialEq<[S]> for SourceLines<'_>
+where S: AsRef<str> {
+    #[inline]
+    fn eq(&self, other: &[S]) -> bool {
+        self.len() == other.len() &&
+        self.iter().zip(other.iter()).all(|(a, b)| a == b.as_ref())
+    }
+}
+
+impl PartialOrd for SourceLines<'_> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a> Iterator for SourceLinesIter<'a> {
+    type Item = &'a str;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a str> {
+        if self.front < self.back {
+            let out = self.lines.get(self.front);
+
+            self.front += 1;
+
+            out
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for SourceLinesIter<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front < self.back {
+            self.back -= 1;
+
+            self.lines.get(self.back)
+        } else {
+            None
+        }
+    }
+}
+
+impl ExactSizeIterator for SourceLinesIter<'_> {}
+
+impl Display for ContextError<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            ContextError::UnknownFile { filename } =>
+                write!(f, "no source for {}", filename),
+            ContextError::OutOfRange { filename, offset } =>
+                write!(f, "offset {} is outside {}", offset, filename),
+            ContextError::NotCharBoundary { filename, offset } =>
+                write!(f, "offset {} in {} is not on a character boundary",
+                       offset, filename)
+        }
+    }
+}
+
+impl std::error::Error for ContextError<'_> {}
+
+impl Display for LoadError<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            LoadError::Io(err) => Display::fmt(err, f),
+            LoadError::Encoding(err) => Display::fmt(err, f)
+        }
+    }
+}
+
+impl std::error::Error for LoadError<'_> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io(err) => Some(err),
+            LoadError::Encoding(_) => None
+        }
+    }
+}
+
+impl From<Error> for LoadError<'_> {
+    #[inline]
+    fn from(err: Error) -> Self {
+        LoadError::Io(err)
+    }
+}
+
+impl<'a> From<EncodingError<'a>> for LoadError<'a> {
+    #[inline]
+    fn from(err: EncodingError<'a>) -> Self {
+        LoadError::Encoding(Box::new(err))
+    }
+}
+
+/// Check that `filename` can be loaded from disk.
+#[inline]
+fn check_loadable(filename: Filename<'_>) -> Result<(), Error> {
+    if filename.is_virtual() {
+        Err(Error::new(ErrorKind::NotFound,
+                       format!("cannot load virtual file {}", filename)))
+    } else {
+        Ok(())
+    }
+}
+
+/// Get the [`ContentHash`] of `text`, decoded from `bytes`, whose
+/// hash is `hash`.
+///
+/// This reuses `hash` if `text` borrows all of `bytes` unchanged.
+#[inline]
+fn text_hash(bytes: &[u8], hash: ContentHash, text: &str) -> ContentHash {
+    if ptr::eq(text.as_bytes(), bytes) {
+        hash
+    } else {
+        ContentHash::of(text.as_bytes())
     }
 }
 
@@ -90,96 +662,305 @@ impl<'a> Sources<'a> {
     }
 
     /// Get the [`SourceContext`] for a given [`OffsetPosition`] in `file`.
+    ///
+    /// This returns `None` if the context cannot be extracted; see
+    /// [`try_get_ctx`](Sources::try_get_ctx).
+    #[inline]
     pub fn get_ctx(&'a self, file: Filename<'a>, pos: &'a OffsetPosition) ->
         Option<SourceContext<'a>> {
-        match self.files.get(&file) {
-            Some(src) => match pos {
-                OffsetPosition::Span { start, len } => {
-                    let end = *start + *len;
-                    let (start_line, start_col) =
-                        src.line_offsets.lookup(*start);
-                    let (end_line, end_col) =
-                        src.line_offsets.lookup(end);
-                    let end_line = if end_col != 0 {
-                        end_line
-                    } else {
-                        end_line - 1
+        self.try_get_ctx(file, pos).ok()
+    }
+
+    /// Get the [`SourceContext`] for a given [`OffsetPosition`] in
+    /// `file`, or a [`ContextError`] describing why it cannot be
+    /// extracted.
+    ///
+    /// The position is fully validated against the [`Source`], so a
+    /// position from a different version of the file results in an
+    /// error, not a panic.  Offsets between the end of a line's text
+    /// and the start of the next line (that is, within a line
+    /// terminator) refer to the end of the line.
+    #[inline]
+    pub fn try_get_ctx(&'a self, file: Filename<'a>,
+                       pos: &'a OffsetPosition) ->
+        Result<SourceContext<'a>, ContextError<'a>> {
+        self.extract(file, pos).map(|(_, ctx, _, _)| ctx)
+    }
+
+    /// Get the [`SourceExcerpt`] for a given [`OffsetPosition`] in
+    /// `file`, with up to `leading` lines before it and up to
+    /// `trailing` lines after it.
+    ///
+    /// This returns `None` if the context cannot be extracted; see
+    /// [`try_get_excerpt`](Sources::try_get_excerpt).
+    #[inline]
+    pub fn get_excerpt(&'a self, file: Filename<'a>, pos: &'a OffsetPosition,
+                       leading: usize, trailing: usize) ->
+        Option<SourceExcerpt<'a>> {
+        self.try_get_excerpt(file, pos, leading, trailing).ok()
+    }
+
+    /// Get the [`SourceExcerpt`] for a given [`OffsetPosition`] in
+    /// `file`, with up to `leading` lines before it and up to
+    /// `trailing` lines after it, or a [`ContextError`] describing why
+    /// it cannot be extracted.
+    ///
+    /// See [`try_get_ctx`](Sources::try_get_ctx).
+    pub fn try_get_excerpt(&'a self, file: Filename<'a>,
+                           pos: &'a OffsetPosition, leading: usize,
+                           trailing: usize) ->
+        Result<SourceExcerpt<'a>, ContextError<'a>> {
+        let (src, ctx, start_line, end_line) = self.extract(file, pos)?;
+        let lines = src.lines();
+        let first = (start_line - 1).saturating_sub(leading);
+        let last = end_line.saturating_add(trailing).min(lines.len());
+
+        Ok(SourceExcerpt { leading: lines.slice(first, start_line - 1),
+                           context: ctx,
+                           trailing: lines.slice(end_line, last) })
+    }
+
+    /// Extract the [`SourceContext`] for `pos` in `file`, along with
+    /// its [`Source`] and its first and last lines (starting at 1).
+    fn extract(&'a self, file: Filename<'a>, pos: &'a OffsetPosition) ->
+        Result<(&'a Source, SourceContext<'a>, usize, usize),
+               ContextError<'a>> {
+        let src = match self.files.get(&file) {
+            Some(src) => src,
+            None => return Err(ContextError::UnknownFile { filename: file })
+        };
+
+        match pos {
+            OffsetPosition::Span { start, len } => {
+                let start = usize::from(start);
+                let end = match start.checked_add(usize::from(len)) {
+                    Some(end) => end,
+                    None => return Err(ContextError::OutOfRange {
+                        filename: file, offset: Offset::from(start)
+                    })
+                };
+                let (start_line, start_col) = src.locate(file, start)?;
+                let (end_line, end_col) =
+                    match src.line_offsets.lookup(Offset::from(end)) {
+                        (end_line, 0) if end_line > start_line =>
+                            (end_line - 1, src.line(end_line - 2).len()),
+                        _ => src.locate(file, end)?
                     };
 
-                    if start_line == end_line {
-                        let content = &src.content[start_line - 1];
-                        let end_col = if end_col != 0 {
-                            end_col
-                        } else {
-                            content.len()
-                        };
-                        let (prefix, rest) = content.split_at(start_col);
-
-                        let out = if end_col < content.len() {
-                            let (selected, suffix) = rest.split_at(len.into());
-
-                            SourceContext::Single {
-                                prefix: prefix, selected: selected,
-                                suffix: suffix
-                            }
-                        } else {
-                            SourceContext::Single {
-                                prefix: prefix, selected: rest, suffix: ""
-                            }
-                        };
-
-                        Some(out)
-                    } else {
-                        let content = &src.content[start_line - 1];
-                        let (prefix, first) = content.split_at(start_col);
-                        let middle = &src.content[start_line .. end_line - 1];
-                        let content = &src.content[end_line - 1];
-                        let end_col = if end_col != 0 {
-                            end_col
-                        } else {
-                            content.len()
-                        };
-                        let out = if end_col < content.len() {
-                            let (last, suffix) = content.split_at(end_col);
-
-                            SourceContext::Multiple {
-                                prefix: prefix, first: first, middle: middle,
-                                last: last, suffix: suffix
-                            }
-                        } else {
-                            SourceContext::Multiple {
-                                prefix: prefix, first: first, middle: middle,
-                                last: content, suffix: ""
-                            }
-                        };
-
-                        Some(out)
-                    }
-                },
-                OffsetPosition::Point { point } => {
-                    let (line, col) = src.line_offsets.lookup(*point);
-                    let content = &src.content[line - 1];
-                    let (prefix, rest) = content.split_at(col);
-
-                    let out = if col < content.len() {
-                        let (selected, suffix) = rest.split_at(1);
-
-                        SourceContext::Single {
-                            prefix: prefix, selected: selected, suffix: suffix
-                        }
-                    } else {
-                        SourceContext::Single {
-                            prefix: prefix, selected: rest, suffix: ""
-                        }
+                if start_line == end_line {
+                    let content = src.line(start_line - 1);
+                    let (prefix, rest) = content.split_at(start_col);
+                    let (selected, suffix) =
+                        rest.split_at(end_col - start_col);
+
+                    let ctx = SourceContext::Single {
+                        prefix, selected, suffix
+                    };
+
+                    Ok((src, ctx, start_line, end_line))
+                } else {
+                    let content = src.line(start_line - 1);
+                    let (prefix, first) = content.split_at(start_col);
+                    let middle = src.lines().slice(start_line, end_line - 1);
+                    let content = src.line(end_line - 1);
+                    let (last, suffix) = content.split_at(end_col);
+
+                    let ctx = SourceContext::Multiple {
+                        prefix, first, middle,
+                        last, suffix
                     };
 
-                    Some(out)
+                    Ok((src, ctx, start_line, end_line))
                 }
             },
-            None => None
+            OffsetPosition::Point { point } => {
+                let (line, col) = src.locate(file, usize::from(point))?;
+                let content = src.line(line - 1);
+                let (prefix, rest) = content.split_at(col);
+                let width = rest.chars().next().map_or(0, char::len_utf8);
+                let (selected, suffix) = rest.split_at(width);
+                let ctx = SourceContext::Single {
+                    prefix, selected, suffix
+                };
+
+                Ok((src, ctx, line, line))
+            }
         }
     }
 
+    /// Get the [`Source`] for `filename`, if there is one.
+    #[inline]
+    pub fn get(&self, filename: Filename<'a>) -> Option<&Source> {
+        self.files.get(&filename)
+    }
+
+    /// Load (or reload) the [`Source`] for `filename` from disk.
+    ///
+    /// The file is decoded as UTF-8, unless it starts with a byte
+    /// order mark indicating otherwise.  See
+    /// [`load_encoded`](Sources::load_encoded).
+    #[inline]
+    pub fn load(&mut self, filename: Filename<'a>) ->
+        Result<&Source, LoadError<'a>> {
+        self.load_encoded(filename, Encoding::Utf8)
+    }
+
+    /// Load (or reload) the [`Source`] for `filename` from disk,
+    /// decoding it from the declared encoding `encoding`.
+    ///
+    /// A byte order mark at the start of the file overrides
+    /// `encoding`, and is not included in the source.  Text that is
+    /// not UTF-8 is transcoded, and the [`OffsetMap`] of the source
+    /// can be used to recover offsets in the original file.  If the
+    /// file cannot be decoded, a [`LoadError::Encoding`] is returned,
+    /// which is a [`Message`](crate::messages::Message) positioned at
+    /// the first invalid byte.
+    ///
+    /// This records a [`Fingerprint`] of the file, which can be used
+    /// to detect later changes (see [`changed`](Sources::changed)).
+    /// Virtual files cannot be loaded; use
+    /// [`add_str`](Sources::add_str) instead.
+    pub fn load_encoded(&mut self, filename: Filename<'a>,
+                        encoding: Encoding) ->
+        Result<&Source, LoadError<'a>> {
+        check_loadable(filename)?;
+
+        let mut file = File::open(filename.as_os_str())?;
+        let metadata = file.metadata()?;
+        let mut bytes = Vec::with_capacity(metadata.len() as usize);
+
+        file.read_to_end(&mut bytes)?;
+
+        let hash = ContentHash::of(&bytes);
+        let decoded = decode(&bytes, encoding)
+            .map_err(|err| EncodingError::new(filename, &bytes, err))?;
+        let text_hash = text_hash(&bytes, hash, &decoded.text);
+        let text = String::from(decoded.text);
+        let mut src = Source::from_text(SourceText::Owned(text), text_hash);
+
+        src.encoding = decoded.encoding;
+        src.offset_map = decoded.offset_map;
+        src.fingerprint = Some(Fingerprint::new(&metadata, hash));
+
+        Ok(self.insert(filename, src))
+    }
+
+    /// Load (or reload) the [`Source`] for `filename` by mapping it
+    /// into memory read-only.
+    ///
+    /// This is intended for very large files.  The contents are
+    /// checked to be valid UTF-8 and scanned for line breaks once,
+    /// and [`get_ctx`](Sources::get_ctx) then serves lines directly
+    /// from the mapping.  Byte order marks are handled as in
+    /// [`load`](Sources::load); a file that must be transcoded is
+    /// copied into memory instead of being served from the mapping.
+    /// As with [`load`](Sources::load), a [`Fingerprint`] is
+    /// recorded.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, by this process or
+    /// any other, for as long as the [`Source`] exists (that is, until
+    /// it is reloaded or this `Sources` is dropped).  Text borrowed
+    /// from the `Source` is read directly from the mapping, and is
+    /// only checked to be valid UTF-8 when the file is loaded.
+    /// [`changed`](Sources::changed) can detect modifications after
+    /// the fact, but does not prevent them.
+    pub unsafe fn load_mapped(&mut self, filename: Filename<'a>) ->
+        Result<&Source, LoadError<'a>> {
+        check_loadable(filename)?;
+
+        let file = File::open(filename.as_os_str())?;
+        let metadata = file.metadata()?;
+
+        if metadata.len() == 0 {
+            // Empty files cannot be mapped on all platforms.
+            let hash = ContentHash::of(&[]);
+            let mut src = Source::from_text(SourceText::Owned(String::new()),
+                                            hash);
+
+            src.fingerprint = Some(Fingerprint::new(&metadata, hash));
+
+            return Ok(self.insert(filename, src));
+        }
+
+        // SAFETY: the mapping is read-only, and the caller guarantees
+        // that the file is not modified while the `Source` exists.
+        let map = unsafe { Mmap::map(&file)? };
+        let hash = ContentHash::of(&map);
+        let decoded = decode(&map, Encoding::Utf8)
+            .map_err(|err| EncodingError::new(filename, &map, err))?;
+        let text_hash = text_hash(&map, hash, &decoded.text);
+        let encoding = decoded.encoding;
+        let offset_map = decoded.offset_map;
+        let text = match decoded.text {
+            Cow::Borrowed(text) => {
+                let start = map.len() - text.len();
+
+                SourceText::Mapped(map, start)
+            },
+            Cow::Owned(text) => SourceText::Owned(text)
+        };
+        let mut src = Source::from_text(text, text_hash);
+
+        src.encoding = encoding;
+        src.offset_map = offset_map;
+        src.fingerprint = Some(Fingerprint::new(&metadata, hash));
+
+        Ok(self.insert(filename, src))
+    }
+
+    /// Insert `src` for `filename`, replacing any existing [`Source`].
+    fn insert(&mut self, filename: Filename<'a>, src: Source) -> &Source {
+        match self.files.entry(filename) {
+            Entry::Occupied(mut ent) => {
+                ent.insert(src);
+
+                ent.into_mut()
+            },
+            Entry::Vacant(ent) => ent.insert(src)
+        }
+    }
+
+    /// Add a [`Source`] for `filename` with contents `content`, if it
+    /// doesn't already exist, and return a reference to it.
+    ///
+    /// This is intended for virtual files, such as standard input or
+    /// in-memory test fixtures.
+    #[inline]
+    pub fn add_str(&mut self, filename: Filename<'a>, content: &str) ->
+        Option<&Source> {
+        match self.files.entry(filename) {
+            Entry::Vacant(ent) => Some(ent.insert(Source::from_text(
+                SourceText::Owned(String::from(content)),
+                ContentHash::of(content.as_bytes())
+            ))),
+            Entry::Occupied(_) => None
+        }
+    }
+
+    /// Get all files loaded with [`load`](Sources::load) that have
+    /// changed on disk since they were loaded.
+    ///
+    /// Files that can no longer be read are considered to have
+    /// changed.  The files are sorted by path.  See
+    /// [`Fingerprint::changed`].
+    pub fn changed(&self) -> Vec<Filename<'a>> {
+        let mut changed: Vec<Filename<'a>> =
+            self.files.iter().filter_map(|(filename, src)| {
+                src.fingerprint.as_ref().and_then(|fingerprint| {
+                    match fingerprint.changed(*filename) {
+                        Ok(false) => None,
+                        _ => Some(*filename)
+                    }
+                })
+            }).collect();
+
+        changed.sort_by(|a, b| a.as_os_str().cmp(b.as_os_str()));
+
+        changed
+    }
+
     /// Add a [`Source`] for `filename` if it doesn't already exist,
     /// and return a mutable reference to it.
     #[inline]
@@ -202,3 +983,10 @@ impl<'a> Sources<'a> {
         }
     }
 }
+
+impl Default for Sources<'_> {
+    #[inline]
+    fn default() -> Self {
+        Sources::new()
+    }
+}
@@ -0,0 +1,645 @@
+use crate::files::FileKind;
+use crate::files::Filename;
+use crate::files::Filenames;
+use crate::symbol::Symbol;
+use crate::symbol::Symbols;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::io::Error;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// Magic number for binary symbol tables.
+const SYMBOL_MAGIC: &[u8; 4] = b"CTST";
+
+/// Magic number for binary filename tables.
+const FILENAME_MAGIC: &[u8; 4] = b"CTFT";
+
+/// Version of the binary table format.
+const VERSION: u8 = 1;
+
+/// Flag for a generated symbol in the binary format.
+const GENERATED: u8 = 1;
+
+/// A table assigning indices to a set of [`Symbol`]s.
+///
+/// This is used to write out data structures containing symbols,
+/// which cannot be written out directly, as their identity is a
+/// pointer.  Instead, each symbol is written as its index in the
+/// table, and the table itself is written out once.
+///
+/// Reading the table back with [`read`](SymbolTable::read) or
+/// [`EncodedSymbols::decode`] interns each entry in a [`Symbols`],
+/// reproducing identical symbols, at the same indices.  Symbols
+/// created with [`gensym`](Symbols::gensym) are regenerated with the
+/// same base name; they remain distinct from all other symbols, but
+/// may be given a different number.
+pub struct SymbolTable<'a> {
+    /// Symbols, by index.
+    syms: Vec<Symbol<'a>>,
+    /// Indices, by symbol.
+    indices: HashMap<Symbol<'a>, usize>
+}
+
+/// A table assigning indices to a set of [`Filename`]s.
+///
+/// This is the equivalent of [`SymbolTable`] for filenames.
+pub struct FilenameTable<'a> {
+    /// Filenames, by index.
+    filenames: Vec<Filename<'a>>,
+    /// Indices, by filename.
+    indices: HashMap<Filename<'a>, usize>
+}
+
+/// An entry in an [`EncodedSymbols`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct EncodedSymbol {
+    /// Text of the symbol.
+    pub text: String,
+    /// Whether the symbol was created by [`gensym`](Symbols::gensym).
+    ///
+    /// If so, `text` is the base name.
+    pub generated: bool
+}
+
+/// Owned, encoded form of a [`SymbolTable`].
+///
+/// This is independent of any [`Symbols`], and can be written out
+/// with serde (if the `serde` feature is enabled), or to a compact
+/// binary format with [`write`](EncodedSymbols::write).
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct EncodedSymbols {
+    /// The entries, by index.
+    pub entries: Vec<EncodedSymbol>
+}
+
+/// An entry in an [`EncodedFilenames`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct EncodedFilename {
+    /// The kind of the filename.
+    pub kind: FileKind,
+    /// The canonical path of a file on disk, or the name of a
+    /// virtual file.
+    pub path: String
+}
+
+/// Owned, encoded form of a [`FilenameTable`].
+///
+/// This is the equivalent of [`EncodedSymbols`] for filenames.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct EncodedFilenames {
+    /// The entries, by index.
+    pub entries: Vec<EncodedFilename>
+}
+
+/// Write `val` to `out` as a LEB128 variable-length integer.
+///
+/// This is the encoding used for indices and lengths in the binary
+/// table format, and can be used to write indices in payloads.
+pub fn write_index<W>(out: &mut W, val: usize) -> Result<(), Error>
+where W: Write {
+    let mut val = val;
+
+    loop {
+        let byte = (val & 0x7f) as u8;
+
+        val >>= 7;
+
+        if val == 0 {
+            return out.write_all(&[byte]);
+        }
+
+        out.write_all(&[byte | 0x80])?;
+    }
+}
+
+/// Read a LEB128 variable-length integer written by [`write_index`].
+///
+/// Values that do not fit in a `usize` are errors.
+pub fn read_index<R>(input: &mut R) -> Result<usize, Error>
+where R: Read {
+    let mut val: usize = 0;
+    let mut shift = 0;
+
+    loop {
+        let mut byte = [0];
+
+        input.read_exact(&mut byte)?;
+
+        let bits = (byte[0] & 0x7f) as usize;
+
+        if shift >= usize::BITS || (bits << shift) >> shift != bits {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "index out of range"));
+        }
+
+        val |= bits << shift;
+        shift += 7;
+
+        if byte[0] & 0x80 == 0 {
+            return Ok(val);
+        }
+    }
+}
+
+/// Write a length-prefixed string.
+fn write_str<W>(out: &mut W, s: &str) -> Result<(), Error>
+where W: Write {
+    write_index(out, s.len())?;
+    out.write_all(s.as_bytes())
+}
+
+/// Read a length-prefixed string.
+fn read_str<R>(input: &mut R) -> Result<String, Error>
+where R: Read {
+    let len = read_index(input)?;
+    let mut buf = Vec::new();
+
+    input.take(len as u64).read_to_end(&mut buf)?;
+
+    if buf.len() != len {
+        return Err(Error::new(ErrorKind::UnexpectedEof,
+                              "truncated string"));
+    }
+
+    String::from_utf8(buf).map_err(|err| Error::new(ErrorKind::InvalidData,
+                                                    err))
+}
+
+/// Write a table header.
+fn write_header<W>(out: &mut W, magic: &[u8; 4], len: usize) ->
+    Result<(), Error>
+where W: Write {
+    out.write_all(magic)?;
+    out.write_all(&[VERSION])?;
+    write_index(out, len)
+}
+
+/// Read a table header, returning the number of entries.
+fn read_header<R>(input: &mut R, magic: &[u8; 4]) -> Result<usize, Error>
+where R: Read {
+    let mut header = [0; 5];
+
+    input.read_exact(&mut header)?;
+
+    if &header[.. 4] != magic {
+        return Err(Error::new(ErrorKind::InvalidData, "bad magic number"));
+    }
+
+    if header[4] != VERSION {
+        return Err(Error::new(ErrorKind::InvalidData,
+                              format!("unsupported version {}", header[4])));
+    }
+
+    read_index(input)
+}
+
+/// Get the binary code for a [`FileKind`].
+fn kind_code(kind: FileKind) -> u8 {
+    match kind {
+        FileKind::Disk => 0,
+        FileKind::Stdin => 1,
+        FileKind::Repl => 2,
+        FileKind::Generated => 3,
+        FileKind::Memory => 4,
+        FileKind::Archive => 5
+    }
+}
+
+/// Get the [`FileKind`] for a binary code.
+fn code_kind(code: u8) -> Result<FileKind, Error> {
+    match code {
+        0 => Ok(FileKind::Disk),
+        1 => Ok(FileKind::Stdin),
+        2 => Ok(FileKind::Repl),
+        3 => Ok(FileKind::Generated),
+        4 => Ok(FileKind::Memory),
+        5 => Ok(FileKind::Archive),
+        _ => Err(Error::new(ErrorKind::InvalidData,
+                            format!("bad file kind {}", code)))
+    }
+}
+
+/// Get the error for a bad index.
+fn bad_index(idx: usize) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("bad table index {}", idx))
+}
+
+impl<'a> SymbolTable<'a> {
+    /// Create a new, empty `SymbolTable`.
+    #[inline]
+    pub fn new() -> Self {
+        SymbolTable { syms: Vec::new(), indices: HashMap::new() }
+    }
+
+    /// Create a new, empty `SymbolTable` with a size hint.
+    #[inline]
+    pub fn with_capacity(size: usize) -> Self {
+        SymbolTable { syms: Vec::with_capacity(size),
+                      indices: HashMap::with_capacity(size) }
+    }
+
+    /// Get the number of symbols in the table.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.syms.len()
+    }
+
+    /// Check whether the table is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.syms.is_empty()
+    }
+
+    /// Get the index of `sym`, adding it to the table if necessary.
+    #[inline]
+    pub fn insert(&mut self, sym: Symbol<'a>) -> usize {
+        match self.indices.entry(sym) {
+            Entry::Occupied(ent) => *ent.get(),
+            Entry::Vacant(ent) => {
+                let idx = self.syms.len();
+
+                self.syms.push(sym);
+                ent.insert(idx);
+
+                idx
+            }
+        }
+    }
+
+    /// Add `sym` at the next index, even if it is already present.
+    ///
+    /// This keeps indices intact when decoding tables with
+    /// duplicate entries.
+    #[inline]
+    fn push(&mut self, sym: Symbol<'a>) {
+        self.indices.entry(sym).or_insert(self.syms.len());
+        self.syms.push(sym);
+    }
+
+    /// Get the index of `sym`, if it is in the table.
+    #[inline]
+    pub fn index(&self, sym: Symbol<'a>) -> Option<usize> {
+        self.indices.get(&sym).copied()
+    }
+
+    /// Get the [`Symbol`] at `idx`.
+    #[inline]
+    pub fn get(&self, idx: usize) -> Option<Symbol<'a>> {
+        self.syms.get(idx).copied()
+    }
+
+    /// Get all symbols in the table, by index.
+    #[inline]
+    pub fn symbols(&self) -> &[Symbol<'a>] {
+        &self.syms
+    }
+
+    /// Write a reference to `sym` to `out`, adding it to the table
+    /// if necessary.
+    ///
+    /// The reference is written as its index, using [`write_index`].
+    #[inline]
+    pub fn write_ref<W>(&mut self, sym: Symbol<'a>, out: &mut W) ->
+        Result<(), Error>
+    where W: Write {
+        write_index(out, self.insert(sym))
+    }
+
+    /// Read a reference written by [`write_ref`](SymbolTable::write_ref).
+    #[inline]
+    pub fn read_ref<R>(&self, input: &mut R) -> Result<Symbol<'a>, Error>
+    where R: Read {
+        let idx = read_index(input)?;
+
+        self.get(idx).ok_or_else(|| bad_index(idx))
+    }
+
+    /// Encode this table, using `symbols` to identify generated
+    /// symbols.
+    pub fn encode(&self, symbols: &Symbols<'_>) -> EncodedSymbols {
+        let entries = self.syms.iter().map(|sym| {
+            if symbols.is_generated(*sym) {
+                let text = match sym[..].rfind('%') {
+                    Some(idx) => &sym[.. idx],
+                    None => &sym[..]
+                };
+
+                EncodedSymbol { text: String::from(text), generated: true }
+            } else {
+                EncodedSymbol { text: String::from(*sym), generated: false }
+            }
+        }).collect();
+
+        EncodedSymbols { entries }
+    }
+
+    /// Write this table to `out` in binary format.
+    ///
+    /// See [`EncodedSymbols::write`].
+    #[inline]
+    pub fn write<W>(&self, symbols: &Symbols<'_>, out: &mut W) ->
+        Result<(), Error>
+    where W: Write {
+        self.encode(symbols).write(out)
+    }
+
+    /// Read a table in binary format from `input`, interning its
+    /// contents in `symbols`.
+    ///
+    /// See [`EncodedSymbols::read`].
+    #[inline]
+    pub fn read<R>(input: &mut R, symbols: &mut Symbols<'a>) ->
+        Result<Self, Error>
+    where R: Read {
+        Ok(EncodedSymbols::read(input)?.decode(symbols))
+    }
+}
+
+impl Default for SymbolTable<'_> {
+    #[inline]
+    fn default() -> Self {
+        SymbolTable::new()
+    }
+}
+
+impl<'a> FilenameTable<'a> {
+    /// Create a new, empty `FilenameTable`.
+    #[inline]
+    pub fn new() -> Self {
+        FilenameTable { filenames: Vec::new(), indices: HashMap::new() }
+    }
+
+    /// Create a new, empty `FilenameTable` with a size hint.
+    #[inline]
+    pub fn with_capacity(size: usize) -> Self {
+        FilenameTable { filenames: Vec::with_capacity(size),
+                        indices: HashMap::with_capacity(size) }
+    }
+
+    /// Get the number of filenames in the table.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.filenames.len()
+    }
+
+    /// Check whether the table is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.filenames.is_empty()
+    }
+
+    /// Get the index of `filename`, adding it to the table if necessary.
+    #[inline]
+    pub fn insert(&mut self, filename: Filename<'a>) -> usize {
+        match self.indices.entry(filename) {
+            Entry::Occupied(ent) => *ent.get(),
+            Entry::Vacant(ent) => {
+                let idx = self.filenames.len();
+
+                self.filenames.push(filename);
+                ent.insert(idx);
+
+                idx
+            }
+        }
+    }
+
+    /// Add `filename` at the next index, even if it is already present.
+    ///
+    /// This keeps indices intact when decoding tables with
+    /// duplicate entries.
+    #[inline]
+    fn push(&mut self, filename: Filename<'a>) {
+        self.indices.entry(filename).or_insert(self.filenames.len());
+        self.filenames.push(filename);
+    }
+
+    /// Get the index of `filename`, if it is in the table.
+    #[inline]
+    pub fn index(&self, filename: Filename<'a>) -> Option<usize> {
+        self.indices.get(&filename).copied()
+    }
+
+    /// Get the [`Filename`] at `idx`.
+    #[inline]
+    pub fn get(&self, idx: usize) -> Option<Filename<'a>> {
+        self.filenames.get(idx).copied()
+    }
+
+    /// Get all filenames in the table, by index.
+    #[inline]
+    pub fn filenames(&self) -> &[Filename<'a>] {
+        &self.filenames
+    }
+
+    /// Write a reference to `filename` to `out`, adding it to the
+    /// table if necessary.
+    ///
+    /// The reference is written as its index, using [`write_index`].
+    #[inline]
+    pub fn write_ref<W>(&mut self, filename: Filename<'a>, out: &mut W) ->
+        Result<(), Error>
+    where W: Write {
+        write_index(out, self.insert(filename))
+    }
+
+    /// Read a reference written by
+    /// [`write_ref`](FilenameTable::write_ref).
+    #[inline]
+    pub fn read_ref<R>(&self, input: &mut R) -> Result<Filename<'a>, Error>
+    where R: Read {
+        let idx = read_index(input)?;
+
+        self.get(idx).ok_or_else(|| bad_index(idx))
+    }
+
+    /// Encode this table.
+    ///
+    /// Filenames are encoded using [`Filename::remapped_path`], so
+    /// path prefix rewrites are applied.  This fails if any of the
+    /// filenames are not valid Unicode.
+    pub fn encode(&self) -> Result<EncodedFilenames, Error> {
+        let entries = self.filenames.iter().map(|filename| {
+            match filename.remapped_path().to_str() {
+                Some(path) => Ok(EncodedFilename { kind: filename.kind(),
+                                                   path: String::from(path) }),
+                None => Err(Error::new(ErrorKind::InvalidData,
+                                       format!("non-Unicode filename {}",
+                                               filename.display())))
+            }
+        }).collect::<Result<Vec<EncodedFilename>, Error>>()?;
+
+        Ok(EncodedFilenames { entries })
+    }
+
+    /// Write this table to `out` in binary format.
+    ///
+    /// See [`EncodedFilenames::write`].
+    #[inline]
+    pub fn write<W>(&self, out: &mut W) -> Result<(), Error>
+    where W: Write {
+        self.encode()?.write(out)
+    }
+
+    /// Read a table in binary format from `input`, interning its
+    /// contents in `filenames`.
+    ///
+    /// See [`EncodedFilenames::read`].
+    #[inline]
+    pub fn read<R>(input: &mut R, filenames: &mut Filenames<'a>) ->
+        Result<Self, Error>
+    where R: Read {
+        Ok(EncodedFilenames::read(input)?.decode(filenames))
+    }
+}
+
+impl Default for FilenameTable<'_> {
+    #[inline]
+    fn default() -> Self {
+        FilenameTable::new()
+    }
+}
+
+impl EncodedSymbols {
+    /// Intern the contents of this `EncodedSymbols` in `symbols`,
+    /// producing a [`SymbolTable`] with the same indices.
+    pub fn decode<'a>(&self, symbols: &mut Symbols<'a>) -> SymbolTable<'a> {
+        let mut table = SymbolTable::with_capacity(self.entries.len());
+
+        for entry in &self.entries {
+            let sym = if entry.generated {
+                symbols.gensym(&entry.text)
+            } else {
+                symbols.symbol_str(&entry.text)
+            };
+
+            table.push(sym);
+        }
+
+        table
+    }
+
+    /// Write this `EncodedSymbols` to `out` in binary format.
+    ///
+    /// The format consists of the magic number `CTST`, a version
+    /// byte, and the number of entries, followed by the entries.
+    /// Each entry consists of a flag byte (`1` for generated
+    /// symbols, `0` otherwise) and a length-prefixed UTF-8 string.
+    /// All numbers are encoded as with [`write_index`].
+    pub fn write<W>(&self, out: &mut W) -> Result<(), Error>
+    where W: Write {
+        write_header(out, SYMBOL_MAGIC, self.entries.len())?;
+
+        for entry in &self.entries {
+            out.write_all(&[if entry.generated { GENERATED } else { 0 }])?;
+            write_str(out, &entry.text)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read an `EncodedSymbols` in binary format from `input`.
+    pub fn read<R>(input: &mut R) -> Result<Self, Error>
+    where R: Read {
+        let len = read_header(input, SYMBOL_MAGIC)?;
+        let mut entries = Vec::new();
+
+        for _ in 0 .. len {
+            let mut flags = [0];
+
+            input.read_exact(&mut flags)?;
+
+            let text = read_str(input)?;
+
+            entries.push(EncodedSymbol { generated: flags[0] & GENERATED != 0,
+                                         text });
+        }
+
+        Ok(EncodedSymbols { entries })
+    }
+}
+
+impl EncodedFilenames {
+    /// Intern the contents of this `EncodedFilenames` in
+    /// `filenames`, producing a [`FilenameTable`] with the same
+    /// indices.
+    ///
+    /// Paths of files on disk have the path prefix rewrites of
+    /// `filenames` undone (see [`Filenames::unmap`]) before they are
+    /// interned.  They are interned as canonical paths (see
+    /// [`Filenames::canonical_filename`]), without accessing the
+    /// filesystem, so files that have since been moved or deleted
+    /// still decode.
+    pub fn decode<'a>(&self, filenames: &mut Filenames<'a>) ->
+        FilenameTable<'a> {
+        let mut table = FilenameTable::with_capacity(self.entries.len());
+
+        for entry in &self.entries {
+            let filename = match entry.kind {
+                FileKind::Disk => {
+                    let path = filenames.unmap(Path::new(&entry.path))
+                        .into_owned();
+
+                    filenames.canonical_filename(&path)
+                },
+                kind => filenames.virtual_filename(kind, &entry.path)
+            };
+
+            table.push(filename);
+        }
+
+        table
+    }
+
+    /// Write this `EncodedFilenames` to `out` in binary format.
+    ///
+    /// The format consists of the magic number `CTFT`, a version
+    /// byte, and the number of entries, followed by the entries.
+    /// Each entry consists of a byte giving the [`FileKind`] and the
+    /// path as a length-prefixed UTF-8 string.  All numbers are
+    /// encoded as with [`write_index`].
+    pub fn write<W>(&self, out: &mut W) -> Result<(), Error>
+    where W: Write {
+        write_header(out, FILENAME_MAGIC, self.entries.len())?;
+
+        for entry in &self.entries {
+            out.write_all(&[kind_code(entry.kind)])?;
+            write_str(out, &entry.path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read an `EncodedFilenames` in binary format from `input`.
+    pub fn read<R>(input: &mut R) -> Result<Self, Error>
+    where R: Read {
+        let len = read_header(input, FILENAME_MAGIC)?;
+        let mut entries = Vec::new();
+
+        for _ in 0 .. len {
+            let mut kind = [0];
+
+            input.read_exact(&mut kind)?;
+
+            let kind = code_kind(kind[0])?;
+            let path = read_str(input)?;
+
+            entries.push(EncodedFilename { kind, path });
+        }
+
+        Ok(EncodedFilenames { entries })
+    }
+}
@@ -0,0 +1,237 @@
+use crate::messages::Highlighting;
+use crate::messages::Message;
+use crate::messages::MessagePositions;
+use crate::messages::Severity;
+use crate::symbol::Symbol;
+use std::cmp::max;
+use std::cmp::min;
+use std::iter::IntoIterator;
+
+/// A candidate suggestion for a misspelled [`Symbol`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Suggestion<'a> {
+    /// The suggested symbol.
+    pub symbol: Symbol<'a>,
+    /// Edit distance from the original, ignoring case.
+    pub distance: usize,
+    /// Edit distance from the original, including case.
+    pub case_distance: usize
+}
+
+/// A [`Message`] extended with a "did you mean" help note.
+///
+/// This wraps another message, and appends the help note to its
+/// [`detail`](Message::detail).  All other parts of the message are
+/// passed through unchanged.
+pub struct WithSuggestion<M> {
+    /// The original message.
+    msg: M,
+    /// The detail of the original message, with the help note.
+    detail: String,
+    /// The help note, if there is one.
+    help: Option<String>
+}
+
+/// Compute the edit distance between `a` and `b`.
+///
+/// This is the optimal string alignment distance: the number of
+/// single-character insertions, deletions, substitutions, and
+/// transpositions of adjacent characters needed to transform `a`
+/// into `b`.  Characters are compared exactly.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    osa_distance(&a, &b)
+}
+
+/// Compute the edit distance between `a` and `b`, ignoring case.
+///
+/// See [`edit_distance`].
+pub fn edit_distance_ignore_case(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().flat_map(char::to_lowercase).collect();
+    let b: Vec<char> = b.chars().flat_map(char::to_lowercase).collect();
+
+    osa_distance(&a, &b)
+}
+
+/// Internal function to compute the optimal string alignment distance.
+fn osa_distance(a: &[char], b: &[char]) -> usize {
+    let width = b.len() + 1;
+    // Three rows of the table: two rows back, one row back, current.
+    let mut prev2: Vec<usize> = vec![0; width];
+    let mut prev: Vec<usize> = (0 .. width).collect();
+    let mut curr: Vec<usize> = vec![0; width];
+
+    for i in 1 ..= a.len() {
+        curr[0] = i;
+
+        for j in 1 ..= b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut best = min(min(prev[j] + 1, curr[j - 1] + 1),
+                               prev[j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = min(best, prev2[j - 2] + 1);
+            }
+
+            curr[j] = best;
+        }
+
+        std::mem::swap(&mut prev2, &mut prev);
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+/// Get all near matches for `target` from `candidates`, ranked from
+/// best to worst.
+///
+/// Candidates are considered near matches if their case-insensitive
+/// edit distance from `target` is at most a third of the length of
+/// `target` (but always allowing at least one edit), or if they
+/// differ from `target` only by case.  Ties are broken by the
+/// case-sensitive edit distance, and then by the text of the
+/// candidate, so the result is deterministic.  `target` itself and
+/// the empty symbol are never suggested.
+///
+/// `candidates` can be any collection of symbols, such as a scope or
+/// an entire [`Symbols`](crate::symbol::Symbols).
+pub fn suggestions<'a, I>(target: Symbol<'_>, candidates: I) ->
+    Vec<Suggestion<'a>>
+where I: IntoIterator<Item = Symbol<'a>> {
+    let target_len = target.chars().count();
+    let limit = max(target_len / 3, 1);
+    let mut out: Vec<Suggestion<'a>> = candidates.into_iter()
+        .filter(|sym| !sym.is_empty() && sym.id() != target.id())
+        .filter_map(|sym| {
+            let len = sym.chars().count();
+
+            if max(len, target_len) - min(len, target_len) > limit {
+                return None;
+            }
+
+            let distance = edit_distance_ignore_case(&target[..], &sym[..]);
+
+            if distance <= limit {
+                let case_distance = edit_distance(&target[..], &sym[..]);
+
+                Some(Suggestion { symbol: sym, distance,
+                                  case_distance })
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    out.sort_by(|a, b| {
+        a.distance.cmp(&b.distance)
+            .then(a.case_distance.cmp(&b.case_distance))
+            .then_with(|| a.symbol[..].cmp(&b.symbol[..]))
+    });
+    out.dedup_by_key(|suggestion| suggestion.symbol);
+
+    out
+}
+
+/// Get the best near match for `target` from `candidates`, if any.
+///
+/// See [`suggestions`].
+#[inline]
+pub fn best_suggestion<'a, I>(target: Symbol<'_>, candidates: I) ->
+    Option<Symbol<'a>>
+where I: IntoIterator<Item = Symbol<'a>> {
+    suggestions(target, candidates).first().map(|suggestion| suggestion.symbol)
+}
+
+impl<M> WithSuggestion<M>
+where M: Message {
+    /// Add a help note suggesting `suggestion` to `msg`.
+    ///
+    /// If `suggestion` is `None`, the message is left unchanged.
+    pub fn new(msg: M, suggestion: Option<Symbol<'_>>) -> Self {
+        let help = suggestion
+            .map(|sym| format!("help: did you mean `{}`?", sym));
+        let detail = match &help {
+            Some(help) if msg.detail().is_empty() => help.clone(),
+            Some(help) => format!("{}\n{}", msg.detail(), help),
+            None => String::from(msg.detail())
+        };
+
+        WithSuggestion { msg, detail, help }
+    }
+
+    /// Add a help note with the best suggestion for `target` from
+    /// `candidates` to `msg`, if there is one.
+    ///
+    /// See [`best_suggestion`].
+    #[inline]
+    pub fn suggest<'a, I>(msg: M, target: Symbol<'_>, candidates: I) -> Self
+    where I: IntoIterator<Item = Symbol<'a>> {
+        WithSuggestion::new(msg, best_suggestion(target, candidates))
+    }
+
+    /// Get the original message.
+    #[inline]
+    pub fn inner(&self) -> &M {
+        &self.msg
+    }
+
+    /// Unwrap the original message.
+    #[inline]
+    pub fn into_inner(self) -> M {
+        self.msg
+    }
+}
+
+impl<M> Message for WithSuggestion<M>
+where M: Message {
+    #[inline]
+    fn severity(&self) -> Severity {
+        self.msg.severity()
+    }
+
+    #[inline]
+    fn brief(&self) -> &str {
+        self.msg.brief()
+    }
+
+    #[inline]
+    fn detail(&self) -> &str {
+        &self.detail
+    }
+
+    #[inline]
+    fn highlighting(&self) -> Highlighting {
+        self.msg.highlighting()
+    }
+
+    #[inline]
+    fn code(&self) -> Option<&str> {
+        self.msg.code()
+    }
+
+    #[inline]
+    fn key(&self) -> Option<&str> {
+        self.msg.key()
+    }
+
+    #[inline]
+    fn arg(&self, name: &str) -> Option<String> {
+        self.msg.arg(name)
+    }
+
+    #[inline]
+    fn help(&self) -> Option<&str> {
+        self.help.as_deref()
+    }
+}
+
+impl<M, P> MessagePositions<P> for WithSuggestion<M>
+where M: MessagePositions<P> {
+    #[inline]
+    fn positions(&self) -> &[(Option<&str>, P, Severity)] {
+        self.msg.positions()
+    }
+}
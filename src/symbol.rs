@@ -1,7 +1,9 @@
 use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::collections::hash_map::Entry;
+use std::collections::hash_map::Keys;
 use std::convert::AsRef;
 use std::ffi::OsStr;
 use std::ffi::OsString;
@@ -33,28 +35,44 @@ use std::str::FromStr;
 /// compare (these use the address of the interned string).  This is a
 /// common technique employed in compiler implementation, as string
 /// hashing and comparison is so common.
-#[derive(Copy, Eq, Ord)]
+#[derive(Copy, Eq)]
 pub struct Symbol<'a>(&'a str);
 
+/// Interned symbol table, for producing [`Symbol`]s.
 pub struct Symbols<'a> {
     lifetime: PhantomData<&'a str>,
     // Interned [Strings]
-    interned: HashMap<String, ()>
+    interned: HashMap<String, ()>,
+    // Generated names, which are never interned
+    generated: Vec<Box<str>>,
+    // Ids of generated [Symbol]s
+    generated_ids: HashSet<usize>
+}
+
+/// Iterator over the [`Symbol`]s interned in a [`Symbols`].
+pub struct SymbolsIter<'b, 'a> {
+    lifetime: PhantomData<&'a str>,
+    inner: Keys<'b, String, ()>
 }
 
 /// Designated [Symbol] for the empty string.
 ///
 /// This is used as the [Default] instance.
-pub const NULL_SYM: Symbol<'static> = Symbol(&"");
+pub const NULL_SYM: Symbol<'static> = Symbol("");
 
 impl<'a> Symbol<'a> {
     /// Get the id number for this `Symbol`.
     ///
     /// Id numbers are assigned arbitrarily, and not guaranteed to
-    /// form a contiguous or dense range.
+    /// form a contiguous or dense range.  The empty symbol always has
+    /// id 0, as copies of [`NULL_SYM`] need not share an address.
     #[inline]
     pub fn id(&self) -> usize {
-        self.0.as_ptr() as usize
+        if self.0.is_empty() {
+            0
+        } else {
+            self.0.as_ptr() as usize
+        }
     }
 
     /// See [`str::len`].
@@ -212,7 +230,7 @@ impl AsRef<Path> for Symbol<'_> {
 impl<'a> Clone for Symbol<'a> {
     #[inline]
     fn clone(&self) -> Symbol<'a> {
-        Symbol(self.0)
+        *self
     }
 }
 
@@ -388,10 +406,25 @@ impl PartialOrd<Symbol<'_>> for str {
     }
 }
 
+impl Ord for Symbol<'_> {
+    /// Order by text, then by id.
+    ///
+    /// Generated symbols can have the same text as interned ones, so
+    /// the id breaks ties to stay consistent with equality.
+    #[inline]
+    fn cmp(&self, other: &Symbol<'_>) -> Ordering {
+        self.0.cmp(other.0).then_with(|| self.id().cmp(&other.id()))
+    }
+}
+
 impl PartialOrd for Symbol<'_> {
+    /// Order as [`Ord`] does, by text and then by id.
+    ///
+    /// This used to order by id alone, which disagreed with `Ord`, so
+    /// `<` and sorting could put the same symbols in different orders.
     #[inline]
     fn partial_cmp(&self, other: &Symbol<'_>) -> Option<Ordering> {
-        Some(self.id().cmp(&other.id()))
+        Some(self.cmp(other))
     }
 }
 
@@ -403,44 +436,138 @@ impl<'a> Symbols<'a> {
     /// Create a new `Symbols`.
     #[inline]
     pub fn new() -> Symbols<'a> {
-        Symbols { lifetime: PhantomData, interned: HashMap::new() }
+        Symbols { lifetime: PhantomData, interned: HashMap::new(),
+                  generated: Vec::new(), generated_ids: HashSet::new() }
     }
 
     /// Create a new `Symbols` with a size hint.
     #[inline]
     pub fn with_capacity(size: usize) -> Symbols<'a> {
         Symbols { interned: HashMap::with_capacity(size),
+                  generated: Vec::new(), generated_ids: HashSet::new(),
                   lifetime: PhantomData }
     }
 
     /// Shring down this `Symbols` to fit the current contents.
     #[inline]
     pub fn shrink_to_fit(&mut self) {
-        self.interned.shrink_to_fit()
+        self.interned.shrink_to_fit();
+        self.generated.shrink_to_fit();
+        self.generated_ids.shrink_to_fit()
+    }
+
+    /// Get the number of interned symbols.
+    ///
+    /// This does not count [`NULL_SYM`], which is never interned.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.interned.len()
+    }
+
+    /// Check whether any symbols have been interned.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.interned.is_empty()
+    }
+
+    /// Get an iterator over all interned [`Symbol`]s.
+    ///
+    /// The order of iteration is arbitrary.  As with [`len`](Symbols::len),
+    /// [`NULL_SYM`] is not included.
+    #[inline]
+    pub fn iter(&self) -> SymbolsIter<'_, 'a> {
+        SymbolsIter { inner: self.interned.keys(), lifetime: PhantomData }
+    }
+
+    /// Get the [`Symbol`] for `s` if it has already been interned.
+    ///
+    /// Unlike [`symbol`](Symbols::symbol), this never adds to the
+    /// table.  The empty string always yields [`NULL_SYM`].
+    #[inline]
+    pub fn get(&self, s: &str) -> Option<Symbol<'a>> {
+        if !s.is_empty() {
+            self.interned.get_key_value(s).map(|(key, _)| Self::make(key))
+        } else {
+            Some(NULL_SYM)
+        }
+    }
+
+    /// Generate a fresh `Symbol` from `base`.
+    ///
+    /// The result displays as `base` followed by `%` and a number
+    /// unique to this `Symbols` (for example, `tmp%3`), but is
+    /// distinct from every other `Symbol`, including any interned
+    /// symbol with the same text.  This makes it suitable for
+    /// desugaring and hygiene, where names must never collide with
+    /// user identifiers.
+    ///
+    /// Generated symbols are not interned, so they are not returned
+    /// by [`get`](Symbols::get) or [`iter`](Symbols::iter), and are
+    /// not counted by [`len`](Symbols::len).
+    pub fn gensym(&mut self, base: &str) -> Symbol<'a> {
+        let name = format!("{}%{}", base, self.generated.len())
+            .into_boxed_str();
+        let sym = Self::make(&name);
+
+        self.generated.push(name);
+        self.generated_ids.insert(sym.id());
+
+        sym
+    }
+
+    /// Check whether `sym` was created by [`gensym`](Symbols::gensym).
+    #[inline]
+    pub fn is_generated(&self, sym: Symbol<'_>) -> bool {
+        self.generated_ids.contains(&sym.id())
+    }
+
+    /// Get the number of symbols created by
+    /// [`gensym`](Symbols::gensym).
+    #[inline]
+    pub fn generated_len(&self) -> usize {
+        self.generated.len()
+    }
+
+    /// Check whether `s` has already been interned.
+    #[inline]
+    pub fn contains(&self, s: &str) -> bool {
+        s.is_empty() || self.interned.contains_key(s)
+    }
+
+    /// Internal function to make a symbol from an interned string.
+    #[inline]
+    fn make(key: &str) -> Symbol<'a> {
+        unsafe {
+            let ptr = key as *const str;
+
+            Symbol(&*ptr)
+        }
     }
 
     /// Internal function to create a symbol.
     fn create_symbol_nonnull(&mut self, str: String) -> Symbol<'a> {
         match self.interned.entry(str) {
-            Entry::Occupied(ent) => {
-                unsafe {
-                    let ptr = ent.key().as_str() as *const str;
-
-                    Symbol(&*ptr)
-                }
-            },
+            Entry::Occupied(ent) => Self::make(ent.key()),
             Entry::Vacant(ent) => {
-                unsafe {
-                    let ptr = ent.key().as_str() as *const str;
+                let sym = Self::make(ent.key());
 
-                    ent.insert(());
+                ent.insert(());
 
-                    Symbol(&*ptr)
-                }
+                sym
             }
         }
     }
 
+    /// Internal function to create a symbol from a borrowed string,
+    /// only allocating if it has not already been interned.
+    #[inline]
+    fn create_symbol_nonnull_str(&mut self, s: &str) -> Symbol<'a> {
+        match self.interned.get_key_value(s) {
+            Some((key, _)) => Self::make(key),
+            None => self.create_symbol_nonnull(s.to_string())
+        }
+    }
+
     /// Create a `Symbol` from a non-empty string.
     ///
     /// The argument `s` must not be equal to `""`.
@@ -449,7 +576,7 @@ impl<'a> Symbols<'a> {
     where S: ToString {
         let str = s.to_string();
 
-        assert!(str != "");
+        assert!(!str.is_empty());
 
         self.create_symbol_nonnull(str)
     }
@@ -466,4 +593,62 @@ impl<'a> Symbols<'a> {
             NULL_SYM
         }
     }
+
+    /// Create a `Symbol` from a non-empty `&str`.
+    ///
+    /// This behaves like [`symbol_nonnull`](Symbols::symbol_nonnull),
+    /// but only allocates if `s` has not already been interned.  The
+    /// argument `s` must not be equal to `""`.
+    #[inline]
+    pub fn symbol_str_nonnull(&mut self, s: &str) -> Symbol<'a> {
+        assert!(!s.is_empty());
+
+        self.create_symbol_nonnull_str(s)
+    }
+
+    /// Create a `Symbol` from a `&str`.
+    ///
+    /// This behaves like [`symbol`](Symbols::symbol), but only
+    /// allocates if `s` has not already been interned.
+    #[inline]
+    pub fn symbol_str(&mut self, s: &str) -> Symbol<'a> {
+        if !s.is_empty() {
+            self.create_symbol_nonnull_str(s)
+        } else {
+            NULL_SYM
+        }
+    }
 }
+
+impl Default for Symbols<'_> {
+    #[inline]
+    fn default() -> Self {
+        Symbols::new()
+    }
+}
+
+impl<'b, 'a> IntoIterator for &'b Symbols<'a> {
+    type Item = Symbol<'a>;
+    type IntoIter = SymbolsIter<'b, 'a>;
+
+    #[inline]
+    fn into_iter(self) -> SymbolsIter<'b, 'a> {
+        self.iter()
+    }
+}
+
+impl<'a> Iterator for SymbolsIter<'_, 'a> {
+    type Item = Symbol<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Symbol<'a>> {
+        self.inner.next().map(|key| Symbols::make(key))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl ExactSizeIterator for SymbolsIter<'_, '_> {}
@@ -0,0 +1,413 @@
+use crate::messages::MessagePositions;
+use crate::messages::Severity;
+use crate::position::FilePosition;
+use crate::position::OffsetPosition;
+use std::convert::TryFrom;
+use std::convert::TryInto;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fs;
+use std::io::Error;
+use std::path::Path;
+
+/// Runner for UI tests, which check messages against annotations in
+/// source fixtures.
+///
+/// Annotations are comments starting with the comment marker
+/// followed by `~` (by default, `//~`), then a severity (`INFO`,
+/// `REMARK`, `LINT`, `WARNING`, `ERROR`, or `INTERNAL`), then text
+/// that the message's brief description must contain.  Annotations
+/// refer to their own line, or can be adjusted as follows:
+///
+/// - `//~^ ERROR` refers to the line above, `//~^^ ERROR` to the line
+///   two above, and so on.
+/// - `//~| ERROR` refers to the same line as the previous annotation.
+/// - `//~? ERROR` refers to a message without a position in a file.
+///
+/// The marker is found anywhere in a line, including inside string
+/// literals, so fixtures should only contain it in annotations.
+///
+/// A message's line is the line of the fixture where its first
+/// position starts, counting from 1.  Every annotation
+/// must match a distinct message, and every message at or above the
+/// [minimum severity](UiTest::min_severity) must match an annotation.
+/// Annotations are paired with messages so that as many as possible
+/// match, so a loose annotation never takes the only message a more
+/// specific one could match.
+pub struct UiTest {
+    /// Marker starting line comments.
+    comment: String,
+    /// Messages below this severity are not checked.
+    min_severity: Severity
+}
+
+/// An expected message, given by an annotation.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Annotation {
+    /// The line of the annotation itself, starting at 1.
+    pub source_line: usize,
+    /// The line the message should be on, if it should have a
+    /// position.
+    pub line: Option<usize>,
+    /// The severity of the message.
+    pub severity: Severity,
+    /// Text that the brief description must contain.
+    pub text: String
+}
+
+/// A message emitted by the compiler under test.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Emitted {
+    /// The line of the message's first position, if it is in a file.
+    pub line: Option<usize>,
+    /// The severity of the message.
+    pub severity: Severity,
+    /// The brief description of the message.
+    pub brief: String
+}
+
+/// Collector for messages emitted during a UI test.
+#[derive(Clone, Debug, Default)]
+pub struct UiCollector {
+    /// The messages emitted so far.
+    emitted: Vec<Emitted>
+}
+
+/// Results of a UI test.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct UiReport {
+    /// Messages that did not match any annotation.
+    pub unexpected: Vec<Emitted>,
+    /// Annotations that did not match any message.
+    pub missing: Vec<Annotation>
+}
+
+/// Errors that can occur when running a UI test.
+#[derive(Debug)]
+pub enum UiError {
+    /// The fixture could not be read.
+    Io(Error),
+    /// An annotation could not be parsed.
+    Annotation {
+        /// The line of the annotation, starting at 1.
+        line: usize
+    }
+}
+
+impl UiTest {
+    /// Create a new `UiTest` with `//` comments that checks all
+    /// messages.
+    #[inline]
+    pub fn new() -> Self {
+        UiTest { comment: String::from("//"), min_severity: Severity::Info }
+    }
+
+    /// Get the marker starting line comments.
+    #[inline]
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    /// Set the marker starting line comments, such as `#` or `--`.
+    #[inline]
+    pub fn set_comment(&mut self, comment: &str) {
+        self.comment = String::from(comment)
+    }
+
+    /// Get the lowest severity of messages that must be annotated.
+    #[inline]
+    pub fn min_severity(&self) -> Severity {
+        self.min_severity
+    }
+
+    /// Set the lowest severity of messages that must be annotated.
+    ///
+    /// Annotations are still checked for less severe messages.
+    #[inline]
+    pub fn set_min_severity(&mut self, min_severity: Severity) {
+        self.min_severity = min_severity
+    }
+
+    /// Parse the annotations in `text`.
+    ///
+    /// The marker is not recognized as the start of a comment, so one
+    /// inside a string literal also starts an annotation.
+    pub fn parse(&self, text: &str) -> Result<Vec<Annotation>, UiError> {
+        let marker = format!("{}~", self.comment);
+        let mut out: Vec<Annotation> = Vec::new();
+
+        for (idx, line) in text.lines().enumerate() {
+            let lineno = idx + 1;
+            let rest = match line.find(&marker) {
+                Some(pos) => &line[pos + marker.len() ..],
+                None => continue
+            };
+            let err = UiError::Annotation { line: lineno };
+            let (target, rest) = match rest.chars().next() {
+                Some('^') => {
+                    let count = rest.chars().take_while(|c| *c == '^')
+                        .count();
+
+                    if count >= lineno {
+                        return Err(err);
+                    }
+
+                    (Some(lineno - count), &rest[count ..])
+                },
+                Some('|') => match out.last() {
+                    Some(prev) => (prev.line, &rest[1 ..]),
+                    None => return Err(err)
+                },
+                Some('?') => (None, &rest[1 ..]),
+                _ => (Some(lineno), rest)
+            };
+            let rest = rest.trim_start();
+            let word_end = rest.find(char::is_whitespace)
+                .unwrap_or(rest.len());
+            let severity = match &rest[.. word_end] {
+                "INFO" => Severity::Info,
+                "REMARK" => Severity::Remark,
+                "LINT" => Severity::Lint,
+                "WARNING" => Severity::Warning,
+                "ERROR" => Severity::Error,
+                "INTERNAL" => Severity::Internal,
+                _ => return Err(err)
+            };
+
+            out.push(Annotation { source_line: lineno, line: target,
+                                  severity,
+                                  text: String::from(rest[word_end ..]
+                                                     .trim()) });
+        }
+
+        Ok(out)
+    }
+
+    /// Check `emitted` against `annotations`.
+    pub fn check(&self, annotations: &[Annotation], emitted: &[Emitted]) ->
+        UiReport {
+        // The annotation matching each message, if any.
+        let mut owners = vec![None; emitted.len()];
+        let mut matched = vec![false; annotations.len()];
+
+        for idx in 0 .. annotations.len() {
+            let mut seen = vec![false; emitted.len()];
+
+            augment(annotations, emitted, idx, &mut owners, &mut seen);
+        }
+
+        for idx in owners.iter().flatten() {
+            matched[*idx] = true
+        }
+
+        let missing = annotations.iter().zip(matched)
+            .filter(|(_, matched)| !matched)
+            .map(|(annotation, _)| annotation.clone())
+            .collect();
+        let unexpected = emitted.iter().zip(owners)
+            .filter(|(msg, owner)| {
+                owner.is_none() && msg.severity >= self.min_severity
+            })
+            .map(|(msg, _)| msg.clone())
+            .collect();
+
+        UiReport { unexpected, missing }
+    }
+
+    /// Run a UI test on the fixture `text`.
+    ///
+    /// `compile` is called with the fixture, and should compile it and
+    /// emit the resulting messages to the collector.
+    pub fn run_str<F>(&self, text: &str, compile: F) ->
+        Result<UiReport, UiError>
+    where F: FnOnce(&str, &mut UiCollector) {
+        let annotations = self.parse(text)?;
+        let mut collector = UiCollector::new();
+
+        compile(text, &mut collector);
+
+        Ok(self.check(&annotations, collector.emitted()))
+    }
+
+    /// Run a UI test on the fixture at `path`.
+    ///
+    /// `compile` is called with the path and contents of the fixture.
+    /// See [`run_str`](UiTest::run_str).
+    pub fn run<P, F>(&self, path: P, compile: F) -> Result<UiReport, UiError>
+    where P: AsRef<Path>,
+          F: FnOnce(&Path, &str, &mut UiCollector) {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+
+        self.run_str(&text, |text, collector| compile(path, text, collector))
+    }
+
+    /// Run a UI test on the fixture at `path` as with
+    /// [`run`](UiTest::run), panicking with a description of any
+    /// unexpected or missing messages.
+    pub fn assert_run<P, F>(&self, path: P, compile: F)
+    where P: AsRef<Path>,
+          F: FnOnce(&Path, &str, &mut UiCollector) {
+        let path = path.as_ref();
+
+        match self.run(path, compile) {
+            Ok(report) if report.is_ok() => (),
+            Ok(report) => panic!("UI test {} failed:\n{}", path.display(),
+                                 report),
+            Err(err) => panic!("UI test {} failed: {}", path.display(), err)
+        }
+    }
+}
+
+impl Default for UiTest {
+    #[inline]
+    fn default() -> Self {
+        UiTest::new()
+    }
+}
+
+impl Annotation {
+    /// Check whether `msg` is a message this annotation expects.
+    #[inline]
+    pub fn matches(&self, msg: &Emitted) -> bool {
+        msg.line == self.line && msg.severity == self.severity &&
+        msg.brief.contains(self.text.as_str())
+    }
+}
+
+impl UiCollector {
+    /// Create a new, empty `UiCollector`.
+    #[inline]
+    pub fn new() -> Self {
+        UiCollector { emitted: Vec::new() }
+    }
+
+    /// Get the messages emitted so far.
+    #[inline]
+    pub fn emitted(&self) -> &[Emitted] {
+        &self.emitted
+    }
+
+    /// Record the message `msg`.
+    pub fn emit<'a, M, P>(&mut self, msg: &'a M)
+    where &'a FilePosition<'a>: TryFrom<&'a P>,
+          M: MessagePositions<P>,
+          P: 'a {
+        let line = msg.positions().first().and_then(|(_, pos, _)| {
+            let filepos: Result<&'a FilePosition<'a>, _> = pos.try_into();
+
+            filepos.ok().and_then(fixture_line)
+        });
+
+        self.emitted.push(Emitted { line,
+                                    severity: msg.severity(),
+                                    brief: String::from(msg.brief()) })
+    }
+}
+
+/// Try to match the annotation at `annotation` with a message not in
+/// `seen`, moving other annotations in `owners` to different messages
+/// if needed.
+///
+/// This finds an augmenting path, as in a bipartite matching.
+fn augment(annotations: &[Annotation], emitted: &[Emitted],
+           annotation: usize, owners: &mut [Option<usize>],
+           seen: &mut [bool]) -> bool {
+    for (idx, msg) in emitted.iter().enumerate() {
+        if !seen[idx] && annotations[annotation].matches(msg) {
+            seen[idx] = true;
+
+            let free = match owners[idx] {
+                Some(other) => augment(annotations, emitted, other, owners,
+                                       seen),
+                None => true
+            };
+
+            if free {
+                owners[idx] = Some(annotation);
+
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+/// Get the line of the fixture where `pos` starts, counting from 1
+/// as annotations do.
+fn fixture_line(pos: &FilePosition<'_>) -> Option<usize> {
+    match pos {
+        FilePosition::Portion { file_offsets, offset } => {
+            let start = match offset {
+                OffsetPosition::Span { start, .. } => *start,
+                OffsetPosition::Point { point } => *point
+            };
+
+            Some(file_offsets.line_offsets().lookup(start).0)
+        },
+        FilePosition::File { .. } => None
+    }
+}
+
+impl UiReport {
+    /// Check whether all messages matched annotations, and all
+    /// annotations matched messages.
+    #[inline]
+    pub fn is_ok(&self) -> bool {
+        self.unexpected.is_empty() && self.missing.is_empty()
+    }
+}
+
+impl Display for UiReport {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        for msg in &self.unexpected {
+            match msg.line {
+                Some(line) => write!(f, "line {}: ", line)?,
+                None => write!(f, "no line: ")?
+            }
+
+            writeln!(f, "unexpected {}: {}", msg.severity.gnu_name(),
+                     msg.brief)?;
+        }
+
+        for annotation in &self.missing {
+            match annotation.line {
+                Some(line) => write!(f, "line {}: ", line)?,
+                None => write!(f, "no line: ")?
+            }
+
+            writeln!(f, "missing {}: {} (annotated on line {})",
+                     annotation.severity.gnu_name(), annotation.text,
+                     annotation.source_line)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for UiError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            UiError::Io(err) => Display::fmt(err, f),
+            UiError::Annotation { line } =>
+                write!(f, "malformed annotation at line {}", line)
+        }
+    }
+}
+
+impl std::error::Error for UiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UiError::Io(err) => Some(err),
+            UiError::Annotation { .. } => None
+        }
+    }
+}
+
+impl From<Error> for UiError {
+    #[inline]
+    fn from(err: Error) -> Self {
+        UiError::Io(err)
+    }
+}
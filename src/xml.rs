@@ -0,0 +1,208 @@
+use crate::catalog::localized_brief;
+use crate::catalog::localized_detail;
+use crate::messages::MessageGnuWriter;
+use crate::messages::MessagePositions;
+use crate::messages::MessageWriter;
+use crate::messages::Severity;
+use crate::messages::group_by_file;
+use crate::position::FilePosition;
+use std::convert::TryFrom;
+use std::convert::TryInto;
+use std::fmt::Display;
+use std::io::Error;
+use std::io::Write;
+use termcolor::NoColor;
+
+/// Writer for Checkstyle XML reports of a batch of messages.
+///
+/// Each message becomes an `<error>` element, at its first position,
+/// within the `<file>` element of that position's file.  Messages
+/// that are not in a file are reported under a `<file>` with an empty
+/// name.
+pub struct CheckstyleWriter;
+
+/// Writer for JUnit XML reports of a batch of messages.
+///
+/// Each file is a test case, which fails if it has any messages with
+/// a [`Severity`] of [`Error`](Severity::Error) or higher; each such
+/// message is a `<failure>`.  Less severe messages are included in
+/// the test case's `<system-out>`.  Messages that are not in a file
+/// are reported under a test case with an empty name.
+pub struct JUnitWriter {
+    /// Name of the test suite.
+    name: String
+}
+
+/// Escape `text` for use in XML text or attribute values.
+///
+/// Characters that are not allowed in XML documents are replaced
+/// with U+FFFD.
+pub fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\n' => out.push_str("&#10;"),
+            '\r' => out.push_str("&#13;"),
+            '\t' => out.push_str("&#9;"),
+            c if c < ' ' || c == '\u{fffe}' || c == '\u{ffff}' =>
+                out.push('\u{fffd}'),
+            _ => out.push(c)
+        }
+    }
+
+    out
+}
+
+/// Get the Checkstyle name for `severity`.
+fn checkstyle_severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info | Severity::Remark => "info",
+        Severity::Lint | Severity::Warning => "warning",
+        Severity::Error | Severity::Internal => "error"
+    }
+}
+
+/// Render `msg` with a [`MessageGnuWriter`].
+fn gnu_text<'a, M, P>(msg: &'a M) -> Result<String, Error>
+where &'a FilePosition<'a>: TryFrom<&'a P>,
+      M: MessagePositions<P>,
+      P: 'a + Display {
+    let mut out = NoColor::new(Vec::new());
+
+    MessageGnuWriter.write_msg(msg, &mut out)?;
+
+    Ok(String::from_utf8_lossy(&out.into_inner()).into_owned())
+}
+
+impl CheckstyleWriter {
+    /// Write a report of all of `msgs` out to `out`.
+    pub fn write_report<'a, I, M, P, W>(&self, msgs: I, out: &mut W) ->
+        Result<(), Error>
+    where I: IntoIterator<Item = &'a M>,
+          &'a FilePosition<'a>: TryFrom<&'a P>,
+          M: 'a + MessagePositions<P>,
+          W: Write,
+          P: 'a + Display {
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(out, "<checkstyle version=\"4.3\">")?;
+
+        for (file, group) in group_by_file(msgs) {
+            let name = file.map(|filename| filename.display_name())
+                .unwrap_or_default();
+
+            writeln!(out, "<file name=\"{}\">", escape_xml(&name))?;
+
+            for msg in group {
+                let filepos: Option<&'a FilePosition<'a>> = msg.positions()
+                    .first()
+                    .and_then(|(_, pos, _)| pos.try_into().ok());
+
+                write!(out, "<error")?;
+
+                if let Some((line, col)) =
+                    filepos.and_then(FilePosition::start_line_col) {
+                    write!(out, " line=\"{}\" column=\"{}\"", line, col)?;
+                }
+
+                write!(out, " severity=\"{}\" message=\"{}\"",
+                       checkstyle_severity(msg.severity()),
+                       escape_xml(&localized_brief(msg)))?;
+
+                if let Some(code) = msg.code() {
+                    write!(out, " source=\"{}\"", escape_xml(code))?;
+                }
+
+                writeln!(out, "/>")?;
+            }
+
+            writeln!(out, "</file>")?;
+        }
+
+        writeln!(out, "</checkstyle>")
+    }
+}
+
+impl JUnitWriter {
+    /// Create a new `JUnitWriter` with test suite name `name`.
+    #[inline]
+    pub fn new(name: &str) -> Self {
+        JUnitWriter { name: String::from(name) }
+    }
+
+    /// Get the name of the test suite.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Write a report of all of `msgs` out to `out`.
+    pub fn write_report<'a, I, M, P, W>(&self, msgs: I, out: &mut W) ->
+        Result<(), Error>
+    where I: IntoIterator<Item = &'a M>,
+          &'a FilePosition<'a>: TryFrom<&'a P>,
+          M: 'a + MessagePositions<P>,
+          W: Write,
+          P: 'a + Display {
+        let groups = group_by_file(msgs);
+        let tests = groups.len();
+        let failures = groups.iter()
+            .filter(|(_, group)| {
+                group.iter().any(|msg| msg.severity() >= Severity::Error)
+            })
+            .count();
+        let name = escape_xml(&self.name);
+
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(out, "<testsuites name=\"{}\" tests=\"{}\" \
+                       failures=\"{}\">",
+                 name, tests, failures)?;
+        writeln!(out, "<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">",
+                 name, tests, failures)?;
+
+        for (file, group) in groups {
+            let file = file.map(|filename| filename.display_name())
+                .unwrap_or_default();
+            let mut output = String::new();
+
+            writeln!(out, "<testcase name=\"{}\" classname=\"{}\">",
+                     escape_xml(&file), name)?;
+
+            for msg in group {
+                let text = gnu_text(msg)?;
+
+                if msg.severity() >= Severity::Error {
+                    let detail = localized_detail(msg);
+                    let body = if detail.is_empty() {
+                        text
+                    } else {
+                        format!("{}{}\n", text, detail)
+                    };
+
+                    writeln!(out, "<failure message=\"{}\" type=\"{}\">\
+                                   {}</failure>",
+                             escape_xml(&localized_brief(msg)),
+                             msg.severity().gnu_name(),
+                             escape_xml(&body))?;
+                } else {
+                    output.push_str(&text);
+                }
+            }
+
+            if !output.is_empty() {
+                writeln!(out, "<system-out>{}</system-out>",
+                         escape_xml(&output))?;
+            }
+
+            writeln!(out, "</testcase>")?;
+        }
+
+        writeln!(out, "</testsuite>")?;
+        writeln!(out, "</testsuites>")
+    }
+}
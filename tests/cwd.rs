@@ -0,0 +1,41 @@
+//! Tests that change the working directory.
+//!
+//! These run in their own process, as the working directory is shared
+//! by all the threads running the unit tests.
+
+use compiler_tools::files::Filenames;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+#[test]
+fn test_filename_cwd_change() {
+    let dir = env::temp_dir().join(format!("compiler-tools-cwd-{}",
+                                           process::id()));
+    let first = dir.join("first");
+    let second = dir.join("second");
+
+    fs::create_dir_all(&first).expect("Expected success");
+    fs::create_dir_all(&second).expect("Expected success");
+    fs::write(first.join("a.x"), "first").expect("Expected success");
+    fs::write(second.join("a.x"), "second").expect("Expected success");
+
+    let old = env::current_dir().expect("Expected success");
+    let mut filenames = Filenames::new();
+
+    env::set_current_dir(&first).expect("Expected success");
+
+    let a = filenames.filename(Path::new("a.x")).expect("Expected success");
+
+    env::set_current_dir(&second).expect("Expected success");
+
+    let b = filenames.filename(Path::new("a.x")).expect("Expected success");
+
+    env::set_current_dir(&old).expect("Expected success");
+    fs::remove_dir_all(&dir).expect("Expected success");
+
+    assert_ne!(a, b);
+    assert_eq!(&a, first.join("a.x").as_path());
+    assert_eq!(&b, second.join("a.x").as_path())
+}
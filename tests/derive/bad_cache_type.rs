@@ -0,0 +1,8 @@
+#[derive(compiler_tools_derive::Message)]
+#[message(severity = "error", brief = "bad cache")]
+struct BadCache {
+    #[cache]
+    cache: usize
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use compiler_tools::diagnostic::DiagnosticCache;
+
+#[derive(compiler_tools_derive::Message)]
+#[message(severity = "error", brief = "bad label")]
+struct BadLabel {
+    #[label("here", colour = "red")]
+    pos: usize,
+    #[cache]
+    cache: DiagnosticCache<usize>
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use compiler_tools::diagnostic::DiagnosticCache;
+
+#[derive(compiler_tools_derive::Message)]
+#[message(severity = "error", brief = "twice")]
+struct Twice {
+    #[cache]
+    first: DiagnosticCache<usize>,
+    #[cache]
+    second: DiagnosticCache<usize>
+}
+
+fn main() {}
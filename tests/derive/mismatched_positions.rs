@@ -0,0 +1,12 @@
+use compiler_tools::diagnostic::DiagnosticCache;
+
+#[derive(compiler_tools_derive::Message)]
+#[message(severity = "error", brief = "mismatched")]
+struct Mismatched {
+    #[position]
+    pos: String,
+    #[cache]
+    cache: DiagnosticCache<usize>
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use compiler_tools::diagnostic::DiagnosticCache;
+
+#[derive(compiler_tools_derive::Message)]
+#[message(severity = "error")]
+enum Error {
+    #[message(brief = "first")]
+    First(#[cache] DiagnosticCache<usize>),
+    #[message(brief = "second")]
+    Second(#[position] usize)
+}
+
+fn main() {}
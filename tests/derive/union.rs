@@ -0,0 +1,7 @@
+#[derive(compiler_tools_derive::Message)]
+#[message(severity = "error", brief = "union")]
+union Union {
+    pos: usize
+}
+
+fn main() {}
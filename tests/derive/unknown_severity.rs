@@ -0,0 +1,10 @@
+use compiler_tools::diagnostic::DiagnosticCache;
+
+#[derive(compiler_tools_derive::Message)]
+#[message(severity = "fatal", brief = "unknown severity")]
+struct UnknownSeverity {
+    #[cache]
+    cache: DiagnosticCache<usize>
+}
+
+fn main() {}
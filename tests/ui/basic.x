@@ -0,0 +1,6 @@
+# A fixture for the UI test runner.
+let x = bad; #~ ERROR found `bad`
+let y = odd;
+#~^ WARNING found `odd`
+let z = bad + odd; #~ ERROR found `bad`
+#~| WARNING odd
@@ -0,0 +1,164 @@
+use compiler_tools::catalog::Catalog;
+use compiler_tools::catalog::CatalogError;
+use compiler_tools::catalog::localized_detail;
+use compiler_tools::catalog::set_active_catalog;
+use compiler_tools::messages::Message;
+use compiler_tools::messages::MessageGnuWriter;
+use compiler_tools::messages::MessagePositions;
+use compiler_tools::messages::MessageWriter;
+use compiler_tools::messages::Severity;
+use compiler_tools::position::FilePosition;
+use compiler_tools::suggest::WithSuggestion;
+use compiler_tools::symbol::Symbols;
+use std::env;
+use std::fs;
+use std::process;
+use std::rc::Rc;
+use termcolor::NoColor;
+
+struct UnusedMessage {
+    brief: String
+}
+
+impl Message for UnusedMessage {
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn brief(&self) -> &str {
+        &self.brief
+    }
+
+    fn detail(&self) -> &str {
+        ""
+    }
+
+    fn key(&self) -> Option<&str> {
+        Some("unused-variable")
+    }
+
+    fn arg(&self, name: &str) -> Option<String> {
+        match name {
+            "name" => Some(String::from("x")),
+            _ => None
+        }
+    }
+}
+
+impl MessagePositions<FilePosition<'_>> for UnusedMessage {}
+
+#[test]
+fn test_catalog_parse() {
+    let catalog = Catalog::parse("fr", "# Messages\n\
+                                        \n\
+                                        unused-variable = variable {name} \
+                                        inutilisée {{{other}}}\n\
+                                        unused-variable.detail = a\\nb\\\\\n")
+        .expect("Expected success");
+    let msg = UnusedMessage { brief: String::from("unused variable x") };
+
+    assert_eq!("fr", catalog.locale());
+    assert_eq!(2, catalog.len());
+    assert_eq!("variable x inutilisée {{other}}", catalog.brief(&msg));
+    assert_eq!("a\nb\\", catalog.detail(&msg));
+    assert_eq!(None, catalog.severity(Severity::Error));
+}
+
+#[test]
+fn test_catalog_parse_error() {
+    match Catalog::parse("fr", "a = b\n\nnot an entry\n") {
+        Err(CatalogError::Syntax { line }) => assert_eq!(3, line),
+        _ => panic!("Expected syntax error")
+    }
+}
+
+#[test]
+fn test_catalog_fallback() {
+    let mut language = Catalog::new("fr");
+    let mut catalog = Catalog::new("fr-CA");
+    let msg = UnusedMessage { brief: String::from("unused variable x") };
+
+    language.insert("severity.error", "erreur");
+    language.insert("unused-variable", "variable {name} inutilisée");
+    catalog.insert("severity.error", "erreur fatale");
+    catalog.set_fallback(language);
+
+    assert_eq!(Some("erreur fatale"), catalog.severity(Severity::Error));
+    assert_eq!("variable x inutilisée", catalog.brief(&msg));
+    assert_eq!("", catalog.detail(&msg));
+    assert_eq!(None, catalog.lookup("missing"));
+    assert_eq!("unused variable x", Catalog::new("de").brief(&msg));
+}
+
+#[test]
+fn test_catalog_load_locale() {
+    let dir = env::temp_dir().join(format!("compiler-tools-catalog-{}",
+                                           process::id()));
+
+    fs::create_dir_all(&dir).expect("Expected success");
+    fs::write(dir.join("fr.catalog"), "severity.warning = avertissement\n\
+                                       unused-variable = inutilisée\n")
+        .expect("Expected success");
+    fs::write(dir.join("fr-CA.catalog"), "unused-variable = {name}?\n")
+        .expect("Expected success");
+
+    let canadian = Catalog::load_locale(&dir, "fr-CA")
+        .expect("Expected success");
+    let french = Catalog::load_locale(&dir, "fr").expect("Expected success");
+    let german = Catalog::load_locale(&dir, "de-DE")
+        .expect("Expected success");
+
+    fs::remove_dir_all(&dir).expect("Expected success");
+
+    assert_eq!("fr-CA", canadian.locale());
+    assert_eq!(Some("{name}?"), canadian.lookup("unused-variable"));
+    assert_eq!(Some("avertissement"), canadian.lookup("severity.warning"));
+    assert_eq!(Some("inutilisée"), french.lookup("unused-variable"));
+    assert!(french.fallback().is_none());
+    assert!(german.is_empty());
+    assert!(german.fallback().is_none());
+}
+
+#[test]
+fn test_active_catalog() {
+    let mut catalog = Catalog::new("fr");
+    let msg = UnusedMessage { brief: String::from("unused variable x") };
+    let mut out = NoColor::new(Vec::new());
+
+    catalog.insert("severity.warning", "Avertissement");
+    catalog.insert("unused-variable", "variable {name} inutilisée");
+
+    assert_eq!("Warning", Severity::Warning.to_string());
+
+    set_active_catalog(Some(Rc::new(catalog)));
+
+    let label = Severity::Warning.to_string();
+
+    MessageGnuWriter.write_msg(&msg, &mut out).expect("Expected success");
+    set_active_catalog(None);
+
+    let text = String::from_utf8(out.into_inner()).expect("Expected success");
+
+    assert_eq!("Avertissement", label);
+    assert_eq!("warning: variable x inutilisée\n", text);
+    assert_eq!("Warning", Severity::Warning.to_string());
+}
+
+#[test]
+fn test_active_catalog_keeps_suggestion() {
+    let mut catalog = Catalog::new("fr");
+    let mut symbols = Symbols::new();
+    let sym = symbols.symbol_str("y");
+    let msg = UnusedMessage { brief: String::from("unused variable x") };
+    let msg = WithSuggestion::new(msg, Some(sym));
+
+    catalog.insert("unused-variable.detail", "la variable {name} est inutile");
+    set_active_catalog(Some(Rc::new(catalog)));
+
+    let detail = localized_detail(&msg).into_owned();
+
+    set_active_catalog(None);
+
+    assert_eq!("la variable x est inutile\nhelp: did you mean `y`?", detail);
+    assert_eq!("help: did you mean `y`?", localized_detail(&msg))
+}
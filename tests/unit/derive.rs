@@ -0,0 +1,168 @@
+use compiler_tools::diagnostic::Diagnostic;
+use compiler_tools::diagnostic::DiagnosticCache;
+use compiler_tools::messages::Highlighting;
+use compiler_tools::messages::Message;
+use compiler_tools::messages::MessagePositions;
+use compiler_tools::messages::Severity;
+
+#[derive(compiler_tools_derive::Diagnostic)]
+#[message(severity = "error")]
+enum ResolveError<'a> {
+    #[message(brief = "undefined variable `{name}`", code = "E0425")]
+    #[help("did you mean `{suggestion}`?")]
+    Undefined {
+        name: &'a str,
+        suggestion: &'a str,
+        #[label("not found in this scope")]
+        pos: usize
+    },
+    #[message(brief = "`{0}` defined {{twice}}", severity = "warning",
+              highlighting = "background")]
+    #[note("names must be unique")]
+    Duplicate(
+        &'a str,
+        #[label("redefined here")]
+        usize,
+        #[label("first defined as `{0}` here", severity = "info")]
+        usize
+    ),
+    #[message(brief = "internal error", severity = "internal")]
+    Internal {
+        #[position]
+        pos: usize
+    }
+}
+
+#[derive(compiler_tools_derive::Diagnostic)]
+#[message(severity = "lint", brief = "unused import", key = "unused-import")]
+struct UnusedImport {
+    name: String
+}
+
+#[derive(compiler_tools_derive::Diagnostic)]
+#[message(severity = "error", brief = "`{name}` redefined at {second}")]
+#[note("first definition at {first}")]
+struct Redefined {
+    name: String,
+    #[label("defined as {first}", severity = "info")]
+    first: String,
+    #[label("redefined after {first} as {second}")]
+    second: String
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, compiler_tools_derive::Message)]
+#[message(severity = "error")]
+enum TypeError<P> {
+    #[message(brief = "expected `{expected}`, found `{found}`",
+              code = "E0308", key = "mismatched-types")]
+    #[note("the types must match")]
+    Mismatch {
+        expected: String,
+        found: String,
+        #[label("expected `{expected}` here")]
+        pos: P,
+        #[label("found `{found}` here", severity = "info")]
+        other: P,
+        #[cache]
+        cache: DiagnosticCache<P>
+    },
+    #[message(brief = "cannot infer a type", severity = "warning")]
+    Unknown(#[cache] DiagnosticCache<P>)
+}
+
+#[test]
+fn test_derive_enum() {
+    let undefined = Diagnostic::from(ResolveError::Undefined {
+        name: "x", suggestion: "y", pos: 4
+    });
+    let duplicate = Diagnostic::from(ResolveError::Duplicate("f", 7, 2));
+    let internal = Diagnostic::from(ResolveError::Internal { pos: 1 });
+
+    assert_eq!(Severity::Error, undefined.severity());
+    assert_eq!("undefined variable `x`", undefined.brief());
+    assert_eq!("help: did you mean `y`?", undefined.detail());
+    assert_eq!(Some("E0425"), undefined.code());
+    assert_eq!(&[(Some("not found in this scope"), 4, Severity::Error)],
+               undefined.positions());
+    assert_eq!(Severity::Warning, duplicate.severity());
+    assert_eq!("`f` defined {twice}", duplicate.brief());
+    assert_eq!("note: names must be unique", duplicate.detail());
+    assert_eq!(Highlighting::Background, duplicate.highlighting());
+    assert_eq!(None, duplicate.code());
+    assert_eq!(&[(Some("redefined here"), 7, Severity::Warning),
+                 (Some("first defined as `f` here"), 2, Severity::Info)],
+               duplicate.positions());
+    assert_eq!(Severity::Internal, internal.severity());
+    assert_eq!(&[(None, 1, Severity::Internal)], internal.positions());
+}
+
+#[test]
+fn test_derive_struct() {
+    let msg: Diagnostic<()> =
+        Diagnostic::from(UnusedImport { name: String::from("std::io") });
+
+    assert_eq!(Severity::Lint, msg.severity());
+    assert_eq!("unused import", msg.brief());
+    assert_eq!(Some("unused-import"), msg.key());
+    assert_eq!(Some(String::from("std::io")), msg.arg("name"));
+    assert!(msg.positions().is_empty());
+}
+
+#[test]
+fn test_derive_non_copy_positions() {
+    let msg = Diagnostic::from(Redefined { name: String::from("x"),
+                                           first: String::from("a:1"),
+                                           second: String::from("a:2") });
+
+    assert_eq!("`x` redefined at a:2", msg.brief());
+    assert_eq!("note: first definition at a:1", msg.detail());
+    assert_eq!(&[(Some("defined as a:1"), String::from("a:1"),
+                  Severity::Info),
+                 (Some("redefined after a:1 as a:2"), String::from("a:2"),
+                  Severity::Error)],
+               msg.positions());
+}
+
+#[test]
+fn test_derive_message() {
+    let mismatch = TypeError::Mismatch {
+        expected: String::from("u32"), found: String::from("bool"),
+        pos: 3, other: 8, cache: DiagnosticCache::new()
+    };
+    let unknown: TypeError<usize> =
+        TypeError::Unknown(DiagnosticCache::new());
+
+    assert_eq!(Severity::Error, mismatch.severity());
+    assert_eq!("expected `u32`, found `bool`", mismatch.brief());
+    assert_eq!("note: the types must match", mismatch.detail());
+    assert_eq!(Some("E0308"), mismatch.code());
+    assert_eq!(Some("mismatched-types"), mismatch.key());
+    assert_eq!(Some(String::from("bool")), mismatch.arg("found"));
+    assert_eq!(&[(Some("expected `u32` here"), 3, Severity::Error),
+                 (Some("found `bool` here"), 8, Severity::Info)],
+               mismatch.positions());
+    assert_eq!(Severity::Warning, unknown.severity());
+    assert_eq!("cannot infer a type", unknown.brief());
+    assert!(unknown.positions().is_empty());
+}
+
+#[test]
+fn test_derive_message_cached() {
+    let msg = TypeError::Mismatch {
+        expected: String::from("u32"), found: String::from("bool"),
+        pos: 3, other: 8, cache: DiagnosticCache::new()
+    };
+    let copy = msg.clone();
+
+    assert!(std::ptr::eq(msg.brief(), msg.brief()));
+    assert_eq!(msg, copy);
+    assert_eq!(msg.brief(), copy.brief());
+    assert!(!std::ptr::eq(msg.brief(), copy.brief()))
+}
+
+#[test]
+fn test_derive_errors() {
+    let cases = trybuild::TestCases::new();
+
+    cases.compile_fail("tests/derive/*.rs")
+}
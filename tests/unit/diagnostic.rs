@@ -0,0 +1,67 @@
+use compiler_tools::diagnostic::Diagnostic;
+use compiler_tools::files::FileKind;
+use compiler_tools::files::Filenames;
+use compiler_tools::messages::Highlighting;
+use compiler_tools::messages::Message;
+use compiler_tools::messages::MessageGnuWriter;
+use compiler_tools::messages::MessagePositions;
+use compiler_tools::messages::MessageWriter;
+use compiler_tools::messages::Severity;
+use compiler_tools::position::FilePosition;
+use termcolor::NoColor;
+
+#[test]
+fn test_diagnostic_builder() {
+    let msg = Diagnostic::error("mismatched types")
+        .with_label(1, "expected `int`")
+        .with_secondary(2, &format!("found `{}`", "bool"))
+        .with_position(3)
+        .with_note("conversions must be explicit")
+        .with_help("add a cast")
+        .with_code("E0308")
+        .with_key("mismatched-types")
+        .with_arg("expected", "int")
+        .with_arg("expected", 5)
+        .with_highlighting(Highlighting::Background);
+
+    assert_eq!(Severity::Error, msg.severity());
+    assert_eq!("mismatched types", msg.brief());
+    assert_eq!("note: conversions must be explicit\nhelp: add a cast",
+               msg.detail());
+    assert_eq!(Some("E0308"), msg.code());
+    assert_eq!(Some("mismatched-types"), msg.key());
+    assert_eq!(Some(String::from("5")), msg.arg("expected"));
+    assert_eq!(None, msg.arg("found"));
+    assert_eq!(Highlighting::Background, msg.highlighting());
+    assert_eq!(&[(Some("expected `int`"), 1, Severity::Error),
+                 (Some("found `bool`"), 2, Severity::Info),
+                 (None, 3, Severity::Error)],
+               msg.positions());
+}
+
+#[test]
+fn test_diagnostic_clone() {
+    let msg = Diagnostic::warning("unused").with_label(1, "here");
+    let copy = msg.clone();
+
+    drop(msg);
+
+    assert_eq!(&[(Some("here"), 1, Severity::Warning)], copy.positions());
+}
+
+#[test]
+fn test_diagnostic_write() {
+    let mut filenames = Filenames::new();
+    let filename = filenames.virtual_filename(FileKind::Memory, "a.x");
+    let msg = Diagnostic::lint("unused import")
+        .with_label(0, "remove this")
+        .map_positions(|_| FilePosition::File { filename });
+    let mut out = NoColor::new(Vec::new());
+
+    MessageGnuWriter.write_msg(&msg, &mut out).expect("Expected success");
+
+    let text = String::from_utf8(out.into_inner()).expect("Expected success");
+
+    assert_eq!("<memory:a.x>: lint warning: unused import: remove this\n",
+               text)
+}
@@ -0,0 +1,218 @@
+use compiler_tools::encoding::DecodeError;
+use compiler_tools::encoding::Encoding;
+use compiler_tools::encoding::OffsetMap;
+use compiler_tools::encoding::decode;
+use compiler_tools::files::Filenames;
+use compiler_tools::lines::Offset;
+use compiler_tools::messages::Message;
+use compiler_tools::messages::MessageFullWriter;
+use compiler_tools::messages::MessagePositions;
+use compiler_tools::messages::MessageWriter;
+use compiler_tools::sources::LoadError;
+use compiler_tools::sources::Sources;
+use std::env;
+use std::fs;
+use std::process;
+use termcolor::NoColor;
+
+#[test]
+fn test_detect_bom() {
+    assert_eq!(Some((Encoding::Utf8, 3)),
+               Encoding::detect_bom(b"\xef\xbb\xbfabc"));
+    assert_eq!(Some((Encoding::Utf16Le, 2)),
+               Encoding::detect_bom(b"\xff\xfea\x00"));
+    assert_eq!(Some((Encoding::Utf16Be, 2)),
+               Encoding::detect_bom(b"\xfe\xff\x00a"));
+    assert_eq!(None, Encoding::detect_bom(b"abc"))
+}
+
+#[test]
+fn test_from_label() {
+    assert_eq!(Some(Encoding::Latin1), Encoding::from_label("ISO-8859-1"));
+    assert_eq!(Some(Encoding::Latin1), Encoding::from_label("latin1"));
+    assert_eq!(Some(Encoding::Utf16Le), Encoding::from_label("utf_16le"));
+    assert_eq!(Some(Encoding::Utf8), Encoding::from_label("UTF-8"));
+    assert_eq!(None, Encoding::from_label("ebcdic"))
+}
+
+#[test]
+fn test_decode_utf8_bom() {
+    let decoded = decode(b"\xef\xbb\xbfab\ncd", Encoding::Latin1)
+        .expect("Expected success");
+
+    assert_eq!("ab\ncd", decoded.text);
+    assert_eq!(Encoding::Utf8, decoded.encoding);
+    assert!(!decoded.offset_map.is_identity());
+    assert_eq!(Offset::from(6), decoded.offset_map.original(Offset::from(3)))
+}
+
+#[test]
+fn test_decode_utf8_identity() {
+    let decoded = decode("a\u{e9}b".as_bytes(), Encoding::Utf8)
+        .expect("Expected success");
+
+    assert_eq!("a\u{e9}b", decoded.text);
+    assert_eq!(OffsetMap::identity(), decoded.offset_map);
+    assert_eq!(Offset::from(3), decoded.offset_map.original(Offset::from(3)))
+}
+
+#[test]
+fn test_decode_latin1() {
+    let decoded = decode(b"a\xe9b\xffc", Encoding::Latin1)
+        .expect("Expected success");
+    let map = &decoded.offset_map;
+
+    assert_eq!("a\u{e9}b\u{ff}c", decoded.text);
+    assert_eq!(Encoding::Latin1, decoded.encoding);
+    assert_eq!(Offset::from(0), map.original(Offset::from(0)));
+    assert_eq!(Offset::from(1), map.original(Offset::from(1)));
+    assert_eq!(Offset::from(1), map.original(Offset::from(2)));
+    assert_eq!(Offset::from(2), map.original(Offset::from(3)));
+    assert_eq!(Offset::from(3), map.original(Offset::from(4)));
+    assert_eq!(Offset::from(4), map.original(Offset::from(6)))
+}
+
+#[test]
+fn test_decode_utf16le() {
+    let decoded = decode(b"\xff\xfea\x00\xe9\x00\n\x00\x3d\xd8\x00\xdeb\x00",
+                         Encoding::Utf8)
+        .expect("Expected success");
+    let map = &decoded.offset_map;
+
+    assert_eq!("a\u{e9}\n\u{1f600}b", decoded.text);
+    assert_eq!(Encoding::Utf16Le, decoded.encoding);
+    assert_eq!(Offset::from(2), map.original(Offset::from(0)));
+    assert_eq!(Offset::from(4), map.original(Offset::from(1)));
+    assert_eq!(Offset::from(6), map.original(Offset::from(3)));
+    assert_eq!(Offset::from(8), map.original(Offset::from(4)));
+    assert_eq!(Offset::from(12), map.original(Offset::from(8)))
+}
+
+#[test]
+fn test_decode_utf16be() {
+    let decoded = decode(b"\xfe\xff\x00a\x00b", Encoding::Utf8)
+        .expect("Expected success");
+
+    assert_eq!("ab", decoded.text);
+    assert_eq!(Encoding::Utf16Be, decoded.encoding);
+    assert_eq!(Offset::from(4),
+               decoded.offset_map.original(Offset::from(1)))
+}
+
+#[test]
+fn test_decode_invalid_utf8() {
+    let err = decode(b"ab\ncd\xffe", Encoding::Utf8)
+        .expect_err("Expected error");
+
+    assert_eq!(DecodeError { encoding: Encoding::Utf8, offset: 5, len: 1 },
+               err)
+}
+
+#[test]
+fn test_decode_invalid_utf16() {
+    let unpaired = decode(b"\xff\xfea\x00\x00\xdcb\x00", Encoding::Utf8)
+        .expect_err("Expected error");
+    let odd = decode(b"\xff\xfea\x00b", Encoding::Utf8)
+        .expect_err("Expected error");
+
+    assert_eq!(DecodeError { encoding: Encoding::Utf16Le, offset: 4,
+                             len: 2 },
+               unpaired);
+    assert_eq!(DecodeError { encoding: Encoding::Utf16Le, offset: 4,
+                             len: 1 },
+               odd)
+}
+
+#[test]
+fn test_load_encoded() {
+    let path = env::temp_dir().join(format!("compiler-tools-latin1-{}.x",
+                                            process::id()));
+
+    fs::write(&path, b"caf\xe9\nna\xefve\n").expect("Expected success");
+
+    let mut filenames = Filenames::new();
+    let filename = filenames.filename(&path).expect("Expected success");
+    let mut srcs = Sources::new();
+    let src = srcs.load_encoded(filename, Encoding::Latin1)
+        .expect("Expected success");
+    let lines: Vec<String> = src.lines().iter().map(String::from).collect();
+    let encoding = src.encoding();
+    let original = src.offset_map().original(Offset::from(8));
+
+    fs::remove_file(&path).expect("Expected success");
+
+    assert_eq!(vec!["caf\u{e9}", "na\u{ef}ve"], lines);
+    assert_eq!(Encoding::Latin1, encoding);
+    assert_eq!(Offset::from(7), original)
+}
+
+#[test]
+fn test_load_invalid_utf8() {
+    let path = env::temp_dir().join(format!("compiler-tools-invalid-{}.x",
+                                            process::id()));
+
+    fs::write(&path, b"abc\nde\xc3(f\n").expect("Expected success");
+
+    let mut filenames = Filenames::new();
+    let filename = filenames.filename(&path).expect("Expected success");
+    let mut srcs = Sources::new();
+    let err = match srcs.load(filename) {
+        Err(LoadError::Encoding(err)) => err,
+        _ => panic!("Expected encoding error")
+    };
+    // SAFETY: nothing modifies the file while it is mapped.
+    let mapped = unsafe { srcs.load_mapped(filename) };
+
+    fs::remove_file(&path).expect("Expected success");
+
+    let pos = err.position();
+
+    assert!(matches!(mapped, Err(LoadError::Encoding(_))));
+    assert_eq!(Encoding::Utf8, err.encoding());
+    assert_eq!(filename, pos.filename());
+    assert_eq!(6, pos.offset());
+    assert_eq!(1, pos.line());
+    assert_eq!(2, pos.column());
+    assert_eq!(1, err.positions().len());
+    assert_eq!("byte 0xc3 at offset 6 is not valid UTF-8", err.detail());
+    assert_eq!(format!("invalid UTF-8 in {} at {}:2.3 (byte 6): byte 0xc3 \
+                        at offset 6 is not valid UTF-8",
+                       filename, filename),
+               err.to_string());
+
+    let mut out = NoColor::new(Vec::new());
+
+    MessageFullWriter::new(&srcs).write_msg(&*err, &mut out)
+        .expect("Expected success");
+
+    let text = String::from_utf8(out.into_inner()).expect("Expected success");
+
+    assert_eq!(format!("Error: invalid UTF-8 in {}\n  invalid byte at {}:2.3 \
+                        (byte 6)\nbyte 0xc3 at offset 6 is not valid UTF-8\n",
+                       filename, filename),
+               text)
+}
+
+#[test]
+fn test_load_mapped_bom() {
+    let path = env::temp_dir().join(format!("compiler-tools-bom-{}.x",
+                                            process::id()));
+
+    fs::write(&path, b"\xef\xbb\xbfab\ncd\n").expect("Expected success");
+
+    let mut filenames = Filenames::new();
+    let filename = filenames.filename(&path).expect("Expected success");
+    let mut srcs = Sources::new();
+    // SAFETY: nothing modifies the file while it is mapped.
+    let src = unsafe { srcs.load_mapped(filename) }
+        .expect("Expected success");
+    let lines: Vec<String> = src.lines().iter().map(String::from).collect();
+    let is_mapped = src.is_mapped();
+    let original = src.offset_map().original(Offset::from(3));
+
+    fs::remove_file(&path).expect("Expected success");
+
+    assert!(is_mapped);
+    assert_eq!(vec!["ab", "cd"], lines);
+    assert_eq!(Offset::from(6), original)
+}
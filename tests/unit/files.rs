@@ -1,13 +1,19 @@
+use compiler_tools::files::FileKind;
+use compiler_tools::files::FilenameDisplay;
 use compiler_tools::files::Filenames;
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
 use std::path::Path;
+use std::process;
 
 #[test]
 fn test_eq() {
     let path_a = Path::new("./Cargo.toml");
     let path_b = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let a = filenames.filename(&path_a).expect("Expected success");
-    let b = filenames.filename(&path_b).expect("Expected success");
+    let a = filenames.filename(path_a).expect("Expected success");
+    let b = filenames.filename(path_b).expect("Expected success");
 
     assert_eq!(a, b)
 }
@@ -17,8 +23,8 @@ fn test_ne() {
     let path_a = Path::new("./src/symbol.rs");
     let path_b = Path::new("./src/files.rs");
     let mut filenames = Filenames::new();
-    let a = filenames.filename(&path_a).expect("Expected success");
-    let b = filenames.filename(&path_b).expect("Expected success");
+    let a = filenames.filename(path_a).expect("Expected success");
+    let b = filenames.filename(path_b).expect("Expected success");
 
     assert_ne!(a, b)
 }
@@ -27,7 +33,7 @@ fn test_ne() {
 fn test_eq_path() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let fname = filenames.filename(&path).expect("Expected success");
+    let fname = filenames.filename(path).expect("Expected success");
 
     assert_eq!(&fname, Path::new("./Cargo.toml"));
     assert_eq!(Path::new("./Cargo.toml"), &fname)
@@ -37,7 +43,7 @@ fn test_eq_path() {
 fn test_ne_path() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let fname = filenames.filename(&path).expect("Expected success");
+    let fname = filenames.filename(path).expect("Expected success");
 
     assert_ne!(&fname, Path::new("./src/files.rs"));
     assert_ne!(Path::new("./src/files.rs"), &fname)
@@ -48,8 +54,258 @@ fn test_ne_path() {
 fn test_ord_path() {
     let path = Path::new("./src/symbol.rs");
     let mut filenames = Filenames::new();
-    let fname = filenames.filename(&path).expect("Expected success");
+    let fname = filenames.filename(path).expect("Expected success");
 
     assert!(&fname > Path::new("./src/files.rs"));
     assert!(Path::new("./src/files.rs") < &fname)
 }
+
+#[test]
+fn test_virtual_display() {
+    let mut filenames = Filenames::new();
+    let stdin = filenames.stdin();
+    let generated = filenames.virtual_filename(FileKind::Generated, "foo");
+
+    assert_eq!("<stdin>", stdin.to_string());
+    assert_eq!("<generated:foo>", generated.to_string())
+}
+
+#[test]
+fn test_virtual_kind() {
+    let path = Path::new("./Cargo.toml");
+    let mut filenames = Filenames::new();
+    let disk = filenames.filename(path).expect("Expected success");
+    let memory = filenames.virtual_filename(FileKind::Memory, "test.x");
+
+    assert_eq!(FileKind::Disk, disk.kind());
+    assert!(!disk.is_virtual());
+    assert_eq!(FileKind::Memory, memory.kind());
+    assert!(memory.is_virtual());
+    assert!(!memory.exists());
+    assert!(memory.metadata().is_err());
+    assert_eq!(Some(OsStr::new("x")), memory.extension())
+}
+
+#[test]
+fn test_virtual_eq() {
+    let mut filenames = Filenames::new();
+    let a = filenames.virtual_filename(FileKind::Memory, "Cargo.toml");
+    let b = filenames.virtual_filename(FileKind::Memory, "Cargo.toml");
+    let c = filenames.virtual_filename(FileKind::Generated, "Cargo.toml");
+    let disk = filenames.filename(Path::new("./Cargo.toml"))
+        .expect("Expected success");
+
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+    assert_ne!(a, disk)
+}
+
+#[test]
+fn test_virtual_nonexistent() {
+    let mut filenames = Filenames::new();
+
+    assert!(filenames.filename(Path::new("./does/not/exist.x")).is_err());
+
+    let fname = filenames.virtual_filename(FileKind::Archive,
+                                           "lib.zip/does/not/exist.x");
+
+    assert_eq!("<archive:lib.zip/does/not/exist.x>", fname.to_string())
+}
+
+#[test]
+fn test_display_canonical() {
+    let path = Path::new("./Cargo.toml");
+    let canonical = path.canonicalize().expect("Expected success");
+    let mut filenames = Filenames::new();
+    let fname = filenames.filename(path).expect("Expected success");
+
+    assert_eq!(FilenameDisplay::Canonical, filenames.display_mode());
+    assert_eq!(format!("{:?}", canonical), fname.to_string())
+}
+
+#[test]
+fn test_display_supplied() {
+    let mut filenames = Filenames::new();
+
+    filenames.set_display_mode(FilenameDisplay::Supplied);
+
+    let fname = filenames.filename(Path::new("./src/../Cargo.toml"))
+        .expect("Expected success");
+    let again = filenames.filename(Path::new("./Cargo.toml"))
+        .expect("Expected success");
+
+    assert_eq!(fname, again);
+    assert_eq!(Path::new("./src/../Cargo.toml"), fname.supplied());
+    assert_eq!("\"./src/../Cargo.toml\"", again.to_string())
+}
+
+#[test]
+fn test_display_relative() {
+    let mut filenames = Filenames::new();
+
+    filenames.set_base(Path::new(".")).expect("Expected success");
+    filenames.set_display_mode(FilenameDisplay::Relative);
+
+    let fname = filenames.filename(Path::new("./src/../src/lib.rs"))
+        .expect("Expected success");
+
+    assert_eq!(Path::new("src/lib.rs"), fname.display_path());
+    assert_eq!("\"src/lib.rs\"", fname.to_string())
+}
+
+#[test]
+fn test_display_relative_outside() {
+    let path = Path::new("./Cargo.toml");
+    let canonical = path.canonicalize().expect("Expected success");
+    let mut filenames = Filenames::new();
+
+    filenames.set_base(Path::new("./src")).expect("Expected success");
+    filenames.set_display_mode(FilenameDisplay::Relative);
+
+    let fname = filenames.filename(path).expect("Expected success");
+
+    assert_eq!(canonical, fname.display_path())
+}
+
+#[test]
+fn test_display_relative_no_base() {
+    let mut filenames = Filenames::new();
+
+    filenames.set_display_mode(FilenameDisplay::Relative);
+
+    let fname = filenames.filename(Path::new("./Cargo.toml"))
+        .expect("Expected success");
+
+    assert_eq!("\"./Cargo.toml\"", fname.to_string())
+}
+
+#[test]
+fn test_remap_display() {
+    let cwd = Path::new(".").canonicalize().expect("Expected success");
+    let mut filenames = Filenames::new();
+
+    filenames.add_remap(&cwd, Path::new("/src"));
+
+    let fname = filenames.filename(Path::new("./src/lib.rs"))
+        .expect("Expected success");
+
+    assert_eq!(Path::new("/src/src/lib.rs"), fname.display_path());
+    assert_eq!(Path::new("/src/src/lib.rs"), fname.remapped_path());
+    assert_eq!("\"/src/src/lib.rs\"", fname.to_string());
+    assert!(fname.exists())
+}
+
+#[test]
+fn test_remap_last_wins() {
+    let cwd = Path::new(".").canonicalize().expect("Expected success");
+    let mut filenames = Filenames::new();
+
+    filenames.add_remap(&cwd, Path::new("/a"));
+    filenames.add_remap(&cwd.join("src"), Path::new("/b"));
+
+    let fname = filenames.filename(Path::new("./src/lib.rs"))
+        .expect("Expected success");
+
+    filenames.clear_remaps();
+
+    let other = filenames.filename(Path::new("./src/files.rs"))
+        .expect("Expected success");
+
+    assert_eq!(Path::new("/b/lib.rs"), fname.display_path());
+    assert_eq!(cwd.join("src/files.rs"), other.display_path())
+}
+
+#[test]
+fn test_remap_components() {
+    let cwd = Path::new(".").canonicalize().expect("Expected success");
+    let partial = cwd.join("sr");
+    let mut filenames = Filenames::new();
+
+    filenames.add_remap(&partial, Path::new("/a"));
+
+    let fname = filenames.filename(Path::new("./src/lib.rs"))
+        .expect("Expected success");
+
+    assert_eq!(cwd.join("src/lib.rs"), fname.display_path())
+}
+
+#[test]
+fn test_unmap() {
+    let cwd = Path::new(".").canonicalize().expect("Expected success");
+    let mut filenames = Filenames::new();
+
+    filenames.add_remap(&cwd, Path::new("/src"));
+
+    assert_eq!(cwd.join("Cargo.toml"),
+               filenames.unmap(Path::new("/src/Cargo.toml")));
+    assert_eq!(Path::new("/other/Cargo.toml"),
+               filenames.unmap(Path::new("/other/Cargo.toml")))
+}
+
+#[test]
+fn test_find() {
+    let mut filenames = Filenames::new();
+
+    assert_eq!(None, filenames.find(Path::new("./Cargo.toml")));
+
+    let fname = filenames.filename(Path::new("./Cargo.toml"))
+        .expect("Expected success");
+    let canonical = Path::new("./Cargo.toml").canonicalize()
+        .expect("Expected success");
+
+    assert_eq!(Some(fname), filenames.find(Path::new("./Cargo.toml")));
+    assert_eq!(Some(fname), filenames.find(Path::new("Cargo.toml")));
+    assert_eq!(Some(fname), filenames.find(Path::new("./src/../Cargo.toml")));
+    assert_eq!(Some(fname), filenames.find(&canonical));
+    assert_eq!(None, filenames.find(Path::new("./src/lib.rs")))
+}
+
+#[test]
+fn test_find_virtual() {
+    let mut filenames = Filenames::new();
+    let memory = filenames.virtual_filename(FileKind::Memory, "Cargo.toml");
+
+    assert_eq!(None, filenames.find(Path::new("Cargo.toml")));
+    assert_ne!(&memory, Path::new("Cargo.toml"));
+    assert_eq!(None, memory.partial_cmp(Path::new("Cargo.toml")))
+}
+
+#[test]
+fn test_deleted() {
+    let path = env::temp_dir().join(format!("compiler-tools-deleted-{}.x",
+                                            process::id()));
+
+    fs::write(&path, "x").expect("Expected success");
+
+    let mut filenames = Filenames::new();
+    let fname = filenames.filename(&path).expect("Expected success");
+
+    fs::remove_file(&path).expect("Expected success");
+
+    assert_eq!(&fname, path.as_path());
+    assert_eq!(Some(fname), filenames.find(&path));
+    assert!(filenames.filename(&path).is_err())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_find_symlink() {
+    let tmp = env::temp_dir().canonicalize().expect("Expected success");
+    let dir = tmp.join(format!("compiler-tools-symlink-{}", process::id()));
+    let target = dir.join("target.x");
+    let link = dir.join("link.x");
+
+    fs::create_dir_all(&dir).expect("Expected success");
+    fs::write(&target, "x").expect("Expected success");
+    std::os::unix::fs::symlink(&target, &link).expect("Expected success");
+
+    let mut filenames = Filenames::new();
+    let fname = filenames.filename(&link).expect("Expected success");
+    let found = filenames.find(&link);
+
+    fs::remove_dir_all(&dir).expect("Expected success");
+
+    assert_eq!(Some(fname), found);
+    assert_eq!(&fname, link.as_path());
+    assert_eq!(&fname, target.as_path())
+}
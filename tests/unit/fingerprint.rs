@@ -0,0 +1,58 @@
+use compiler_tools::files::Filenames;
+use compiler_tools::fingerprint::ContentHash;
+use compiler_tools::fingerprint::Fingerprint;
+use std::env;
+use std::fs;
+use std::process;
+
+#[test]
+fn test_hash_eq() {
+    assert_eq!(ContentHash::of(b"hello"), ContentHash::of(b"hello"));
+    assert_ne!(ContentHash::of(b"hello"), ContentHash::of(b"hellp"))
+}
+
+#[test]
+fn test_hash64() {
+    let hash = ContentHash::of(b"hello");
+
+    assert_eq!(hash.hash128() as u64, hash.hash64())
+}
+
+#[cfg(feature = "sha256")]
+#[test]
+fn test_sha256() {
+    let hash = ContentHash::of(b"");
+    let digest = hash.sha256().expect("Expected some");
+
+    assert_eq!([0xe3, 0xb0, 0xc4, 0x42], digest[.. 4])
+}
+
+#[test]
+fn test_fingerprint_changed() {
+    let path = env::temp_dir().join(format!("compiler-tools-fp-{}.x",
+                                            process::id()));
+
+    fs::write(&path, "abc").expect("Expected success");
+
+    let mut filenames = Filenames::new();
+    let filename = filenames.filename(&path).expect("Expected success");
+    let metadata = filename.metadata().expect("Expected success");
+    let fingerprint = Fingerprint::new(&metadata, ContentHash::of(b"abc"));
+
+    assert_eq!(3, fingerprint.size());
+    assert!(!fingerprint.changed(filename).expect("Expected success"));
+
+    fs::write(&path, "abd").expect("Expected success");
+
+    let same_size = fingerprint.changed(filename);
+
+    fs::write(&path, "abcd").expect("Expected success");
+
+    let bigger = fingerprint.changed(filename);
+
+    fs::remove_file(&path).expect("Expected success");
+
+    assert!(same_size.expect("Expected success"));
+    assert!(bigger.expect("Expected success"));
+    assert!(fingerprint.changed(filename).is_err())
+}
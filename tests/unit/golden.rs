@@ -0,0 +1,78 @@
+use compiler_tools::diagnostic::Diagnostic;
+use compiler_tools::files::Filenames;
+use compiler_tools::golden::GoldenError;
+use compiler_tools::golden::GoldenTest;
+use compiler_tools::messages::MessageGnuWriter;
+use compiler_tools::messages::MessageSimpleWriter;
+use compiler_tools::position::FilePosition;
+use std::env;
+use std::fs;
+use std::process;
+
+#[test]
+fn test_golden_normalize() {
+    let dir = env::temp_dir().join(format!("compiler-tools-normalize-{}",
+                                           process::id()));
+    let mut golden = GoldenTest::new(&dir);
+
+    golden.add_replacement("1234", "$PID");
+    golden.normalize_path("/home/user/project", "$DIR");
+
+    assert_eq!("$DIR/src/a.x:1:1: error (pid $PID)\n$DIR\n",
+               golden.normalize("/home/user/project\\src\\a.x:1:1: \
+                                 error (pid 1234)\r\n\
+                                 /home/user/project\n"));
+}
+
+#[test]
+fn test_golden_bless_and_check() {
+    let dir = env::temp_dir().join(format!("compiler-tools-golden-{}",
+                                           process::id()));
+    let path = env::current_dir().expect("Expected success")
+        .join("Cargo.toml");
+    let mut filenames = Filenames::new();
+    let filename = filenames.filename(&path).expect("Expected success");
+    let msgs = vec![
+        Diagnostic::error("bad thing")
+            .with_label(FilePosition::File { filename }, "here")
+    ];
+    let mut golden = GoldenTest::new(&dir);
+
+    golden.normalize_path(env::current_dir().expect("Expected success"),
+                          "$DIR");
+    golden.set_bless(false);
+
+    match golden.check_messages("gnu", &MessageGnuWriter, &msgs) {
+        Err(GoldenError::Missing { path }) =>
+            assert_eq!(dir.join("gnu.stderr"), path),
+        _ => panic!("Expected missing file")
+    }
+
+    golden.set_bless(true);
+    golden.check_messages("gnu", &MessageGnuWriter, &msgs)
+        .expect("Expected success");
+    golden.set_bless(false);
+    golden.check_messages("gnu", &MessageGnuWriter, &msgs)
+        .expect("Expected success");
+
+    let plain = fs::read_to_string(dir.join("gnu.stderr"))
+        .expect("Expected success");
+    let color = fs::read_to_string(dir.join("gnu.color.stderr"))
+        .expect("Expected success");
+    let mismatch = golden.check_messages("gnu", &MessageSimpleWriter, &msgs);
+
+    fs::remove_dir_all(&dir).expect("Expected success");
+
+    assert_eq!("$DIR/Cargo.toml: error: bad thing: here\n", plain);
+    assert_eq!(plain, color);
+
+    match mismatch {
+        Err(err @ GoldenError::Mismatch { .. }) => {
+            let text = err.to_string();
+
+            assert!(text.contains("   1 -$DIR/Cargo.toml: error: bad thing"));
+            assert!(text.contains("   1 +Error: bad thing"))
+        },
+        _ => panic!("Expected mismatch")
+    }
+}
@@ -0,0 +1,142 @@
+use compiler_tools::catalog::Catalog;
+use compiler_tools::catalog::set_active_catalog;
+use compiler_tools::files::FileOffsets;
+use compiler_tools::files::FileKind;
+use compiler_tools::files::Filenames;
+use compiler_tools::html::HtmlReportWriter;
+use compiler_tools::html::escape_html;
+use compiler_tools::lines::LineOffsets;
+use compiler_tools::lines::Offset;
+use compiler_tools::messages::Message;
+use compiler_tools::messages::MessagePositions;
+use compiler_tools::messages::Severity;
+use compiler_tools::position::FilePosition;
+use compiler_tools::position::OffsetPosition;
+use compiler_tools::sources::Sources;
+use std::path::Path;
+use std::rc::Rc;
+
+struct TestMessage<'a> {
+    severity: Severity,
+    brief: &'static str,
+    positions: Vec<(Option<&'static str>, FilePosition<'a>, Severity)>
+}
+
+impl Message for TestMessage<'_> {
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn brief(&self) -> &str {
+        self.brief
+    }
+
+    fn detail(&self) -> &str {
+        "a < b"
+    }
+}
+
+impl<'a> MessagePositions<FilePosition<'a>> for TestMessage<'a> {
+    fn positions(&self) -> &[(Option<&str>, FilePosition<'a>, Severity)] {
+        &self.positions
+    }
+}
+
+#[test]
+fn test_escape_html() {
+    assert_eq!("&lt;a href=&quot;x&quot;&gt;&amp;&#39;",
+               escape_html("<a href=\"x\">&'"))
+}
+
+#[test]
+fn test_html_report() {
+    let mut filenames = Filenames::new();
+    let first = filenames.virtual_filename(FileKind::Memory, "first");
+    let second = filenames.virtual_filename(FileKind::Memory, "second");
+    let mut srcs = Sources::new();
+
+    srcs.add_str(first, "let x = <y>;\n").expect("Expected some");
+
+    let mut line_offsets = LineOffsets::new();
+
+    line_offsets.push_line(0);
+
+    let file_offsets = FileOffsets::new(first, line_offsets);
+    let span = FilePosition::Portion {
+        file_offsets: &file_offsets,
+        offset: OffsetPosition::Span { start: Offset::from(8),
+                                       len: Offset::from(3) }
+    };
+    let msgs = vec![
+        TestMessage { severity: Severity::Error, brief: "bad <y>",
+                      positions: vec![(Some("here"), span,
+                                       Severity::Error)] },
+        TestMessage { severity: Severity::Warning, brief: "whole file",
+                      positions: vec![(None,
+                                       FilePosition::File { filename: second },
+                                       Severity::Warning)] },
+        TestMessage { severity: Severity::Error, brief: "no position",
+                      positions: vec![] }
+    ];
+    let mut writer = HtmlReportWriter::new(&srcs);
+    let mut out = Vec::new();
+
+    writer.set_title("Nightly <build>");
+    writer.write_report(&msgs, &mut out).expect("Expected success");
+
+    let text = String::from_utf8(out).expect("Expected success");
+
+    assert_eq!("Nightly <build>", writer.title());
+    assert!(text.starts_with("<!DOCTYPE html>\n"));
+    assert!(text.contains("<title>Nightly &lt;build&gt;</title>"));
+    assert!(text.contains("<p class=\"summary\">2 Error, 1 Warning</p>"));
+    assert!(text.contains("<section class=\"file\" id=\"file-1\">\n\
+                           <h2>&lt;memory:first&gt;</h2>"));
+    assert!(text.contains("<section class=\"file\" id=\"file-2\">\n\
+                           <h2>&lt;memory:second&gt;</h2>"));
+    assert!(text.contains("<section class=\"file\" id=\"file-3\">\n\
+                           <h2>Other</h2>"));
+    assert!(text.contains("id=\"msg-1\""));
+    assert!(text.contains("href=\"#msg-3\""));
+    assert!(text.contains("style=\"background-color: red\">Error</span>\
+                           <span class=\"brief\">bad &lt;y&gt;</span>"));
+    assert!(text.contains("<pre class=\"excerpt\">let x = \
+                           <mark style=\"color: red\">&lt;y&gt;</mark>;\n\
+                           </pre>"));
+    assert!(text.contains("<p class=\"detail\">a &lt; b</p>"));
+    assert!(text.ends_with("</body>\n</html>\n"))
+}
+
+#[test]
+fn test_html_report_localized() {
+    let mut filenames = Filenames::new();
+    let cargo = filenames.filename(Path::new("./Cargo.toml"))
+        .expect("Expected success");
+    let srcs = Sources::new();
+    let msgs = vec![
+        TestMessage { severity: Severity::Error, brief: "bad",
+                      positions: vec![(None,
+                                       FilePosition::File { filename: cargo },
+                                       Severity::Error)] }
+    ];
+    let mut catalog = Catalog::new("xx");
+    let writer = HtmlReportWriter::new(&srcs);
+    let mut out = Vec::new();
+
+    catalog.insert("severity.error", "<E&>");
+    set_active_catalog(Some(Rc::new(catalog)));
+
+    let result = writer.write_report(&msgs, &mut out);
+
+    set_active_catalog(None);
+    result.expect("Expected success");
+
+    let text = String::from_utf8(out).expect("Expected success");
+
+    assert!(text.contains("<p class=\"summary\">1 &lt;E&amp;&gt;</p>"));
+    assert!(text.contains(">&lt;E&amp;&gt;</span>"));
+    assert!(text.contains(&format!("<h2>{}</h2>",
+                                   escape_html(&cargo.display_name()))));
+    assert!(!text.contains(&format!("<h2>{}</h2>",
+                                    escape_html(&cargo.to_string()))))
+}
@@ -0,0 +1,397 @@
+use compiler_tools::diagnostic::Diagnostic;
+use compiler_tools::files::FileOffsets;
+use compiler_tools::files::FileKind;
+use compiler_tools::files::Filenames;
+#[cfg(feature = "golden")]
+use compiler_tools::golden::GoldenTest;
+use compiler_tools::lines::LineOffsets;
+use compiler_tools::lines::Offset;
+use compiler_tools::messages::Message;
+use compiler_tools::messages::MessageFullWriter;
+use compiler_tools::messages::MessageGithubWriter;
+use compiler_tools::messages::MessageGnuWriter;
+use compiler_tools::messages::MessageMsvcWriter;
+use compiler_tools::messages::MessagePositions;
+use compiler_tools::messages::MessageWriter;
+use compiler_tools::messages::Severity;
+use compiler_tools::messages::escape_github_data;
+use compiler_tools::messages::escape_github_property;
+use compiler_tools::position::FilePosition;
+use compiler_tools::position::OffsetPosition;
+use compiler_tools::sources::Sources;
+use std::path::Path;
+use termcolor::NoColor;
+
+struct TestMessage<'a> {
+    positions: Vec<(Option<&'static str>, FilePosition<'a>, Severity)>
+}
+
+struct CodedMessage<'a> {
+    positions: Vec<(Option<&'static str>, FilePosition<'a>, Severity)>
+}
+
+impl Message for TestMessage<'_> {
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn brief(&self) -> &str {
+        "test message"
+    }
+
+    fn detail(&self) -> &str {
+        "test detail"
+    }
+}
+
+impl<'a> MessagePositions<FilePosition<'a>> for TestMessage<'a> {
+    fn positions(&self) -> &[(Option<&str>, FilePosition<'a>, Severity)] {
+        &self.positions
+    }
+}
+
+impl Message for CodedMessage<'_> {
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn brief(&self) -> &str {
+        "unused variable"
+    }
+
+    fn detail(&self) -> &str {
+        ""
+    }
+
+    fn code(&self) -> Option<&str> {
+        Some("W0101")
+    }
+}
+
+impl<'a> MessagePositions<FilePosition<'a>> for CodedMessage<'a> {
+    fn positions(&self) -> &[(Option<&str>, FilePosition<'a>, Severity)] {
+        &self.positions
+    }
+}
+
+#[test]
+fn test_full_writer_fallback() {
+    let path = Path::new("./Cargo.toml");
+    let mut filenames = Filenames::new();
+    let filename = filenames.filename(path).expect("Expected success");
+    let mut line_offsets = LineOffsets::new();
+
+    line_offsets.push_line(0);
+    line_offsets.push_line(3);
+
+    let file_offsets = FileOffsets::new(filename, line_offsets);
+    let mut srcs = Sources::new();
+    let src = srcs.add_src(filename).expect("Expected some");
+
+    src.push_line(0, String::from("ab"));
+
+    let stale = FilePosition::Portion {
+        file_offsets: &file_offsets,
+        offset: OffsetPosition::Point { point: Offset::from(4) }
+    };
+    let expected = format!("  here {}\n", stale);
+    let msg = TestMessage { positions: vec![(Some("here"), stale,
+                                              Severity::Error)] };
+    let writer = MessageFullWriter::new(&srcs);
+    let mut out = NoColor::new(Vec::new());
+
+    writer.write_msg(&msg, &mut out).expect("Expected success");
+
+    let text = String::from_utf8(out.into_inner()).expect("Expected success");
+
+    assert!(text.contains(&expected));
+    assert!(text.ends_with("test detail\n"))
+}
+
+#[test]
+fn test_full_writer_context_lines() {
+    let mut filenames = Filenames::new();
+    let filename = filenames.stdin();
+    let mut line_offsets = LineOffsets::new();
+    let mut srcs = Sources::new();
+
+    for start in vec![0, 2, 4, 7, 10, 13, 16, 19, 21] {
+        line_offsets.push_line(start);
+    }
+
+    srcs.add_str(filename, "a\nb\nc1\nc2\nc3\nc4\nc5\nd\ne\n")
+        .expect("Expected some");
+
+    let file_offsets = FileOffsets::new(filename, line_offsets);
+    let pos = FilePosition::Portion {
+        file_offsets: &file_offsets,
+        offset: OffsetPosition::Span { start: Offset::from(4),
+                                       len: Offset::from(14) }
+    };
+    let msg = TestMessage { positions: vec![(None, pos, Severity::Error)] };
+    let mut writer = MessageFullWriter::new(&srcs);
+    let mut out = NoColor::new(Vec::new());
+
+    writer.set_context_lines(1, 1);
+    writer.set_elision(2, 1);
+    writer.write_msg(&msg, &mut out).expect("Expected success");
+
+    let text = String::from_utf8(out.into_inner()).expect("Expected success");
+
+    assert_eq!((1, 1), writer.context_lines());
+    assert_eq!((2, 1), writer.elision());
+    assert!(text.ends_with(":\nb\nc1\n^^\nc2\n...\nc4\nc5\n^^\nd\n\
+                            test detail\n"))
+}
+
+#[test]
+fn test_full_writer_render() {
+    let mut filenames = Filenames::new();
+    let filename = filenames.stdin();
+    let mut line_offsets = LineOffsets::new();
+    let mut srcs = Sources::new();
+
+    line_offsets.push_line(0);
+    srcs.add_str(filename, "\tx = \u{202e}y;\n").expect("Expected some");
+
+    let file_offsets = FileOffsets::new(filename, line_offsets);
+    let pos = FilePosition::Portion {
+        file_offsets: &file_offsets,
+        offset: OffsetPosition::Span { start: Offset::from(5),
+                                       len: Offset::from(4) }
+    };
+    let msg = TestMessage { positions: vec![(None, pos, Severity::Error)] };
+    let mut writer = MessageFullWriter::new(&srcs);
+    let mut out = NoColor::new(Vec::new());
+
+    writer.set_tab_width(2);
+    writer.write_msg(&msg, &mut out).expect("Expected success");
+
+    let text = String::from_utf8(out.into_inner()).expect("Expected success");
+
+    assert_eq!(2, writer.renderer().tab_width());
+    assert!(text.ends_with(":\n  x = <U+202E>y;\n      ^^^^^^^^^\n\
+                            test detail\n"))
+}
+
+#[test]
+fn test_gnu_writer() {
+    let mut filenames = Filenames::new();
+    let filename = filenames.virtual_filename(FileKind::Memory, "a.x");
+    let other = filenames.stdin();
+    let mut line_offsets = LineOffsets::new();
+
+    line_offsets.push_line(0);
+    line_offsets.push_line(5);
+
+    let file_offsets = FileOffsets::new(filename, line_offsets);
+    let span = FilePosition::Portion {
+        file_offsets: &file_offsets,
+        offset: OffsetPosition::Span { start: Offset::from(7),
+                                       len: Offset::from(3) }
+    };
+    let point = FilePosition::Portion {
+        file_offsets: &file_offsets,
+        offset: OffsetPosition::Point { point: Offset::from(1) }
+    };
+    let msg = TestMessage {
+        positions: vec![(Some("here"), span, Severity::Error),
+                        (Some("defined here"), point, Severity::Info),
+                        (None, FilePosition::File { filename: other },
+                         Severity::Lint)]
+    };
+    let mut out = NoColor::new(Vec::new());
+
+    MessageGnuWriter.write_msg(&msg, &mut out).expect("Expected success");
+
+    let text = String::from_utf8(out.into_inner()).expect("Expected success");
+
+    assert_eq!("<memory:a.x>:3:3: error: test message: here\n\
+                <memory:a.x>:2:2: info: defined here\n\
+                <stdin>: lint warning: test message\n",
+               text)
+}
+
+#[test]
+fn test_gnu_writer_no_positions() {
+    let msg = TestMessage { positions: vec![] };
+    let mut out = NoColor::new(Vec::new());
+
+    MessageGnuWriter.write_msg(&msg, &mut out).expect("Expected success");
+
+    let text = String::from_utf8(out.into_inner()).expect("Expected success");
+
+    assert_eq!("error: test message\n", text)
+}
+
+#[test]
+fn test_one_line_writers_multiline_text() {
+    let mut filenames = Filenames::new();
+    let filename = filenames.virtual_filename(FileKind::Memory, "a.x");
+    let pos = FilePosition::File { filename };
+    let msg = Diagnostic::error("bad\nthing")
+        .with_code("E1")
+        .with_label(pos.clone(), "here\r\nand there")
+        .with_secondary(pos, "see\nalso");
+    let mut gnu = NoColor::new(Vec::new());
+    let mut msvc = NoColor::new(Vec::new());
+
+    MessageGnuWriter.write_msg(&msg, &mut gnu).expect("Expected success");
+    MessageMsvcWriter.write_msg(&msg, &mut msvc).expect("Expected success");
+
+    let gnu = String::from_utf8(gnu.into_inner()).expect("Expected success");
+    let msvc = String::from_utf8(msvc.into_inner())
+        .expect("Expected success");
+
+    assert_eq!("<memory:a.x>: error: bad thing: here and there\n\
+                <memory:a.x>: info: see also\n",
+               gnu);
+    assert_eq!("<memory:a.x>: error E1: bad thing: here and there\n\
+                <memory:a.x>: info E1: see also\n",
+               msvc)
+}
+
+#[test]
+fn test_msvc_writer() {
+    let path = Path::new("./Cargo.toml");
+    let mut filenames = Filenames::new();
+    let filename = filenames.filename(path).expect("Expected success");
+    let name = filename.display_name();
+    let mut line_offsets = LineOffsets::new();
+
+    line_offsets.push_line(0);
+    line_offsets.push_line(5);
+
+    let file_offsets = FileOffsets::new(filename, line_offsets);
+    let span = FilePosition::Portion {
+        file_offsets: &file_offsets,
+        offset: OffsetPosition::Span { start: Offset::from(7),
+                                       len: Offset::from(3) }
+    };
+    let coded = CodedMessage { positions: vec![(None, span.clone(),
+                                                Severity::Warning)] };
+    let plain = TestMessage { positions: vec![(None, span,
+                                               Severity::Error)] };
+    let mut out = NoColor::new(Vec::new());
+
+    MessageMsvcWriter.write_msg(&coded, &mut out).expect("Expected success");
+    MessageMsvcWriter.write_msg(&plain, &mut out).expect("Expected success");
+
+    let text = String::from_utf8(out.into_inner()).expect("Expected success");
+
+    assert!(!name.starts_with('"'));
+    assert_eq!(format!("{}(3,3): warning W0101: unused variable\n\
+                        {}(3,3): error: test message\n",
+                       name, name),
+               text)
+}
+
+#[test]
+fn test_github_escapes() {
+    assert_eq!("100%25%0D%0Adone: a, b",
+               escape_github_data("100%\r\ndone: a, b"));
+    assert_eq!("a%3Ab%2Cc%250A", escape_github_property("a:b,c%0A"))
+}
+
+#[test]
+fn test_github_writer() {
+    let mut filenames = Filenames::new();
+    let filename = filenames.virtual_filename(FileKind::Memory, "a.x");
+    let other = filenames.stdin();
+    let mut line_offsets = LineOffsets::new();
+
+    line_offsets.push_line(0);
+    line_offsets.push_line(5);
+
+    let file_offsets = FileOffsets::new(filename, line_offsets);
+    let span = FilePosition::Portion {
+        file_offsets: &file_offsets,
+        offset: OffsetPosition::Span { start: Offset::from(2),
+                                       len: Offset::from(6) }
+    };
+    let msg = TestMessage {
+        positions: vec![(Some("50% done"), span, Severity::Error),
+                        (None, FilePosition::File { filename: other },
+                         Severity::Remark)]
+    };
+    let coded = CodedMessage { positions: vec![] };
+    let mut out = NoColor::new(Vec::new());
+
+    MessageGithubWriter.write_msg(&msg, &mut out).expect("Expected success");
+    MessageGithubWriter.write_msg(&coded, &mut out)
+        .expect("Expected success");
+
+    let text = String::from_utf8(out.into_inner()).expect("Expected success");
+
+    assert_eq!("::error file=<memory%3Aa.x>,line=2,col=3,endLine=3,\
+                endColumn=4::test message: 50%25 done\n\
+                ::notice file=<stdin>::test message\n\
+                ::warning::unused variable\n",
+               text)
+}
+
+#[cfg(feature = "golden")]
+#[test]
+fn test_full_writer_golden() {
+    let mut filenames = Filenames::new();
+    let filename = filenames.stdin();
+    let missing = filenames.virtual_filename(FileKind::Memory, "missing");
+    let mut line_offsets = LineOffsets::new();
+    let mut missing_offsets = LineOffsets::new();
+    let mut srcs = Sources::new();
+
+    for start in vec![0, 11, 18, 23, 28, 33, 38, 43, 48, 53, 58] {
+        line_offsets.push_line(start);
+    }
+
+    missing_offsets.push_line(0);
+    srcs.add_str(filename, "let x = 1;\nif x {\n  a;\n  b;\n  c;\n  d;\n  \
+                            e;\n  f;\n  g;\n  h;\n}\n")
+        .expect("Expected some");
+
+    let file_offsets = FileOffsets::new(filename, line_offsets);
+    let missing_offsets = FileOffsets::new(missing, missing_offsets);
+    let single = FilePosition::Portion {
+        file_offsets: &file_offsets,
+        offset: OffsetPosition::Span { start: Offset::from(4),
+                                       len: Offset::from(1) }
+    };
+    let multi = FilePosition::Portion {
+        file_offsets: &file_offsets,
+        offset: OffsetPosition::Span { start: Offset::from(8),
+                                       len: Offset::from(13) }
+    };
+    let elided = FilePosition::Portion {
+        file_offsets: &file_offsets,
+        offset: OffsetPosition::Span { start: Offset::from(16),
+                                       len: Offset::from(43) }
+    };
+    let unknown = FilePosition::Portion {
+        file_offsets: &missing_offsets,
+        offset: OffsetPosition::Point { point: Offset::from(0) }
+    };
+    let mut writer = MessageFullWriter::new(&srcs);
+    let golden = GoldenTest::new(Path::new(env!("CARGO_MANIFEST_DIR"))
+                                 .join("tests/golden"));
+
+    writer.set_context_lines(1, 1);
+    golden.assert_messages("full_single", &writer, &[TestMessage {
+        positions: vec![(Some("defined here"), single.clone(),
+                         Severity::Error)]
+    }]);
+    golden.assert_messages("full_multi", &writer, &[TestMessage {
+        positions: vec![(Some("in this block"), multi, Severity::Error),
+                        (Some("see also"), single.clone(), Severity::Info)]
+    }]);
+    golden.assert_messages("full_elided", &writer, &[TestMessage {
+        positions: vec![(Some("in this block"), elided, Severity::Error)]
+    }]);
+    golden.assert_messages("full_unlabeled", &writer, &[CodedMessage {
+        positions: vec![(None, single, Severity::Warning),
+                        (None, FilePosition::File { filename },
+                         Severity::Warning)]
+    }]);
+    golden.assert_messages("full_missing", &writer, &[TestMessage {
+        positions: vec![(Some("here"), unknown, Severity::Error)]
+    }]);
+}
@@ -1,7 +1,22 @@
 
+mod catalog;
+mod derive;
+mod diagnostic;
+mod encoding;
 mod files;
+mod fingerprint;
+#[cfg(feature = "golden")]
+mod golden;
+mod html;
 mod lines;
+mod messages;
 mod nondistinct;
 mod position;
+mod resolve;
+mod snippet;
 mod sources;
+mod strtab;
+mod suggest;
 mod symbol;
+mod ui;
+mod xml;
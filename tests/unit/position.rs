@@ -1,3 +1,4 @@
+use compiler_tools::files::FilenameDisplay;
 use compiler_tools::files::Filenames;
 use compiler_tools::files::FileOffsets;
 use compiler_tools::lines::LineOffsets;
@@ -14,8 +15,8 @@ fn test_file_position_file() {
     let canonical_path = path.canonicalize().expect("Expected success");
     let path_str = canonical_path.to_str().expect("Expected some");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
-    let pos = FilePosition::File { filename: filename };
+    let filename = filenames.filename(path).expect("Expected success");
+    let pos = FilePosition::File { filename };
 
     assert_eq!(format!("in \"{}\"", path_str), format!("{}", pos));
 }
@@ -26,7 +27,7 @@ fn test_file_position_portion_point() {
     let canonical_path = path.canonicalize().expect("Expected success");
     let path_str = canonical_path.to_str().expect("Expected some");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut line_offsets = LineOffsets::new();
 
     line_offsets.push_line(2);
@@ -47,7 +48,7 @@ fn test_file_position_portion_span_same_line() {
     let canonical_path = path.canonicalize().expect("Expected success");
     let path_str = canonical_path.to_str().expect("Expected some");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut line_offsets = LineOffsets::new();
 
     line_offsets.push_line(2);
@@ -69,7 +70,7 @@ fn test_file_position_portion_span_cross_lines() {
     let canonical_path = path.canonicalize().expect("Expected success");
     let path_str = canonical_path.to_str().expect("Expected some");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut line_offsets = LineOffsets::new();
 
     line_offsets.push_line(2);
@@ -94,8 +95,8 @@ fn test_basic_position_file() {
     let canonical_path = path.canonicalize().expect("Expected success");
     let path_str = canonical_path.to_str().expect("Expected some");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
-    let file_pos = FilePosition::File { filename: filename };
+    let filename = filenames.filename(path).expect("Expected success");
+    let file_pos = FilePosition::File { filename };
     let pos = BasicPosition::File { pos: file_pos };
 
     assert_eq!(format!("in \"{}\"", path_str), format!("{}", pos));
@@ -107,7 +108,7 @@ fn test_basic_position_file_portion_point() {
     let canonical_path = path.canonicalize().expect("Expected success");
     let path_str = canonical_path.to_str().expect("Expected some");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut line_offsets = LineOffsets::new();
 
     line_offsets.push_line(2);
@@ -129,7 +130,7 @@ fn test_basic_position_file_portion_span_same_line() {
     let canonical_path = path.canonicalize().expect("Expected success");
     let path_str = canonical_path.to_str().expect("Expected some");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut line_offsets = LineOffsets::new();
 
     line_offsets.push_line(2);
@@ -153,7 +154,7 @@ fn test_basic_position_file_portion_span_cross_lines() {
     let canonical_path = path.canonicalize().expect("Expected success");
     let path_str = canonical_path.to_str().expect("Expected some");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut line_offsets = LineOffsets::new();
 
     line_offsets.push_line(2);
@@ -217,3 +218,68 @@ fn test_basic_position_input_portion_span_cross_lines() {
 
     assert_eq!(format!("at input 2.2-3.2"), format!("{}", pos));
 }
+
+#[test]
+fn test_file_position_virtual() {
+    let mut filenames = Filenames::new();
+    let filename = filenames.stdin();
+    let mut line_offsets = LineOffsets::new();
+
+    line_offsets.push_line(0);
+    line_offsets.push_line(3);
+
+    let file_offsets = FileOffsets::new(filename, line_offsets);
+    let offset_pos = OffsetPosition::Point { point: Offset::from(4) };
+    let pos = FilePosition::Portion { file_offsets: &file_offsets,
+                                      offset: offset_pos };
+
+    assert_eq!("at <stdin>:3.2", format!("{}", pos));
+}
+
+#[test]
+fn test_file_position_relative() {
+    let mut filenames = Filenames::new();
+
+    filenames.set_base(Path::new(".")).expect("Expected success");
+    filenames.set_display_mode(FilenameDisplay::Relative);
+
+    let filename = filenames.filename(Path::new("./Cargo.toml"))
+        .expect("Expected success");
+    let mut line_offsets = LineOffsets::new();
+
+    line_offsets.push_line(0);
+    line_offsets.push_line(3);
+
+    let file_offsets = FileOffsets::new(filename, line_offsets);
+    let offset_pos = OffsetPosition::Point { point: Offset::from(4) };
+    let pos = FilePosition::Portion { file_offsets: &file_offsets,
+                                      offset: offset_pos };
+
+    assert_eq!("at \"Cargo.toml\":3.2", format!("{}", pos));
+}
+
+#[test]
+fn test_file_position_line_col() {
+    let path = Path::new("./Cargo.toml");
+    let mut filenames = Filenames::new();
+    let filename = filenames.filename(path).expect("Expected success");
+    let mut line_offsets = LineOffsets::new();
+
+    line_offsets.push_line(0);
+    line_offsets.push_line(3);
+
+    let file_offsets = FileOffsets::new(filename, line_offsets);
+    let span = FilePosition::Portion {
+        file_offsets: &file_offsets,
+        offset: OffsetPosition::Span { start: Offset::from(1),
+                                       len: Offset::from(4) }
+    };
+    let whole = FilePosition::File { filename };
+
+    assert_eq!(filename, span.filename());
+    assert_eq!(Some((2, 2)), span.start_line_col());
+    assert_eq!(Some((3, 3)), span.end_line_col());
+    assert_eq!(filename, whole.filename());
+    assert_eq!(None, whole.start_line_col());
+    assert_eq!(None, whole.end_line_col())
+}
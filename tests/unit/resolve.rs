@@ -0,0 +1,179 @@
+use compiler_tools::files::Filenames;
+use compiler_tools::messages::Message;
+use compiler_tools::messages::Severity;
+use compiler_tools::resolve::ModuleResolver;
+use compiler_tools::resolve::ResolveError;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+#[test]
+fn test_resolve_found() {
+    let mut filenames = Filenames::new();
+    let mut resolver = ModuleResolver::new();
+
+    resolver.add_root(Path::new("./src"));
+    resolver.add_root(Path::new("./tests"));
+    resolver.add_extension("x");
+    resolver.add_extension("rs");
+
+    let found = resolver.resolve(&mut filenames, &["lib"])
+        .expect("Expected success");
+    let expected = filenames.filename(Path::new("./src/lib.rs"))
+        .expect("Expected success");
+
+    assert_eq!(expected, found)
+}
+
+#[test]
+fn test_resolve_nested() {
+    let mut filenames = Filenames::new();
+    let mut resolver = ModuleResolver::new();
+
+    resolver.add_root(Path::new("./src"));
+    resolver.add_root(Path::new("./tests"));
+    resolver.add_extension("rs");
+
+    let found = resolver.resolve(&mut filenames, &["unit", "mod"])
+        .expect("Expected success");
+    let expected = filenames.filename(Path::new("./tests/unit/mod.rs"))
+        .expect("Expected success");
+
+    assert_eq!(expected, found)
+}
+
+#[test]
+fn test_resolve_not_found() {
+    let mut filenames = Filenames::new();
+    let mut resolver = ModuleResolver::new();
+
+    resolver.add_root(Path::new("./src"));
+    resolver.add_root(Path::new("./tests"));
+    resolver.add_extension("x");
+    resolver.add_extension("rs");
+
+    match resolver.resolve(&mut filenames, &["a", "b"]) {
+        Err(err @ ResolveError::NotFound { .. }) => {
+            assert_eq!(Severity::Error, err.severity());
+            assert_eq!("module `a.b` not found", err.brief());
+            assert_eq!("searched for:\n  ./src/a/b.x\n  ./src/a/b.rs\n  \
+                        ./tests/a/b.x\n  ./tests/a/b.rs",
+                       err.detail())
+        },
+        _ => panic!("Expected not found")
+    }
+}
+
+#[test]
+fn test_resolve_ambiguous() {
+    let mut filenames = Filenames::new();
+    let mut resolver = ModuleResolver::new();
+
+    resolver.add_root(Path::new("./src"));
+    resolver.add_root(Path::new("./tests/unit"));
+    resolver.add_extension("rs");
+    resolver.set_separator("::");
+
+    let src = filenames.filename(Path::new("./src/files.rs"))
+        .expect("Expected success");
+    let test = filenames.filename(Path::new("./tests/unit/files.rs"))
+        .expect("Expected success");
+
+    match resolver.resolve(&mut filenames, &["files"]) {
+        Err(err @ ResolveError::Ambiguous { .. }) => {
+            assert_eq!("module `files` is ambiguous", err.brief());
+
+            if let ResolveError::Ambiguous { found, .. } = err {
+                assert_eq!(vec![src, test], found)
+            }
+        },
+        _ => panic!("Expected ambiguous")
+    }
+}
+
+#[test]
+fn test_resolve_negative_cache() {
+    let root = env::temp_dir().join(format!("compiler-tools-resolve-{}",
+                                            process::id()));
+    let path = root.join("cached.x");
+
+    fs::create_dir_all(&root).expect("Expected success");
+
+    let mut filenames = Filenames::new();
+    let mut resolver = ModuleResolver::new();
+
+    resolver.add_root(&root);
+    resolver.add_extension("x");
+
+    assert!(resolver.resolve(&mut filenames, &["cached"]).is_err());
+
+    fs::write(&path, "x").expect("Expected success");
+
+    assert!(resolver.resolve(&mut filenames, &["cached"]).is_err());
+
+    resolver.clear_cache();
+
+    let found = resolver.resolve(&mut filenames, &["cached"]);
+
+    fs::remove_dir_all(&root).expect("Expected success");
+
+    assert!(found.is_ok())
+}
+
+#[test]
+fn test_resolve_invalid_component() {
+    let mut filenames = Filenames::new();
+    let mut resolver = ModuleResolver::new();
+
+    resolver.add_root(Path::new("./src"));
+    resolver.add_extension("rs");
+
+    for module in &[&["..", "src", "lib"][..], &["/etc", "passwd"],
+                    &["unit/mod"], &["."], &[""]] {
+        match resolver.resolve(&mut filenames, module) {
+            Err(ResolveError::InvalidComponent { .. }) => (),
+            _ => panic!("Expected invalid component")
+        }
+    }
+}
+
+#[test]
+fn test_resolve_dotted_name() {
+    let root = env::temp_dir().join(format!("compiler-tools-dotted-{}",
+                                            process::id()));
+
+    fs::create_dir_all(&root).expect("Expected success");
+    fs::write(root.join("a.b.x"), "x").expect("Expected success");
+
+    let mut filenames = Filenames::new();
+    let mut resolver = ModuleResolver::new();
+
+    resolver.add_root(&root);
+    resolver.add_extension("x");
+
+    let found = resolver.resolve(&mut filenames, &["a.b"]);
+    let expected = filenames.filename(&root.join("a.b.x"));
+
+    fs::remove_dir_all(&root).expect("Expected success");
+
+    assert_eq!(expected.expect("Expected success"),
+               found.expect("Expected success"))
+}
+
+#[test]
+fn test_resolve_io_error() {
+    let mut filenames = Filenames::new();
+    let mut resolver = ModuleResolver::new();
+    let long = "a".repeat(1000);
+
+    resolver.add_root(Path::new("./src"));
+    resolver.add_extension("rs");
+
+    match resolver.resolve(&mut filenames, &[long.as_str()]) {
+        Err(err @ ResolveError::Io { .. }) =>
+            assert_eq!(format!("cannot access module `{}`", long),
+                       err.brief()),
+        _ => panic!("Expected I/O error")
+    }
+}
@@ -0,0 +1,65 @@
+use compiler_tools::snippet::SnippetRenderer;
+use compiler_tools::snippet::is_invisible;
+
+#[test]
+fn test_is_invisible() {
+    assert!(is_invisible('\r'));
+    assert!(is_invisible('\u{202e}'));
+    assert!(is_invisible('\u{2067}'));
+    assert!(is_invisible('\u{200b}'));
+    assert!(is_invisible('\u{feff}'));
+    assert!(!is_invisible('\t'));
+    assert!(!is_invisible('a'));
+    assert!(!is_invisible('\u{e9}'))
+}
+
+#[test]
+fn test_render_tabs() {
+    let renderer = SnippetRenderer::new();
+
+    assert_eq!((String::from("    a"), 5), renderer.render("\ta", 0));
+    assert_eq!((String::from("  a"), 5), renderer.render("\ta", 2));
+    assert_eq!((String::from("ab  c"), 5), renderer.render("ab\tc", 0))
+}
+
+#[test]
+fn test_render_tab_width() {
+    let mut renderer = SnippetRenderer::new();
+
+    renderer.set_tab_width(8);
+
+    assert_eq!(8, renderer.tab_width());
+    assert_eq!((String::from("ab      c"), 9), renderer.render("ab\tc", 0));
+
+    renderer.set_tab_width(0);
+
+    assert_eq!(1, renderer.tab_width());
+    assert_eq!((String::from("a b"), 3), renderer.render("a\tb", 0))
+}
+
+#[test]
+fn test_render_escapes() {
+    let renderer = SnippetRenderer::new();
+
+    assert_eq!((String::from("a<U+202E>b<U+000D>"), 18),
+               renderer.render("a\u{202e}b\r", 0));
+    assert_eq!((String::from("x<U+200B>"), 9),
+               renderer.render("x\u{200b}", 0))
+}
+
+#[test]
+fn test_render_wide() {
+    let renderer = SnippetRenderer::new();
+
+    assert_eq!(5, renderer.column("a\u{4e2d}\u{6587}", 0));
+    assert_eq!(2, renderer.column("e\u{301}x", 0))
+}
+
+#[test]
+fn test_underline() {
+    let renderer = SnippetRenderer::new();
+
+    assert_eq!("  ^^^", renderer.underline(2, 5));
+    assert_eq!("   ^", renderer.underline(3, 3));
+    assert_eq!("^", renderer.underline(0, 0))
+}
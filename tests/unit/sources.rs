@@ -1,15 +1,22 @@
+use compiler_tools::files::FileKind;
 use compiler_tools::files::Filenames;
+use compiler_tools::fingerprint::ContentHash;
 use compiler_tools::lines::Offset;
 use compiler_tools::position::OffsetPosition;
+use compiler_tools::sources::ContextError;
 use compiler_tools::sources::Sources;
 use compiler_tools::sources::SourceContext;
+use compiler_tools::sources::SourceLines;
+use std::env;
+use std::fs;
 use std::path::Path;
+use std::process;
 
 #[test]
 fn test_sources_point_begin() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -31,7 +38,7 @@ fn test_sources_point_begin() {
 fn test_sources_point_endline() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -53,7 +60,7 @@ fn test_sources_point_endline() {
 fn test_sources_point_endline_last() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -75,7 +82,7 @@ fn test_sources_point_endline_last() {
 fn test_sources_point_empty_line() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -98,7 +105,7 @@ fn test_sources_point_empty_line() {
 fn test_sources_point_middle() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -120,7 +127,7 @@ fn test_sources_point_middle() {
 fn test_sources_span_begin_len_1() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -143,7 +150,7 @@ fn test_sources_span_begin_len_1() {
 fn test_sources_span_endline_len_1() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -166,7 +173,7 @@ fn test_sources_span_endline_len_1() {
 fn test_sources_span_empty_line_len_1() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -189,7 +196,7 @@ fn test_sources_span_empty_line_len_1() {
 fn test_sources_span_middle_len_1() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -212,7 +219,7 @@ fn test_sources_span_middle_len_1() {
 fn test_sources_span_middle() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -235,7 +242,7 @@ fn test_sources_span_middle() {
 fn test_sources_span_start_line() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -258,7 +265,7 @@ fn test_sources_span_start_line() {
 fn test_sources_span_end_visible_line() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -281,7 +288,7 @@ fn test_sources_span_end_visible_line() {
 fn test_sources_span_end_whole_line() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -304,7 +311,7 @@ fn test_sources_span_end_whole_line() {
 fn test_sources_span_visible_line() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -327,7 +334,7 @@ fn test_sources_span_visible_line() {
 fn test_sources_span_whole_line() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -350,7 +357,7 @@ fn test_sources_span_whole_line() {
 fn test_sources_span_two_lines_middle() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -365,7 +372,8 @@ fn test_sources_span_two_lines_middle() {
     let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
 
     assert_eq!(SourceContext::Multiple { prefix: "b", first: "cde",
-                                         middle: &vec![], last: "fg",
+                                         middle: SourceLines::from(&vec![]),
+                                         last: "fg",
                                          suffix: "hi" },
                ctx)
 }
@@ -374,7 +382,7 @@ fn test_sources_span_two_lines_middle() {
 fn test_sources_span_two_lines_start_line() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -389,7 +397,8 @@ fn test_sources_span_two_lines_start_line() {
     let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
 
     assert_eq!(SourceContext::Multiple { prefix: "", first: "bcde",
-                                         middle: &vec![], last: "fg",
+                                         middle: SourceLines::from(&vec![]),
+                                         last: "fg",
                                          suffix: "hi" },
                ctx)
 }
@@ -398,7 +407,7 @@ fn test_sources_span_two_lines_start_line() {
 fn test_sources_span_two_lines_end_visible_line() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -413,7 +422,8 @@ fn test_sources_span_two_lines_end_visible_line() {
     let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
 
     assert_eq!(SourceContext::Multiple { prefix: "b", first: "cde",
-                                         middle: &vec![], last: "fghi",
+                                         middle: SourceLines::from(&vec![]),
+                                         last: "fghi",
                                          suffix: "" },
                ctx)
 }
@@ -422,7 +432,7 @@ fn test_sources_span_two_lines_end_visible_line() {
 fn test_sources_span_two_lines_end_whole_line() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -437,7 +447,8 @@ fn test_sources_span_two_lines_end_whole_line() {
     let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
 
     assert_eq!(SourceContext::Multiple { prefix: "b", first: "cde",
-                                         middle: &vec![], last: "fghi",
+                                         middle: SourceLines::from(&vec![]),
+                                         last: "fghi",
                                          suffix: "" },
                ctx)
 }
@@ -446,7 +457,7 @@ fn test_sources_span_two_lines_end_whole_line() {
 fn test_sources_span_two_lines_visible_line() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -461,7 +472,8 @@ fn test_sources_span_two_lines_visible_line() {
     let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
 
     assert_eq!(SourceContext::Multiple { prefix: "", first: "bcde",
-                                         middle: &vec![], last: "fghi",
+                                         middle: SourceLines::from(&vec![]),
+                                         last: "fghi",
                                          suffix: "" },
                ctx)
 }
@@ -470,7 +482,7 @@ fn test_sources_span_two_lines_visible_line() {
 fn test_sources_span_two_lines_whole_line() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -485,7 +497,8 @@ fn test_sources_span_two_lines_whole_line() {
     let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
 
     assert_eq!(SourceContext::Multiple { prefix: "", first: "bcde",
-                                         middle: &vec![], last: "fghi",
+                                         middle: SourceLines::from(&vec![]),
+                                         last: "fghi",
                                          suffix: "" },
                ctx)
 }
@@ -494,7 +507,7 @@ fn test_sources_span_two_lines_whole_line() {
 fn test_sources_span_three_lines_middle() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -509,7 +522,9 @@ fn test_sources_span_three_lines_middle() {
     let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
 
     assert_eq!(SourceContext::Multiple { prefix: "b", first: "cde",
-                                         middle: &vec![String::from("fghi")],
+                                         middle: SourceLines::from(
+                                             &vec![String::from("fghi")]
+                                         ),
                                          last: "j", suffix: "k" },
                ctx)
 }
@@ -518,7 +533,7 @@ fn test_sources_span_three_lines_middle() {
 fn test_sources_span_three_lines_start_line() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -533,7 +548,9 @@ fn test_sources_span_three_lines_start_line() {
     let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
 
     assert_eq!(SourceContext::Multiple { prefix: "", first: "bcde",
-                                         middle: &vec![String::from("fghi")],
+                                         middle: SourceLines::from(
+                                             &vec![String::from("fghi")]
+                                         ),
                                          last: "j", suffix: "k" },
                ctx)
 }
@@ -542,7 +559,7 @@ fn test_sources_span_three_lines_start_line() {
 fn test_sources_span_three_lines_end_visible_line() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -557,7 +574,9 @@ fn test_sources_span_three_lines_end_visible_line() {
     let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
 
     assert_eq!(SourceContext::Multiple { prefix: "b", first: "cde",
-                                         middle: &vec![String::from("fghi")],
+                                         middle: SourceLines::from(
+                                             &vec![String::from("fghi")]
+                                         ),
                                          last: "jk", suffix: "" },
                ctx)
 }
@@ -566,7 +585,7 @@ fn test_sources_span_three_lines_end_visible_line() {
 fn test_sources_span_three_lines_end_whole_line() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -582,7 +601,9 @@ fn test_sources_span_three_lines_end_whole_line() {
     let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
 
     assert_eq!(SourceContext::Multiple { prefix: "b", first: "cde",
-                                         middle: &vec![String::from("fghi")],
+                                         middle: SourceLines::from(
+                                             &vec![String::from("fghi")]
+                                         ),
                                          last: "jk", suffix: "" },
                ctx)
 }
@@ -591,7 +612,7 @@ fn test_sources_span_three_lines_end_whole_line() {
 fn test_sources_span_three_lines_visible_line() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -606,7 +627,9 @@ fn test_sources_span_three_lines_visible_line() {
     let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
 
     assert_eq!(SourceContext::Multiple { prefix: "", first: "bcde",
-                                         middle: &vec![String::from("fghi")],
+                                         middle: SourceLines::from(
+                                             &vec![String::from("fghi")]
+                                         ),
                                          last: "jk", suffix: "" },
                ctx)
 }
@@ -615,7 +638,7 @@ fn test_sources_span_three_lines_visible_line() {
 fn test_sources_span_three_lines_whole_line() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -631,7 +654,9 @@ fn test_sources_span_three_lines_whole_line() {
     let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
 
     assert_eq!(SourceContext::Multiple { prefix: "", first: "bcde",
-                                         middle: &vec![String::from("fghi")],
+                                         middle: SourceLines::from(
+                                             &vec![String::from("fghi")]
+                                         ),
                                          last: "jk", suffix: "" },
                ctx)
 }
@@ -640,7 +665,7 @@ fn test_sources_span_three_lines_whole_line() {
 fn test_sources_span_three_lines_start() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -655,7 +680,9 @@ fn test_sources_span_three_lines_start() {
     let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
 
     assert_eq!(SourceContext::Multiple { prefix: "", first: "a",
-                                         middle: &vec![String::from("")],
+                                         middle: SourceLines::from(
+                                             &vec![String::from("")]
+                                         ),
                                          last: "bc", suffix: "de" },
                ctx)
 }
@@ -664,7 +691,7 @@ fn test_sources_span_three_lines_start() {
 fn test_sources_span_three_lines_first_empty() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -679,7 +706,9 @@ fn test_sources_span_three_lines_first_empty() {
     let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
 
     assert_eq!(SourceContext::Multiple { prefix: "", first: "",
-                                         middle: &vec![String::from("bcde")],
+                                         middle: SourceLines::from(
+                                             &vec![String::from("bcde")]
+                                         ),
                                          last: "fg", suffix: "hi" },
                ctx)
 }
@@ -688,7 +717,7 @@ fn test_sources_span_three_lines_first_empty() {
 fn test_sources_span_three_lines_last_empty() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -704,7 +733,9 @@ fn test_sources_span_three_lines_last_empty() {
     let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
 
     assert_eq!(SourceContext::Multiple { prefix: "bc", first: "de",
-                                         middle: &vec![String::from("fghi")],
+                                         middle: SourceLines::from(
+                                             &vec![String::from("fghi")]
+                                         ),
                                          last: "", suffix: "" },
                ctx)
 }
@@ -713,7 +744,7 @@ fn test_sources_span_three_lines_last_empty() {
 fn test_sources_span_three_lines_first_last_empty() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -729,7 +760,9 @@ fn test_sources_span_three_lines_first_last_empty() {
     let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
 
     assert_eq!(SourceContext::Multiple { prefix: "", first: "",
-                                         middle: &vec![String::from("bcde")],
+                                         middle: SourceLines::from(
+                                             &vec![String::from("bcde")]
+                                         ),
                                          last: "", suffix: "" },
                ctx)
 }
@@ -738,7 +771,7 @@ fn test_sources_span_three_lines_first_last_empty() {
 fn test_sources_span_three_lines_all_empty() {
     let path = Path::new("./Cargo.toml");
     let mut filenames = Filenames::new();
-    let filename = filenames.filename(&path).expect("Expected success");
+    let filename = filenames.filename(path).expect("Expected success");
     let mut srcs = Sources::new();
     let src = srcs.add_src(filename).expect("Expected some");
 
@@ -753,7 +786,379 @@ fn test_sources_span_three_lines_all_empty() {
     let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
 
     assert_eq!(SourceContext::Multiple { prefix: "", first: "",
-                                         middle: &vec![String::from("")],
+                                         middle: SourceLines::from(
+                                             &vec![String::from("")]
+                                         ),
                                          last: "", suffix: "" },
                ctx)
 }
+
+#[test]
+fn test_sources_virtual() {
+    let mut filenames = Filenames::new();
+    let filename = filenames.virtual_filename(FileKind::Memory, "test.x");
+    let mut srcs = Sources::new();
+    let src = srcs.add_src(filename).expect("Expected some");
+
+    src.push_line(0, String::from("abc"));
+    src.push_line(4, String::from("def"));
+
+    let offset_pos = OffsetPosition::Point { point: Offset::from(5) };
+    let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
+
+    assert_eq!(SourceContext::Single { prefix: "d", selected: "e",
+                                       suffix: "f" },
+               ctx)
+}
+
+#[test]
+fn test_sources_add_str() {
+    let mut filenames = Filenames::new();
+    let filename = filenames.stdin();
+    let mut srcs = Sources::new();
+    let src = srcs.add_str(filename, "ab\r\n\ncd\n").expect("Expected some");
+
+    assert_eq!(src.lines(), [&"ab", &"", &"cd"][..]);
+    assert!(src.hash().is_some());
+    assert!(src.fingerprint().is_none());
+    assert!(srcs.add_str(filename, "").is_none());
+
+    let offset_pos = OffsetPosition::Point { point: Offset::from(5) };
+    let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
+
+    assert_eq!(SourceContext::Single { prefix: "", selected: "c",
+                                       suffix: "d" },
+               ctx)
+}
+
+#[test]
+fn test_sources_load() {
+    let path = Path::new("./Cargo.toml");
+    let content = fs::read_to_string(path).expect("Expected success");
+    let mut filenames = Filenames::new();
+    let filename = filenames.filename(path).expect("Expected success");
+    let mut srcs = Sources::new();
+    let src = srcs.load(filename).expect("Expected success");
+    let expected: Vec<&str> = content.lines().collect();
+
+    assert_eq!(src.lines(), expected[..]);
+    assert!(src.fingerprint().is_some());
+    assert!(srcs.changed().is_empty())
+}
+
+#[test]
+fn test_sources_load_virtual() {
+    let mut filenames = Filenames::new();
+    let filename = filenames.stdin();
+    let mut srcs = Sources::new();
+
+    assert!(srcs.load(filename).is_err())
+}
+
+#[test]
+fn test_sources_changed() {
+    let path = env::temp_dir().join(format!("compiler-tools-changed-{}.x",
+                                            process::id()));
+
+    fs::write(&path, "abc\n").expect("Expected success");
+
+    let mut filenames = Filenames::new();
+    let filename = filenames.filename(&path).expect("Expected success");
+    let stdin = filenames.stdin();
+    let mut srcs = Sources::new();
+
+    srcs.load(filename).expect("Expected success");
+    srcs.add_str(stdin, "abc\n");
+
+    assert!(srcs.changed().is_empty());
+
+    fs::write(&path, "abcd\n").expect("Expected success");
+
+    let changed = srcs.changed();
+
+    srcs.load(filename).expect("Expected success");
+
+    let reloaded = srcs.changed();
+
+    fs::remove_file(&path).expect("Expected success");
+
+    assert_eq!(vec![filename], changed);
+    assert!(reloaded.is_empty());
+    assert_eq!(vec![filename], srcs.changed())
+}
+
+#[test]
+fn test_sources_changed_sorted() {
+    let dir = env::temp_dir().join(format!("compiler-tools-sorted-{}",
+                                           process::id()));
+    let names = ["c.x", "a.x", "d.x", "b.x"];
+    let mut filenames = Filenames::new();
+    let mut srcs = Sources::new();
+
+    fs::create_dir_all(&dir).expect("Expected success");
+
+    for name in &names {
+        let path = dir.join(name);
+
+        fs::write(&path, "abc\n").expect("Expected success");
+
+        let filename = filenames.filename(&path).expect("Expected success");
+
+        srcs.load(filename).expect("Expected success");
+        fs::write(&path, "abcd\n").expect("Expected success");
+    }
+
+    let changed: Vec<String> = srcs.changed().iter()
+        .map(|filename| filename.to_string_lossy().into_owned())
+        .collect();
+
+    fs::remove_dir_all(&dir).expect("Expected success");
+
+    assert_eq!(4, changed.len());
+    assert!(changed.windows(2).all(|pair| pair[0] < pair[1]))
+}
+
+#[test]
+fn test_sources_hash_matches_fingerprint() {
+    let path = env::temp_dir().join(format!("compiler-tools-hash-{}.x",
+                                            process::id()));
+
+    fs::write(&path, "abc\n").expect("Expected success");
+
+    let mut filenames = Filenames::new();
+    let filename = filenames.filename(&path).expect("Expected success");
+    let mut srcs = Sources::new();
+    let src = srcs.load(filename).expect("Expected success");
+    let hash = src.hash().copied();
+    let fingerprint = src.fingerprint().map(|fingerprint| *fingerprint.hash());
+
+    fs::remove_file(&path).expect("Expected success");
+
+    assert_eq!(Some(ContentHash::of(b"abc\n")), hash);
+    assert_eq!(hash, fingerprint)
+}
+
+#[test]
+fn test_sources_load_mapped() {
+    let path = env::temp_dir().join(format!("compiler-tools-mapped-{}.x",
+                                            process::id()));
+
+    fs::write(&path, "a\r\n\nbcde\nfghi\njk").expect("Expected success");
+
+    let mut filenames = Filenames::new();
+    let filename = filenames.filename(&path).expect("Expected success");
+    let mut srcs = Sources::new();
+    // SAFETY: nothing modifies the file while it is mapped.
+    let src = unsafe { srcs.load_mapped(filename) }
+        .expect("Expected success");
+    let is_mapped = src.is_mapped();
+    let lines: Vec<String> = src.lines().iter().map(String::from).collect();
+    let has_fingerprint = src.fingerprint().is_some();
+    let offset_pos = OffsetPosition::Span { start: Offset::from(5),
+                                            len: Offset::from(10) };
+    let ctx = srcs.get_ctx(filename, &offset_pos).expect("Expected some");
+
+    assert!(is_mapped);
+    assert!(has_fingerprint);
+    assert_eq!(vec!["a", "", "bcde", "fghi", "jk"], lines);
+    assert_eq!(SourceContext::Multiple { prefix: "b", first: "cde",
+                                         middle: SourceLines::from(
+                                             &vec![String::from("fghi")]
+                                         ),
+                                         last: "j", suffix: "k" },
+               ctx);
+    assert!(srcs.changed().is_empty());
+
+    fs::remove_file(&path).expect("Expected success");
+}
+
+#[test]
+fn test_sources_ctx_unknown_file() {
+    let path = Path::new("./Cargo.toml");
+    let mut filenames = Filenames::new();
+    let filename = filenames.filename(path).expect("Expected success");
+    let srcs = Sources::new();
+    let offset_pos = OffsetPosition::Point { point: Offset::from(0) };
+
+    assert_eq!(Err(ContextError::UnknownFile { filename }),
+               srcs.try_get_ctx(filename, &offset_pos));
+    assert!(srcs.get_ctx(filename, &offset_pos).is_none())
+}
+
+#[test]
+fn test_sources_ctx_eof_after_newline() {
+    let mut filenames = Filenames::new();
+    let filename = filenames.virtual_filename(FileKind::Memory, "eof");
+    let mut srcs = Sources::new();
+
+    srcs.add_str(filename, "a\nbc\n").expect("Expected some");
+
+    let eof = OffsetPosition::Point { point: Offset::from(5) };
+    let span = OffsetPosition::Span { start: Offset::from(3),
+                                      len: Offset::from(2) };
+    let past = OffsetPosition::Point { point: Offset::from(6) };
+
+    assert_eq!(SourceContext::Single { prefix: "bc", selected: "",
+                                       suffix: "" },
+               srcs.get_ctx(filename, &eof).expect("Expected some"));
+    assert_eq!(SourceContext::Single { prefix: "b", selected: "c",
+                                       suffix: "" },
+               srcs.get_ctx(filename, &span).expect("Expected some"));
+    assert_eq!(Err(ContextError::OutOfRange { filename,
+                                              offset: Offset::from(6) }),
+               srcs.try_get_ctx(filename, &past))
+}
+
+#[test]
+fn test_sources_ctx_eof_pushed_lines() {
+    let mut filenames = Filenames::new();
+    let filename = filenames.virtual_filename(FileKind::Memory, "pushed");
+    let mut srcs = Sources::new();
+    let src = srcs.add_src(filename).expect("Expected some");
+
+    src.push_line(0, String::from("ab"));
+    src.push_line(4, String::from("cd"));
+
+    let terminator = OffsetPosition::Point { point: Offset::from(3) };
+    let eof = OffsetPosition::Point { point: Offset::from(6) };
+    let past = OffsetPosition::Point { point: Offset::from(7) };
+
+    assert_eq!(SourceContext::Single { prefix: "ab", selected: "",
+                                       suffix: "" },
+               srcs.get_ctx(filename, &terminator).expect("Expected some"));
+    assert_eq!(SourceContext::Single { prefix: "cd", selected: "",
+                                       suffix: "" },
+               srcs.get_ctx(filename, &eof).expect("Expected some"));
+    assert_eq!(Err(ContextError::OutOfRange { filename,
+                                              offset: Offset::from(7) }),
+               srcs.try_get_ctx(filename, &past))
+}
+
+#[test]
+fn test_sources_ctx_out_of_range() {
+    let path = Path::new("./Cargo.toml");
+    let mut filenames = Filenames::new();
+    let filename = filenames.filename(path).expect("Expected success");
+    let mut srcs = Sources::new();
+    let src = srcs.add_src(filename).expect("Expected some");
+
+    src.push_line(4, String::from("ab"));
+    src.push_line(7, String::from("cd"));
+
+    let before = OffsetPosition::Point { point: Offset::from(2) };
+    let after = OffsetPosition::Point { point: Offset::from(10) };
+    let span = OffsetPosition::Span { start: Offset::from(5),
+                                      len: Offset::from(20) };
+    let overflow = OffsetPosition::Span { start: Offset::from(5),
+                                          len: Offset::from(usize::MAX) };
+
+    assert_eq!(Err(ContextError::OutOfRange { filename,
+                                              offset: Offset::from(2) }),
+               srcs.try_get_ctx(filename, &before));
+    assert_eq!(Err(ContextError::OutOfRange { filename,
+                                              offset: Offset::from(10) }),
+               srcs.try_get_ctx(filename, &after));
+    assert_eq!(Err(ContextError::OutOfRange { filename,
+                                              offset: Offset::from(25) }),
+               srcs.try_get_ctx(filename, &span));
+    assert_eq!(Err(ContextError::OutOfRange { filename,
+                                              offset: Offset::from(5) }),
+               srcs.try_get_ctx(filename, &overflow))
+}
+
+#[test]
+fn test_sources_ctx_not_char_boundary() {
+    let mut filenames = Filenames::new();
+    let filename = filenames.stdin();
+    let mut srcs = Sources::new();
+
+    srcs.add_str(filename, "a\u{e9}b\n").expect("Expected some");
+
+    let inside = OffsetPosition::Point { point: Offset::from(2) };
+    let span = OffsetPosition::Span { start: Offset::from(0),
+                                      len: Offset::from(2) };
+
+    assert_eq!(Err(ContextError::NotCharBoundary {
+                   filename, offset: Offset::from(2)
+               }),
+               srcs.try_get_ctx(filename, &inside));
+    assert_eq!(Err(ContextError::NotCharBoundary {
+                   filename, offset: Offset::from(2)
+               }),
+               srcs.try_get_ctx(filename, &span))
+}
+
+#[test]
+fn test_sources_ctx_multibyte_point() {
+    let mut filenames = Filenames::new();
+    let filename = filenames.stdin();
+    let mut srcs = Sources::new();
+
+    srcs.add_str(filename, "a\u{e9}b\n").expect("Expected some");
+
+    let offset_pos = OffsetPosition::Point { point: Offset::from(1) };
+
+    assert_eq!(Ok(SourceContext::Single { prefix: "a", selected: "\u{e9}",
+                                          suffix: "b" }),
+               srcs.try_get_ctx(filename, &offset_pos))
+}
+
+#[test]
+fn test_sources_ctx_terminator() {
+    let mut filenames = Filenames::new();
+    let filename = filenames.stdin();
+    let mut srcs = Sources::new();
+
+    srcs.add_str(filename, "ab\r\ncd\n").expect("Expected some");
+
+    let offset_pos = OffsetPosition::Point { point: Offset::from(3) };
+    let empty = OffsetPosition::Span { start: Offset::from(4),
+                                       len: Offset::from(0) };
+
+    assert_eq!(Ok(SourceContext::Single { prefix: "ab", selected: "",
+                                          suffix: "" }),
+               srcs.try_get_ctx(filename, &offset_pos));
+    assert_eq!(Ok(SourceContext::Single { prefix: "", selected: "",
+                                          suffix: "cd" }),
+               srcs.try_get_ctx(filename, &empty))
+}
+
+#[test]
+fn test_sources_excerpt() {
+    let mut filenames = Filenames::new();
+    let filename = filenames.stdin();
+    let mut srcs = Sources::new();
+
+    srcs.add_str(filename, "a\nb\ncd\nef\ng\nh\n").expect("Expected some");
+
+    let offset_pos = OffsetPosition::Span { start: Offset::from(5),
+                                            len: Offset::from(4) };
+    let excerpt = srcs.get_excerpt(filename, &offset_pos, 1, 5)
+        .expect("Expected some");
+
+    assert_eq!(excerpt.leading, [&"b"][..]);
+    assert_eq!(SourceContext::Multiple { prefix: "c", first: "d",
+                                         middle: SourceLines::from(&vec![]),
+                                         last: "ef", suffix: "" },
+               excerpt.context);
+    assert_eq!(excerpt.trailing, [&"g", &"h"][..])
+}
+
+#[test]
+fn test_sources_excerpt_first_line() {
+    let mut filenames = Filenames::new();
+    let filename = filenames.stdin();
+    let mut srcs = Sources::new();
+
+    srcs.add_str(filename, "ab\ncd\n").expect("Expected some");
+
+    let offset_pos = OffsetPosition::Point { point: Offset::from(1) };
+    let excerpt = srcs.get_excerpt(filename, &offset_pos, 3, 0)
+        .expect("Expected some");
+
+    assert!(excerpt.leading.is_empty());
+    assert_eq!(SourceContext::Single { prefix: "a", selected: "b",
+                                       suffix: "" },
+               excerpt.context);
+    assert!(excerpt.trailing.is_empty())
+}
@@ -0,0 +1,228 @@
+use compiler_tools::files::FileKind;
+use compiler_tools::files::Filenames;
+use compiler_tools::strtab::EncodedFilename;
+use compiler_tools::strtab::EncodedFilenames;
+use compiler_tools::strtab::EncodedSymbols;
+use compiler_tools::strtab::FilenameTable;
+use compiler_tools::strtab::SymbolTable;
+use compiler_tools::strtab::read_index;
+use compiler_tools::strtab::write_index;
+use compiler_tools::symbol::Symbols;
+use std::io::Cursor;
+use std::path::Path;
+
+#[test]
+fn test_index_roundtrip() {
+    let vals = [0, 1, 127, 128, 300, 1 << 20, usize::MAX];
+    let mut buf = Vec::new();
+
+    for val in &vals {
+        write_index(&mut buf, *val).expect("Expected success");
+    }
+
+    let mut input = Cursor::new(buf);
+
+    for val in &vals {
+        assert_eq!(*val, read_index(&mut input).expect("Expected success"));
+    }
+}
+
+#[test]
+fn test_index_overflow() {
+    let mut buf = vec![0xff; (usize::BITS / 7) as usize];
+
+    buf.push(0x7f);
+
+    assert!(read_index(&mut Cursor::new(buf)).is_err())
+}
+
+#[test]
+fn test_symbol_table_indices() {
+    let mut symbols = Symbols::new();
+    let a = symbols.symbol_str("a");
+    let b = symbols.symbol_str("b");
+    let mut table = SymbolTable::new();
+
+    assert_eq!(0, table.insert(a));
+    assert_eq!(1, table.insert(b));
+    assert_eq!(0, table.insert(a));
+    assert_eq!(Some(1), table.index(b));
+    assert_eq!(Some(a), table.get(0));
+    assert_eq!(None, table.get(2))
+}
+
+#[test]
+fn test_symbol_table_roundtrip() {
+    let mut symbols = Symbols::new();
+    let foo = symbols.symbol_str("foo");
+    let bar = symbols.symbol_str("bar");
+    let tmp = symbols.gensym("tmp");
+    let mut table = SymbolTable::new();
+    let mut payload = Vec::new();
+
+    for sym in &[foo, bar, tmp, foo, tmp] {
+        table.write_ref(*sym, &mut payload).expect("Expected success");
+    }
+
+    let mut buf = Vec::new();
+
+    table.write(&symbols, &mut buf).expect("Expected success");
+
+    let mut fresh = Symbols::new();
+    let other_tmp = fresh.gensym("tmp");
+    let read = SymbolTable::read(&mut Cursor::new(buf), &mut fresh)
+        .expect("Expected success");
+    let mut payload = Cursor::new(payload);
+    let syms: Vec<_> = (0 .. 5).map(|_| {
+        read.read_ref(&mut payload).expect("Expected success")
+    }).collect();
+
+    assert_eq!(fresh.get("foo"), Some(syms[0]));
+    assert_eq!(fresh.get("bar"), Some(syms[1]));
+    assert_eq!(syms[0], syms[3]);
+    assert_eq!(syms[2], syms[4]);
+    assert!(fresh.is_generated(syms[2]));
+    assert_ne!(other_tmp, syms[2]);
+    assert!(syms[2].to_string().starts_with("tmp%"))
+}
+
+#[test]
+fn test_symbol_table_bad_magic() {
+    let mut symbols = Symbols::new();
+
+    assert!(SymbolTable::read(&mut Cursor::new(b"CTFT\x01\x00"),
+                              &mut symbols).is_err())
+}
+
+#[test]
+fn test_symbol_table_bad_ref() {
+    let table = SymbolTable::new();
+
+    assert!(table.read_ref(&mut Cursor::new([0])).is_err())
+}
+
+#[test]
+fn test_symbol_table_truncated() {
+    assert!(EncodedSymbols::read(&mut Cursor::new(b"CTST\x01\x01\x00\x05ab"))
+            .is_err())
+}
+
+#[test]
+fn test_filename_table_roundtrip() {
+    let mut filenames = Filenames::new();
+    let cargo = filenames.filename(Path::new("./Cargo.toml"))
+        .expect("Expected success");
+    let lib = filenames.filename(Path::new("./src/lib.rs"))
+        .expect("Expected success");
+    let mut table = FilenameTable::new();
+
+    table.insert(lib);
+    table.insert(cargo);
+
+    let mut buf = Vec::new();
+
+    table.write(&mut buf).expect("Expected success");
+
+    let mut fresh = Filenames::new();
+    let read = FilenameTable::read(&mut Cursor::new(buf), &mut fresh)
+        .expect("Expected success");
+    let fresh_cargo = fresh.filename(Path::new("./Cargo.toml"))
+        .expect("Expected success");
+
+    assert_eq!(2, read.len());
+    assert_eq!(Some(fresh_cargo), read.get(1));
+    assert_eq!(Some(1), read.index(fresh_cargo))
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_symbol_table_serde() {
+    let mut symbols = Symbols::new();
+    let foo = symbols.symbol_str("foo");
+    let tmp = symbols.gensym("tmp");
+    let mut table = SymbolTable::new();
+
+    table.insert(foo);
+    table.insert(tmp);
+
+    let json = serde_json::to_string(&table.encode(&symbols))
+        .expect("Expected success");
+
+    assert_eq!(r#"[{"text":"foo","generated":false},"#.to_string() +
+               r#"{"text":"tmp","generated":true}]"#,
+               json);
+
+    let encoded: EncodedSymbols = serde_json::from_str(&json)
+        .expect("Expected success");
+    let mut fresh = Symbols::new();
+    let read = encoded.decode(&mut fresh);
+
+    assert_eq!(fresh.get("foo"), read.get(0));
+    assert!(fresh.is_generated(read.get(1).expect("Expected some")))
+}
+
+#[test]
+fn test_filename_table_virtual() {
+    let mut filenames = Filenames::new();
+    let stdin = filenames.stdin();
+    let generated = filenames.virtual_filename(FileKind::Generated, "foo");
+    let mut table = FilenameTable::new();
+
+    table.insert(stdin);
+    table.insert(generated);
+
+    let mut buf = Vec::new();
+
+    table.write(&mut buf).expect("Expected success");
+
+    let mut fresh = Filenames::new();
+    let read = FilenameTable::read(&mut Cursor::new(buf), &mut fresh)
+        .expect("Expected success");
+
+    assert_eq!(Some(fresh.stdin()), read.get(0));
+    assert_eq!(Some(fresh.virtual_filename(FileKind::Generated, "foo")),
+               read.get(1))
+}
+
+#[test]
+fn test_filename_table_remap() {
+    let cwd = Path::new(".").canonicalize().expect("Expected success");
+    let mut filenames = Filenames::new();
+
+    filenames.add_remap(&cwd, Path::new("/src"));
+
+    let cargo = filenames.filename(Path::new("./Cargo.toml"))
+        .expect("Expected success");
+    let mut table = FilenameTable::new();
+
+    table.insert(cargo);
+
+    let encoded = table.encode().expect("Expected success");
+
+    assert_eq!("/src/Cargo.toml", encoded.entries[0].path);
+
+    let mut fresh = Filenames::new();
+
+    fresh.add_remap(&cwd, Path::new("/src"));
+
+    let read = encoded.decode(&mut fresh);
+    let fresh_cargo = fresh.filename(Path::new("./Cargo.toml"))
+        .expect("Expected success");
+
+    assert_eq!(Some(fresh_cargo), read.get(0))
+}
+
+#[test]
+fn test_filename_table_missing_file() {
+    let path = Path::new("/nonexistent/compiler-tools/a.x");
+    let encoded = EncodedFilenames {
+        entries: vec![EncodedFilename { kind: FileKind::Disk,
+                                        path: path.display().to_string() }]
+    };
+    let mut fresh = Filenames::new();
+    let read = encoded.decode(&mut fresh);
+    let filename = read.get(0).expect("Expected some");
+
+    assert_eq!(&filename, path);
+    assert_eq!(Some(filename), fresh.find(path))
+}
@@ -0,0 +1,114 @@
+use compiler_tools::messages::Message;
+use compiler_tools::messages::Severity;
+use compiler_tools::suggest::WithSuggestion;
+use compiler_tools::suggest::best_suggestion;
+use compiler_tools::suggest::edit_distance;
+use compiler_tools::suggest::edit_distance_ignore_case;
+use compiler_tools::suggest::suggestions;
+use compiler_tools::symbol::Symbols;
+
+struct Undefined;
+
+impl Message for Undefined {
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn brief(&self) -> &str {
+        "undefined variable"
+    }
+
+    fn detail(&self) -> &str {
+        ""
+    }
+}
+
+#[test]
+fn test_edit_distance_equal() {
+    assert_eq!(0, edit_distance("hello", "hello"))
+}
+
+#[test]
+fn test_edit_distance_empty() {
+    assert_eq!(5, edit_distance("", "hello"));
+    assert_eq!(5, edit_distance("hello", ""))
+}
+
+#[test]
+fn test_edit_distance_substitute() {
+    assert_eq!(1, edit_distance("hello", "hallo"))
+}
+
+#[test]
+fn test_edit_distance_insert_delete() {
+    assert_eq!(1, edit_distance("hello", "helo"));
+    assert_eq!(1, edit_distance("helo", "hello"))
+}
+
+#[test]
+fn test_edit_distance_transpose() {
+    assert_eq!(1, edit_distance("hello", "hlelo"))
+}
+
+#[test]
+fn test_edit_distance_case() {
+    assert_eq!(1, edit_distance("Hello", "hello"));
+    assert_eq!(0, edit_distance_ignore_case("Hello", "hello"))
+}
+
+#[test]
+fn test_suggestions_ranked() {
+    let mut symbols = Symbols::new();
+    let target = symbols.symbol_str("lenght");
+    let length = symbols.symbol_str("length");
+    let lengths = symbols.symbol_str("lengths");
+    let height = symbols.symbol_str("height");
+    let width = symbols.symbol_str("width");
+
+    let found: Vec<_> = suggestions(target, &symbols).iter()
+        .map(|suggestion| suggestion.symbol)
+        .collect();
+
+    assert_eq!(vec![length, height, lengths], found);
+    assert!(!found.contains(&target));
+    assert!(!found.contains(&width))
+}
+
+#[test]
+fn test_suggestions_case_first() {
+    let mut symbols = Symbols::new();
+    let target = symbols.symbol_str("foo");
+    let upper = symbols.symbol_str("Foo");
+
+    symbols.symbol_str("fob");
+
+    assert_eq!(Some(upper), best_suggestion(target, &symbols))
+}
+
+#[test]
+fn test_suggestions_none() {
+    let mut symbols = Symbols::new();
+    let target = symbols.symbol_str("x");
+
+    symbols.symbol_str("completely_different");
+
+    assert_eq!(None, best_suggestion(target, &symbols))
+}
+
+#[test]
+fn test_with_suggestion_detail() {
+    let mut symbols = Symbols::new();
+    let target = symbols.symbol_str("lenght");
+    let scope = vec![symbols.symbol_str("length"), symbols.symbol_str("x")];
+    let msg = WithSuggestion::suggest(Undefined, target, scope);
+
+    assert_eq!("undefined variable", msg.brief());
+    assert_eq!("help: did you mean `length`?", msg.detail())
+}
+
+#[test]
+fn test_with_suggestion_none() {
+    let msg = WithSuggestion::new(Undefined, None);
+
+    assert_eq!("", msg.detail())
+}
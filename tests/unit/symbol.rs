@@ -1,4 +1,8 @@
+use compiler_tools::symbol::NULL_SYM;
+use compiler_tools::symbol::Symbol;
 use compiler_tools::symbol::Symbols;
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
 
 #[test]
 fn test_symbol_equality() {
@@ -51,3 +55,120 @@ fn test_ord_str() {
     assert!(&sym < "hellob");
     assert!("hellob" > &sym)
 }
+
+#[test]
+fn test_partial_ord_agrees_with_ord() {
+    let mut gensym = Symbols::new();
+    let b = gensym.symbol_nonnull(&"b");
+    let a = gensym.symbol_nonnull(&"a");
+
+    assert!(a < b);
+    assert_eq!(Some(a.cmp(&b)), a.partial_cmp(&b))
+}
+
+#[test]
+fn test_get_absent() {
+    let mut gensym = Symbols::new();
+
+    gensym.symbol_nonnull(&"hello");
+
+    assert_eq!(None, gensym.get("world"));
+    assert!(!gensym.contains("world"));
+    assert_eq!(1, gensym.len())
+}
+
+#[test]
+fn test_get_present() {
+    let mut gensym = Symbols::new();
+    let sym = gensym.symbol_nonnull(&"hello");
+
+    assert_eq!(Some(sym), gensym.get("hello"));
+    assert!(gensym.contains("hello"))
+}
+
+#[test]
+fn test_get_empty() {
+    let gensym = Symbols::new();
+
+    assert_eq!(Some(NULL_SYM), gensym.get(""));
+    assert!(gensym.contains(""));
+    assert!(gensym.is_empty())
+}
+
+#[test]
+fn test_symbol_str_equality() {
+    let mut gensym = Symbols::new();
+    let a = gensym.symbol(&"hello");
+    let b = gensym.symbol_str("hello");
+    let c = gensym.symbol_str_nonnull("hello");
+
+    assert_eq!(a, b);
+    assert_eq!(a, c);
+    assert_eq!(1, gensym.len());
+    assert_eq!(NULL_SYM, gensym.symbol_str(""))
+}
+
+#[test]
+fn test_iter() {
+    let mut gensym = Symbols::new();
+    let a = gensym.symbol_str("a");
+    let b = gensym.symbol_str("b");
+    let c = gensym.symbol_str("c");
+
+    gensym.symbol_str("b");
+
+    let mut syms: Vec<Symbol> = gensym.iter().collect();
+
+    syms.sort_by_key(|sym| String::from(*sym));
+
+    assert_eq!(vec![a, b, c], syms)
+}
+
+#[test]
+fn test_gensym_display() {
+    let mut gensym = Symbols::new();
+    let a = gensym.gensym("tmp");
+    let b = gensym.gensym("tmp");
+
+    assert_eq!("tmp%0", a.to_string());
+    assert_eq!("tmp%1", b.to_string());
+    assert_ne!(a, b)
+}
+
+#[test]
+fn test_gensym_distinct() {
+    let mut gensym = Symbols::new();
+    let generated = gensym.gensym("tmp");
+    let interned = gensym.symbol_str("tmp%0");
+
+    assert_eq!(&generated, "tmp%0");
+    assert_ne!(generated, interned);
+    assert_eq!(Some(interned), gensym.get("tmp%0"))
+}
+
+#[test]
+fn test_gensym_ordered_set() {
+    let mut gensym = Symbols::new();
+    let generated = gensym.gensym("tmp");
+    let interned = gensym.symbol_str("tmp%0");
+    let set: BTreeSet<Symbol> = vec![generated, interned].into_iter()
+        .collect();
+
+    assert_ne!(Ordering::Equal, generated.cmp(&interned));
+    assert_eq!(2, set.len());
+    assert!(set.contains(&generated));
+    assert!(set.contains(&interned))
+}
+
+#[test]
+fn test_gensym_is_generated() {
+    let mut gensym = Symbols::new();
+    let generated = gensym.gensym("tmp");
+    let interned = gensym.symbol_str("tmp");
+
+    assert!(gensym.is_generated(generated));
+    assert!(!gensym.is_generated(interned));
+    assert!(!gensym.is_generated(NULL_SYM));
+    assert_eq!(1, gensym.len());
+    assert_eq!(1, gensym.generated_len())
+}
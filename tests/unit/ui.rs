@@ -0,0 +1,230 @@
+use compiler_tools::diagnostic::Diagnostic;
+use compiler_tools::files::FileKind;
+use compiler_tools::files::FileOffsets;
+use compiler_tools::files::Filenames;
+use compiler_tools::lines::LineOffsets;
+use compiler_tools::lines::Offset;
+use compiler_tools::messages::Severity;
+use compiler_tools::position::FilePosition;
+use compiler_tools::position::OffsetPosition;
+use compiler_tools::ui::Annotation;
+use compiler_tools::ui::Emitted;
+use compiler_tools::ui::UiCollector;
+use compiler_tools::ui::UiError;
+use compiler_tools::ui::UiReport;
+use compiler_tools::ui::UiTest;
+use std::path::Path;
+
+/// A toy compiler, which reports an error for each `bad`, a warning
+/// for each `odd`, and a lint without a position for `nolint`, ignoring
+/// comments starting with `//` or `#`.
+fn compile(text: &str, collector: &mut UiCollector) {
+    let mut filenames = Filenames::new();
+    let filename = filenames.virtual_filename(FileKind::Memory, "fixture");
+    let mut line_offsets = LineOffsets::new();
+    let mut start = 0;
+
+    for line in text.split_inclusive('\n') {
+        line_offsets.push_line(start);
+        start += line.len();
+    }
+
+    let file_offsets = FileOffsets::new(filename, line_offsets);
+    let mut start = 0;
+
+    for line in text.split_inclusive('\n') {
+        let code = &line[.. line.find(['/', '#'])
+                         .unwrap_or(line.len())];
+
+        for (word, severity) in &[("bad", Severity::Error),
+                                  ("odd", Severity::Warning)] {
+            for (idx, _) in code.match_indices(word) {
+                let pos = FilePosition::Portion {
+                    file_offsets: &file_offsets,
+                    offset: OffsetPosition::Span {
+                        start: Offset::from(start + idx),
+                        len: Offset::from(3)
+                    }
+                };
+                let msg = Diagnostic::new(*severity,
+                                          &format!("found `{}`", word))
+                    .with_label(pos, "here");
+
+                collector.emit(&msg)
+            }
+        }
+
+        if code.contains("nolint") {
+            let msg: Diagnostic<FilePosition> = Diagnostic::lint("no lint");
+
+            collector.emit(&msg)
+        }
+
+        start += line.len();
+    }
+}
+
+#[test]
+fn test_ui_parse() {
+    let ui = UiTest::new();
+    let annotations = ui.parse("a //~ ERROR  bad thing \n\
+                                b\n\
+                                //~^ WARNING\n\
+                                //~| LINT style\n\
+                                //~? INTERNAL\n")
+        .expect("Expected success");
+
+    assert_eq!(vec![
+        Annotation { source_line: 1, line: Some(1), severity: Severity::Error,
+                     text: String::from("bad thing") },
+        Annotation { source_line: 3, line: Some(2),
+                     severity: Severity::Warning, text: String::new() },
+        Annotation { source_line: 4, line: Some(2), severity: Severity::Lint,
+                     text: String::from("style") },
+        Annotation { source_line: 5, line: None,
+                     severity: Severity::Internal, text: String::new() }
+    ], annotations);
+}
+
+#[test]
+fn test_ui_parse_comment() {
+    let mut ui = UiTest::new();
+
+    ui.set_comment("--");
+
+    assert_eq!("--", ui.comment());
+
+    let annotations = ui.parse("x // not ~ this\n\
+                                y --~ REMARK r\n")
+        .expect("Expected success");
+
+    assert_eq!(1, annotations.len());
+    assert_eq!(Some(2), annotations[0].line);
+    assert_eq!(Severity::Remark, annotations[0].severity);
+}
+
+#[test]
+fn test_ui_parse_malformed() {
+    let ui = UiTest::new();
+
+    for text in &["//~ BAD text", "//~^ ERROR", "//~| ERROR", "//~"] {
+        match ui.parse(text) {
+            Err(UiError::Annotation { line }) => assert_eq!(1, line),
+            _ => panic!("Expected malformed annotation")
+        }
+    }
+}
+
+#[test]
+fn test_ui_run_str() {
+    let ui = UiTest::new();
+    let report = ui.run_str("a = bad; //~ ERROR found `bad`\n\
+                             b = odd;\n\
+                             //~^ WARNING odd\n\
+                             nolint //~? LINT no lint\n",
+                            compile)
+        .expect("Expected success");
+
+    assert!(report.is_ok());
+}
+
+#[test]
+fn test_ui_run_str_mismatch() {
+    let ui = UiTest::new();
+    let report = ui.run_str("a = bad;\n\
+                             b = ok; //~ WARNING odd\n\
+                             c = odd; //~ ERROR odd\n",
+                            compile)
+        .expect("Expected success");
+
+    assert!(!report.is_ok());
+    assert_eq!(UiReport {
+        unexpected: vec![
+            Emitted { line: Some(1), severity: Severity::Error,
+                      brief: String::from("found `bad`") },
+            Emitted { line: Some(3), severity: Severity::Warning,
+                      brief: String::from("found `odd`") }
+        ],
+        missing: vec![
+            Annotation { source_line: 2, line: Some(2),
+                         severity: Severity::Warning,
+                         text: String::from("odd") },
+            Annotation { source_line: 3, line: Some(3),
+                         severity: Severity::Error,
+                         text: String::from("odd") }
+        ]
+    }, report);
+    assert_eq!("line 1: unexpected error: found `bad`\n\
+                line 3: unexpected warning: found `odd`\n\
+                line 2: missing warning: odd (annotated on line 2)\n\
+                line 3: missing error: odd (annotated on line 3)\n",
+               report.to_string());
+}
+
+#[test]
+fn test_ui_check_loose_annotation() {
+    let ui = UiTest::new();
+    let annotations = ui.parse("a //~ ERROR
+                                //~| ERROR found `bad`
+")
+        .expect("Expected success");
+    let emitted = vec![
+        Emitted { line: Some(1), severity: Severity::Error,
+                  brief: String::from("found `bad`") },
+        Emitted { line: Some(1), severity: Severity::Error,
+                  brief: String::from("found `odd`") }
+    ];
+
+    assert_eq!(UiReport::default(), ui.check(&annotations, &emitted));
+    assert!(annotations[0].matches(&emitted[1]));
+    assert!(!annotations[1].matches(&emitted[1]))
+}
+
+#[test]
+fn test_ui_parse_string_literal() {
+    let ui = UiTest::new();
+    let annotations = ui.parse("s = \"//~ ERROR quoted\";\n")
+        .expect("Expected success");
+
+    assert_eq!(1, annotations.len());
+    assert_eq!("quoted\";", annotations[0].text)
+}
+
+#[test]
+fn test_ui_min_severity() {
+    let mut ui = UiTest::new();
+
+    ui.set_min_severity(Severity::Error);
+
+    assert_eq!(Severity::Error, ui.min_severity());
+
+    let report = ui.run_str("a = odd;\nnolint\n", compile)
+        .expect("Expected success");
+
+    assert!(report.is_ok());
+
+    let report = ui.run_str("a = ok; //~ WARNING odd\n", compile)
+        .expect("Expected success");
+
+    assert_eq!(1, report.missing.len());
+}
+
+#[test]
+fn test_ui_run_file() {
+    let mut ui = UiTest::new();
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/ui/basic.x");
+
+    ui.set_comment("#");
+    ui.assert_run(&path, |file, text, collector| {
+        assert_eq!(path, file);
+
+        compile(text, collector)
+    });
+
+    match ui.run(path.join("missing"), |_, text, collector| {
+        compile(text, collector)
+    }) {
+        Err(UiError::Io(_)) => (),
+        _ => panic!("Expected I/O error")
+    }
+}
@@ -0,0 +1,123 @@
+use compiler_tools::files::FileOffsets;
+use compiler_tools::files::FileKind;
+use compiler_tools::files::Filenames;
+use compiler_tools::lines::LineOffsets;
+use compiler_tools::lines::Offset;
+use compiler_tools::messages::Message;
+use compiler_tools::messages::MessagePositions;
+use compiler_tools::messages::Severity;
+use compiler_tools::position::FilePosition;
+use compiler_tools::position::OffsetPosition;
+use compiler_tools::xml::CheckstyleWriter;
+use compiler_tools::xml::JUnitWriter;
+use compiler_tools::xml::escape_xml;
+
+struct TestMessage<'a> {
+    severity: Severity,
+    brief: &'static str,
+    code: Option<&'static str>,
+    positions: Vec<(Option<&'static str>, FilePosition<'a>, Severity)>
+}
+
+impl Message for TestMessage<'_> {
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn brief(&self) -> &str {
+        self.brief
+    }
+
+    fn detail(&self) -> &str {
+        ""
+    }
+
+    fn code(&self) -> Option<&str> {
+        self.code
+    }
+}
+
+impl<'a> MessagePositions<FilePosition<'a>> for TestMessage<'a> {
+    fn positions(&self) -> &[(Option<&str>, FilePosition<'a>, Severity)] {
+        &self.positions
+    }
+}
+
+#[test]
+fn test_escape_xml() {
+    assert_eq!("&lt;a b=&quot;&amp;&apos;&gt;&#10;\u{fffd}",
+               escape_xml("<a b=\"&'>\n\u{1}"))
+}
+
+#[test]
+fn test_xml_reports() {
+    let mut filenames = Filenames::new();
+    let first = filenames.virtual_filename(FileKind::Memory, "first");
+    let mut line_offsets = LineOffsets::new();
+
+    line_offsets.push_line(0);
+    line_offsets.push_line(10);
+
+    let file_offsets = FileOffsets::new(first, line_offsets);
+    let span = FilePosition::Portion {
+        file_offsets: &file_offsets,
+        offset: OffsetPosition::Span { start: Offset::from(12),
+                                       len: Offset::from(3) }
+    };
+    let msgs = vec![
+        TestMessage { severity: Severity::Error, brief: "bad <y>",
+                      code: Some("E1"),
+                      positions: vec![(None, span.clone(),
+                                       Severity::Error)] },
+        TestMessage { severity: Severity::Lint, brief: "style",
+                      code: None,
+                      positions: vec![(None, span, Severity::Lint)] },
+        TestMessage { severity: Severity::Warning, brief: "no position",
+                      code: None, positions: vec![] }
+    ];
+    let mut out = Vec::new();
+
+    CheckstyleWriter.write_report(&msgs, &mut out)
+        .expect("Expected success");
+
+    let checkstyle = String::from_utf8(out).expect("Expected success");
+
+    assert_eq!(checkstyle,
+               "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                <checkstyle version=\"4.3\">\n\
+                <file name=\"&lt;memory:first&gt;\">\n\
+                <error line=\"3\" column=\"3\" severity=\"error\" \
+                message=\"bad &lt;y&gt;\" source=\"E1\"/>\n\
+                <error line=\"3\" column=\"3\" severity=\"warning\" \
+                message=\"style\"/>\n\
+                </file>\n\
+                <file name=\"\">\n\
+                <error severity=\"warning\" message=\"no position\"/>\n\
+                </file>\n\
+                </checkstyle>\n");
+
+    let mut out = Vec::new();
+
+    JUnitWriter::new("lint").write_report(&msgs, &mut out)
+        .expect("Expected success");
+
+    let junit = String::from_utf8(out).expect("Expected success");
+
+    assert_eq!(junit,
+               "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                <testsuites name=\"lint\" tests=\"2\" failures=\"1\">\n\
+                <testsuite name=\"lint\" tests=\"2\" failures=\"1\">\n\
+                <testcase name=\"&lt;memory:first&gt;\" \
+                classname=\"lint\">\n\
+                <failure message=\"bad &lt;y&gt;\" type=\"error\">\
+                &lt;memory:first&gt;:3:3: error: bad &lt;y&gt;&#10;\
+                </failure>\n\
+                <system-out>&lt;memory:first&gt;:3:3: lint warning: \
+                style&#10;</system-out>\n\
+                </testcase>\n\
+                <testcase name=\"\" classname=\"lint\">\n\
+                <system-out>warning: no position&#10;</system-out>\n\
+                </testcase>\n\
+                </testsuite>\n\
+                </testsuites>\n");
+}